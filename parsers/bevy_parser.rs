@@ -1,7 +1,9 @@
 //! EditorLevel2D Parser pour Bevy
 //! 
 //! Module Rust pour charger et afficher les niveaux créés avec EditorLevel2D
-//! dans vos jeux Bevy.
+//! dans vos jeux Bevy. Les types du format (`Level`, `Layer`, `TileData`,
+//! `Project`...) viennent de la bibliothèque `editor_level`, la même que
+//! celle de l'éditeur : le parser ne peut pas diverger du format.
 //! 
 //! # Installation
 //! 
@@ -9,8 +11,7 @@
 //! ```toml
 //! [dependencies]
 //! bevy = "0.14"
-//! serde = { version = "1.0", features = ["derive"] }
-//! serde_json = "1.0"
+//! editor_level = { path = "../editor_level", default-features = false }
 //! ```
 //! 
 //! # Utilisation
@@ -25,103 +26,91 @@
 //!         "mon_niveau.editorproj",
 //!         &asset_server,
 //!     ));
+//!
+//!     // Ou un niveau précis d'un projet qui en contient plusieurs,
+//!     // placé selon sa position sur la carte du monde
+//!     match EditorLevelBundle::from_file_level("mon_monde.editorproj", "Grotte", &asset_server) {
+//!         Ok(bundle) => {
+//!             commands.spawn(bundle);
+//!         }
+//!         Err(e) => error!("{}", e),
+//!     }
 //! }
 //! ```
 
 use bevy::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
-/// Référence vers un tile (couleur ou texture)
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum TileData {
-    Color([u8; 3]),
-    Texture { tileset_id: usize, tile_index: u32 },
-}
+pub use editor_level::{
+    AnimationFrame, CollisionShape, Layer, LayerKind, Level, LevelObject, LogicGrid, LogicValue, Project, TerrainKind,
+    TerrainSet, TileData, TileFlags, TileMetadata, TileProperty, TilesetMetadata,
+};
 
-/// Représente un calque du niveau
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Layer {
-    pub name: String,
-    pub visible: bool,
-    #[serde(
-        serialize_with = "serialize_tiles",
-        deserialize_with = "deserialize_tiles"
-    )]
-    pub tiles: HashMap<(i32, i32), TileData>,
+/// Projet complet avec niveaux et tilesets (nom historique du parser)
+pub type EditorProject = Project;
+
+/// Dossier des assets Bevy (`AssetPlugin::file_path`, "assets" par défaut)
+pub const ASSETS_DIR: &str = "assets";
+
+/// Chemin d'un fichier relatif au dossier des assets, tel qu'attendu par l'`AssetServer`.
+/// Les chemins des tilesets sont résolus par rapport au fichier .editorproj au chargement :
+/// un projet dans `assets/levels/` qui référence `../tilesets/sol.png` donne `tilesets/sol.png`.
+fn asset_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    let absolute_assets_dir = std::env::current_dir().unwrap_or_default().join(ASSETS_DIR);
+    path.strip_prefix(ASSETS_DIR)
+        .or_else(|_| path.strip_prefix(&absolute_assets_dir))
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| path.to_path_buf())
 }
 
-/// Structure du niveau
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Level {
-    pub name: String,
-    pub width: i32,
-    pub height: i32,
-    pub tile_size: i32,
-    pub layers: Vec<Layer>,
+/// Component pour identifier un niveau chargé
+#[derive(Component)]
+pub struct EditorLevel {
+    pub project: EditorProject,
+    pub level: usize,  // Index du niveau affiché dans `project.levels`
 }
 
-/// Information sur un tileset
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TilesetInfo {
-    pub name: String,
-    pub path: String,
-    pub tiles_per_row: u32,
-    pub total_tiles: u32,
+impl EditorLevel {
+    /// Niveau affiché
+    pub fn level(&self) -> &Level {
+        &self.project.levels[self.level]
+    }
 }
 
-/// Projet complet avec niveau et tilesets
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EditorProject {
-    pub level: Level,
-    #[serde(default)]
-    pub tilesets: HashMap<String, TilesetInfo>,
+/// Component d'un calque du niveau : parent de ses tiles et objets. Son `z` est
+/// l'index du calque ; `apply_parallax` le déplace selon la caméra.
+#[derive(Component, Debug, Clone)]
+pub struct EditorLayer {
+    pub index: usize,
+    pub offset: Vec2,    // Décalage en pixels (Y vers le haut)
+    pub parallax: Vec2,  // 1 = suit le niveau, 0 = fixe à l'écran
 }
 
-// Fonctions de sérialisation pour HashMap<(i32, i32), TileData>
-fn serialize_tiles<S>(
-    tiles: &HashMap<(i32, i32), TileData>,
-    serializer: S,
-) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    use serde::ser::SerializeMap;
-    let mut map = serializer.serialize_map(Some(tiles.len()))?;
-    for ((x, y), tile) in tiles {
-        let key = format!("{},{}", x, y);
-        map.serialize_entry(&key, tile)?;
-    }
-    map.end()
+/// Grille typée d'un calque de collision, posée sur son entité `EditorLayer`
+/// (aucun sprite n'est créé pour ses cases)
+#[derive(Component, Debug, Clone)]
+pub struct LogicLayer {
+    pub name: String,
+    pub tile_size: f32,
+    pub grid: LogicGrid,
 }
 
-fn deserialize_tiles<'de, D>(
-    deserializer: D,
-) -> Result<HashMap<(i32, i32), TileData>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let string_map: HashMap<String, TileData> = HashMap::deserialize(deserializer)?;
-    let mut tiles = HashMap::new();
-    
-    for (key, value) in string_map {
-        let parts: Vec<&str> = key.split(',').collect();
-        if parts.len() == 2 {
-            if let (Ok(x), Ok(y)) = (parts[0].parse(), parts[1].parse()) {
-                tiles.insert((x, y), value);
-            }
-        }
+impl LogicLayer {
+    /// Case qui contient un point local au niveau (Y vers le haut, comme les sprites)
+    pub fn cell_at(&self, local: Vec2) -> (i32, i32) {
+        (
+            (local.x / self.tile_size + 0.5).floor() as i32,
+            (-local.y / self.tile_size + 0.5).floor() as i32,
+        )
     }
-    
-    Ok(tiles)
-}
 
-/// Component pour identifier un niveau chargé
-#[derive(Component)]
-pub struct EditorLevel {
-    pub project: EditorProject,
+    /// La case sous un point local au niveau porte la valeur `name` ("solid"...)
+    pub fn is_at(&self, local: Vec2, name: &str) -> bool {
+        let (x, y) = self.cell_at(local);
+        self.grid.is(x, y, name)
+    }
 }
 
 /// Component pour identifier un tile du niveau
@@ -131,6 +120,30 @@ pub struct LevelTile {
     pub position: (i32, i32),
 }
 
+/// Component pour identifier un objet du niveau
+#[derive(Component)]
+pub struct EditorObject {
+    pub layer_index: usize,
+    pub object: LevelObject,
+}
+
+/// Propriétés d'un tile texturé (ex: "solid", "damage")
+#[derive(Component, Debug, Clone)]
+pub struct TileProperties(pub BTreeMap<String, TileProperty>);
+
+/// Formes de collision d'un tile texturé (pixels du tile, Y vers le bas)
+#[derive(Component, Debug, Clone)]
+pub struct TileCollision(pub Vec<CollisionShape>);
+
+/// Animation d'un tile texturé : `animate_tiles` fait avancer ses images
+#[derive(Component, Debug, Clone)]
+pub struct AnimatedTile {
+    pub metadata: TileMetadata,
+    pub columns: u32,
+    pub tile_size: Vec2,  // Taille d'un tile dans l'image du tileset
+    pub elapsed_ms: u64,
+}
+
 /// Bundle pour spawner un niveau complet
 #[derive(Bundle)]
 pub struct EditorLevelBundle {
@@ -143,13 +156,31 @@ pub struct EditorLevelBundle {
 }
 
 impl EditorLevelBundle {
-    /// Charge un niveau depuis un fichier .editorproj ou .json
+    /// Charge le premier niveau d'un fichier .editorproj ou .json
     pub fn from_file(path: impl AsRef<Path>, asset_server: &AssetServer) -> Self {
-        let project = EditorProject::load_from_file(path).expect("Impossible de charger le niveau");
-        
+        let project = Project::open(path).expect("Impossible de charger le niveau");
+        Self::from_project(project, 0)
+    }
+
+    /// Charge le niveau `name` d'un projet, placé à sa position sur la carte du monde.
+    /// Erreur si le fichier est illisible ou ne contient pas ce niveau.
+    pub fn from_file_level(path: impl AsRef<Path>, name: &str, asset_server: &AssetServer) -> Result<Self, String> {
+        let path = path.as_ref();
+        let project = Project::open(path).map_err(|e| format!("{} : {}", path.display(), e))?;
+        let index = project
+            .levels
+            .iter()
+            .position(|level| level.name == name)
+            .ok_or_else(|| format!("Aucun niveau '{}' dans {}", name, path.display()))?;
+        Ok(Self::from_project(project, index))
+    }
+
+    fn from_project(project: EditorProject, level: usize) -> Self {
+        // Y vers le haut dans Bevy : la position du monde (pixels, Y vers le bas) est inversée
+        let (x, y) = project.levels[level].world_position;
         Self {
-            level: EditorLevel { project },
-            transform: Transform::default(),
+            level: EditorLevel { project, level },
+            transform: Transform::from_xyz(x as f32, -y as f32, 0.0),
             global_transform: GlobalTransform::default(),
             visibility: Visibility::default(),
             inherited_visibility: InheritedVisibility::default(),
@@ -158,40 +189,6 @@ impl EditorLevelBundle {
     }
 }
 
-impl EditorProject {
-    /// Charge un projet depuis un fichier
-    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
-        let content = std::fs::read_to_string(path)?;
-        let project: EditorProject = serde_json::from_str(&content)?;
-        Ok(project)
-    }
-    
-    /// Retourne tous les tiles d'un calque
-    pub fn get_layer_tiles(&self, layer_name: &str) -> Option<&HashMap<(i32, i32), TileData>> {
-        self.level.layers
-            .iter()
-            .find(|l| l.name == layer_name)
-            .map(|l| &l.tiles)
-    }
-    
-    /// Trouve toutes les positions des tiles d'une couleur spécifique
-    pub fn find_tiles_by_color(&self, color: [u8; 3]) -> Vec<(i32, i32)> {
-        let mut positions = Vec::new();
-        
-        for layer in &self.level.layers {
-            for (&pos, &tile_data) in &layer.tiles {
-                if let TileData::Color(tile_color) = tile_data {
-                    if tile_color == color {
-                        positions.push(pos);
-                    }
-                }
-            }
-        }
-        
-        positions
-    }
-}
-
 /// Système pour spawner les tiles du niveau
 pub fn spawn_level_tiles(
     mut commands: Commands,
@@ -200,41 +197,82 @@ pub fn spawn_level_tiles(
 ) {
     for (entity, editor_level) in query.iter() {
         let project = &editor_level.project;
-        let level = &project.level;
+        let level = editor_level.level();
         let tile_size = level.tile_size as f32;
         
         // Charger les textures des tilesets
-        let mut tileset_handles: HashMap<String, Handle<Image>> = HashMap::new();
-        let project_dir = PathBuf::from("assets"); // Adapter selon votre structure
+        let mut tileset_handles: HashMap<usize, Handle<Image>> = HashMap::new();
         
-        for (tileset_id, tileset_info) in &project.tilesets {
-            let texture_path = project_dir.join(&tileset_info.path);
-            let handle = asset_server.load(texture_path.to_str().unwrap());
-            tileset_handles.insert(tileset_id.clone(), handle);
+        for tileset_info in &project.tilesets {
+            let handle = asset_server.load(asset_path(&tileset_info.path));
+            tileset_handles.insert(tileset_info.id, handle);
         }
         
         // Spawner les tiles de chaque calque
         for (layer_index, layer) in level.layers.iter().enumerate() {
-            if !layer.visible {
+            // Un calque masqué, directement ou par l'un de ses groupes, n'est pas créé
+            if !level.is_layer_visible(layer_index) {
                 continue;
             }
             
+            // Une entité par calque : l'ordre d'affichage (z) et le décalage s'y appliquent
+            let offset = Vec2::new(layer.offset.0, -layer.offset.1);
+            let layer_entity = commands
+                .spawn((
+                    SpatialBundle::from_transform(Transform::from_xyz(offset.x, offset.y, layer_index as f32)),
+                    EditorLayer {
+                        index: layer_index,
+                        offset,
+                        parallax: Vec2::new(layer.parallax.0, layer.parallax.1),
+                    },
+                ))
+                .id();
+            commands.entity(entity).add_child(layer_entity);
+            if layer.kind == LayerKind::Logic {
+                commands.entity(layer_entity).insert(LogicLayer {
+                    name: layer.name.clone(),
+                    tile_size,
+                    grid: layer.logic_grid(level.width, level.height),
+                });
+                continue;
+            }
+            let shade = |rgb: [u8; 3]| {
+                let [r, g, b, a] = layer.shade(rgb);
+                Color::srgba_u8(r, g, b, a)
+            };
+            
+            // Objets : une entité par objet, positionnée sur son coin haut-gauche
+            for object in &layer.objects {
+                let mut transform = Transform::from_xyz(object.x, -object.y, 0.0);
+                transform.rotate_z(-object.rotation.to_radians());
+                commands.entity(layer_entity).with_children(|parent| {
+                    parent.spawn((
+                        SpatialBundle::from_transform(transform),
+                        EditorObject {
+                            layer_index,
+                            object: object.clone(),
+                        },
+                    ));
+                });
+            }
+            
             for (&(x, y), &tile_data) in &layer.tiles {
                 let world_x = x as f32 * tile_size;
                 let world_y = -(y as f32 * tile_size); // Y inversé pour Bevy
                 
                 match tile_data {
+                    TileData::Empty | TileData::Value(_) => {}
                     TileData::Color(color) => {
                         // Spawner un sprite coloré
-                        commands.entity(entity).with_children(|parent| {
+                        commands.entity(layer_entity).with_children(|parent| {
                             parent.spawn((
                                 SpriteBundle {
                                     sprite: Sprite {
-                                        color: Color::srgb_u8(color[0], color[1], color[2]),
+                                        color: shade(color),
                                         custom_size: Some(Vec2::new(tile_size, tile_size)),
                                         ..default()
                                     },
-                                    transform: Transform::from_xyz(world_x, world_y, layer_index as f32),
+                                    transform: Transform::from_xyz(world_x, world_y, 0.0),
                                     ..default()
                                 },
                                 LevelTile {
@@ -244,26 +282,42 @@ pub fn spawn_level_tiles(
                             ));
                         });
                     }
-                    TileData::Texture { tileset_id, tile_index } => {
+                    TileData::Texture { tileset_id, tile_index, flags } => {
                         // Spawner un sprite avec texture
-                        if let Some(texture_handle) = tileset_handles.get(&tileset_id.to_string()) {
-                            let tileset_info = &project.tilesets[&tileset_id.to_string()];
-                            let tiles_per_row = tileset_info.tiles_per_row;
+                        let tileset_info = project.tilesets.iter().find(|t| t.id == tileset_id);
+                        if let (Some(texture_handle), Some(tileset_info)) = (tileset_handles.get(&tileset_id), tileset_info) {
+                            let columns = tileset_info.columns.max(1);
+                            let (tw, th) = (tileset_info.tile_width as f32, tileset_info.tile_height as f32);
                             
                             // Calculer les coordonnées UV
-                            let tile_x = (tile_index % tiles_per_row) as f32 * 16.0;
-                            let tile_y = (tile_index / tiles_per_row) as f32 * 16.0;
+                            let tile_x = (tile_index % columns) as f32 * tw;
+                            let tile_y = (tile_index / columns) as f32 * th;
+
+                            // Le sprite est retourné avant la rotation de l'entité :
+                            // retourner après un quart de tour échange les deux axes
+                            let (flip_x, flip_y) = if flags.rotate {
+                                (flags.flip_y, flags.flip_x)
+                            } else {
+                                (flags.flip_x, flags.flip_y)
+                            };
+                            let mut transform = Transform::from_xyz(world_x, world_y, 0.0);
+                            if flags.rotate {
+                                transform.rotate_z(-std::f32::consts::FRAC_PI_2);
+                            }
                             
-                            commands.entity(entity).with_children(|parent| {
-                                parent.spawn((
+                            commands.entity(layer_entity).with_children(|parent| {
+                                let mut tile = parent.spawn((
                                     SpriteBundle {
                                         texture: texture_handle.clone(),
                                         sprite: Sprite {
+                                            color: shade([255, 255, 255]),
                                             custom_size: Some(Vec2::new(tile_size, tile_size)),
-                                            rect: Some(Rect::new(tile_x, tile_y, tile_x + 16.0, tile_y + 16.0)),
+                                            rect: Some(Rect::new(tile_x, tile_y, tile_x + tw, tile_y + th)),
+                                            flip_x,
+                                            flip_y,
                                             ..default()
                                         },
-                                        transform: Transform::from_xyz(world_x, world_y, layer_index as f32),
+                                        transform,
                                         ..default()
                                     },
                                     LevelTile {
@@ -271,6 +325,24 @@ pub fn spawn_level_tiles(
                                         position: (x, y),
                                     },
                                 ));
+                                
+                                // Métadonnées du tile définies dans l'éditeur
+                                if let Some(metadata) = tileset_info.tiles.get(&tile_index) {
+                                    if !metadata.properties.is_empty() {
+                                        tile.insert(TileProperties(metadata.properties.clone()));
+                                    }
+                                    if !metadata.collision.is_empty() {
+                                        tile.insert(TileCollision(metadata.collision.clone()));
+                                    }
+                                    if !metadata.animation.is_empty() {
+                                        tile.insert(AnimatedTile {
+                                            metadata: metadata.clone(),
+                                            columns,
+                                            tile_size: Vec2::new(tw, th),
+                                            elapsed_ms: 0,
+                                        });
+                                    }
+                                }
                             });
                         }
                     }
@@ -280,12 +352,49 @@ pub fn spawn_level_tiles(
     }
 }
 
+/// Système qui fait avancer les tiles animés (découpe du sprite dans le tileset)
+pub fn animate_tiles(time: Res<Time>, mut query: Query<(&mut AnimatedTile, &mut Sprite)>) {
+    let delta_ms = time.delta().as_millis() as u64;
+    for (mut animated, mut sprite) in query.iter_mut() {
+        animated.elapsed_ms += delta_ms;
+        if let Some(tile_index) = animated.metadata.frame_at(animated.elapsed_ms) {
+            let size = animated.tile_size;
+            let min = Vec2::new((tile_index % animated.columns) as f32, (tile_index / animated.columns) as f32) * size;
+            sprite.rect = Some(Rect::from_corners(min, min + size));
+        }
+    }
+}
+
+/// Système de parallaxe : chaque calque suit la caméra selon `1 - parallax`,
+/// mesuré depuis l'origine de son niveau (facteur 1 : aucun effet)
+pub fn apply_parallax(
+    cameras: Query<&GlobalTransform, With<Camera2d>>,
+    levels: Query<&GlobalTransform, With<EditorLevel>>,
+    mut layers: Query<(&EditorLayer, &Parent, &mut Transform)>,
+) {
+    let Some(camera) = cameras.iter().next() else {
+        return;
+    };
+    for (layer, parent, mut transform) in layers.iter_mut() {
+        if layer.parallax == Vec2::ONE {
+            continue;
+        }
+        let Ok(level) = levels.get(parent.get()) else {
+            continue;
+        };
+        let camera_offset = camera.translation().truncate() - level.translation().truncate();
+        let position = layer.offset + camera_offset * (Vec2::ONE - layer.parallax);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
 /// Plugin pour faciliter l'intégration dans Bevy
 pub struct EditorLevelPlugin;
 
 impl Plugin for EditorLevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, spawn_level_tiles);
+        app.add_systems(Update, (spawn_level_tiles, animate_tiles, apply_parallax));
     }
 }
 
@@ -11,9 +11,12 @@ Installation:
 Utilisation:
     from parsers.pygame_parser import EditorLevelLoader
     
-    # Charger un niveau
+    # Charger un niveau (le premier du projet)
     loader = EditorLevelLoader("mon_niveau.editorproj")
     
+    # Ou un niveau précis d'un projet qui en contient plusieurs
+    grotte = EditorLevelLoader("mon_monde.editorproj", level_name="Grotte")
+    
     # Dans votre boucle de jeu
     loader.render(screen, camera_x=0, camera_y=0)
     
@@ -32,15 +35,19 @@ from typing import Dict, List, Tuple, Optional
 class EditorLevelLoader:
     """Charge et affiche un niveau EditorLevel2D dans Pygame."""
     
-    def __init__(self, project_file: str):
+    def __init__(self, project_file: str, level_name: Optional[str] = None):
         """
         Charge un fichier de projet .editorproj ou .json
         
         Args:
             project_file: Chemin vers le fichier .editorproj ou .json
+            level_name: Niveau à charger (le premier du projet par défaut)
         """
         self.project_file = Path(project_file)
+        self.level_name = level_name
+        self.levels = []  # Tous les niveaux du projet
         self.level = None
+        self.world_position = (0, 0)  # Coin haut-gauche sur la carte du monde, en pixels
         self.tilesets = {}
         self.tileset_surfaces = {}
         self.tile_cache = {}  # Cache des surfaces de tiles
@@ -52,14 +59,23 @@ class EditorLevelLoader:
         with open(self.project_file, 'r', encoding='utf-8') as f:
             data = json.load(f)
         
-        # Si c'est un .editorproj, il contient "level" et "tilesets"
-        if "level" in data:
-            self.level = data["level"]
+        # Un .editorproj contient "levels" (ou "level" avant la version 2.0) et "tilesets"
+        if "levels" in data or "level" in data:
+            self.levels = data.get("levels") or [data["level"]]
             self.tilesets = data.get("tilesets", {})
             self._load_tilesets()
         else:
             # C'est un ancien fichier .json (juste le niveau)
-            self.level = data
+            self.levels = [data]
+        
+        if self.level_name is None:
+            self.level = self.levels[0]
+        else:
+            matches = [level for level in self.levels if level.get("name") == self.level_name]
+            if not matches:
+                raise KeyError(f"Aucun niveau '{self.level_name}' dans {self.project_file}")
+            self.level = matches[0]
+        self.world_position = tuple(self.level.get("world_position", (0, 0)))
     
     def _load_tilesets(self):
         """Charge les images des tilesets."""
@@ -167,6 +183,13 @@ class EditorLevelLoader:
                         tile_surface = pygame.transform.scale(tile_surface, (tile_size, tile_size))
                     
                     surface = tile_surface.copy()
+
+                    # Transformations du tile : quart de tour horaire puis retournements
+                    flags = texture_info.get("flags", {})
+                    if flags.get("rotate"):
+                        surface = pygame.transform.rotate(surface, -90)
+                    if flags.get("flip_x") or flags.get("flip_y"):
+                        surface = pygame.transform.flip(surface, flags.get("flip_x", False), flags.get("flip_y", False))
         
         # Mettre en cache
         if surface:
@@ -174,6 +197,24 @@ class EditorLevelLoader:
         
         return surface
     
+    def is_layer_visible(self, layer: Dict) -> bool:
+        """
+        Indique si un calque est affiché : visible, et aucun de ses groupes
+        (dossiers de calques imbriqués) n'est masqué.
+        """
+        if not layer["visible"]:
+            return False
+        groups = {group["id"]: group for group in self.level.get("groups", [])}
+        group_id = layer.get("group")
+        seen = set()
+        while group_id in groups and group_id not in seen:
+            seen.add(group_id)
+            group = groups[group_id]
+            if not group.get("visible", True):
+                return False
+            group_id = group.get("parent")
+        return True
+    
     def render(self, screen: pygame.Surface, camera_x: int = 0, camera_y: int = 0, 
                scale: float = 1.0):
         """
@@ -196,7 +237,8 @@ class EditorLevelLoader:
         
         # Dessiner chaque calque
         for layer in self.level["layers"]:
-            if not layer["visible"]:
+            # Les calques de collision n'ont pas de rendu
+            if not self.is_layer_visible(layer) or layer.get("kind") == "Logic":
                 continue
             
             tiles = layer["tiles"]
@@ -261,17 +303,56 @@ class EditorLevelLoader:
         
         return positions
     
-    def get_collision_tiles(self, layer_name: str = "Main") -> List[Tuple[int, int]]:
+    def _logic_layer(self, layer_name: Optional[str] = None) -> Optional[Dict]:
+        """Calque de collision nommé, ou le premier du niveau si layer_name vaut None."""
+        for layer in self.level["layers"]:
+            if layer.get("kind") == "Logic" and layer_name in (None, layer["name"]):
+                return layer
+        return None
+    
+    def get_logic_grid(self, layer_name: Optional[str] = None) -> Tuple[List[List[int]], Dict[int, str]]:
         """
-        Retourne toutes les positions des tiles d'un calque (pour les collisions).
+        Retourne la grille typée d'un calque de collision.
+        
+        Args:
+            layer_name: Nom du calque (None = premier calque de collision)
+            
+        Returns:
+            (grille[y][x] de valeurs, 0 = rien ; {valeur: nom}, ex: {1: "solid"})
+        """
+        width, height = self.level["width"], self.level["height"]
+        grid = [[0] * width for _ in range(height)]
+        layer = self._logic_layer(layer_name)
+        if layer is None:
+            return grid, {}
+        for key, tile_data in layer["tiles"].items():
+            x, y = map(int, key.split(','))
+            if "Value" in tile_data and 0 <= x < width and 0 <= y < height:
+                grid[y][x] = tile_data["Value"]
+        names = {value["value"]: value["name"] for value in layer.get("logic_values", [])}
+        return grid, names
+    
+    def get_collision_tiles(self, layer_name: Optional[str] = None, value: str = "solid") -> List[Tuple[int, int]]:
+        """
+        Retourne les positions des cases de collision.
+        
+        Sur un calque de collision, seules les cases de la valeur `value`
+        ("solid", "one_way", "ladder"...) sont retournées. Sur un calque de
+        tiles (anciens niveaux), tous ses tiles comptent comme solides.
         
         Args:
-            layer_name: Nom du calque de collision
+            layer_name: Nom du calque (None = premier calque de collision, sinon "Main")
+            value: Nom de la valeur recherchée sur un calque de collision
             
         Returns:
             Liste de positions [(x, y), ...]
         """
-        tiles = self.get_layer_tiles(layer_name)
+        layer = self._logic_layer(layer_name)
+        if layer is not None:
+            grid, names = self.get_logic_grid(layer["name"])
+            wanted = [v for v, name in names.items() if name == value]
+            return [(x, y) for y, row in enumerate(grid) for x, cell in enumerate(row) if cell and cell in wanted]
+        tiles = self.get_layer_tiles(layer_name or "Main")
         return list(tiles.keys())
 
 
@@ -1,10 +1,14 @@
 use egui::{ColorImage, Context, TextureHandle, TextureOptions};
+use crate::project::{TileMetadata, TilesetMetadata};
+use crate::terrain::TerrainSet;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-/// Représente un tileset avec son image et ses métadonnées
+/// Représente un tileset avec sa texture et ses métadonnées
 #[derive(Clone)]
 pub struct Tileset {
+    pub id: usize,  // Identifiant stable, persisté dans le projet (indépendant de la position)
     pub name: String,
     pub path: PathBuf,
     pub texture: TextureHandle,
@@ -12,7 +16,9 @@ pub struct Tileset {
     pub tile_height: u32,
     pub columns: u32,
     pub rows: u32,
-    pub image: ColorImage,
+    pub tile_metadata: BTreeMap<u32, TileMetadata>,  // Propriétés et collisions par tile
+    pub terrains: Vec<TerrainSet>,  // Règles d'autotiling
+    pub missing: bool,  // Image introuvable : texture de remplacement en attendant la relocalisation
 }
 
 /// Référence vers un tile dans un tileset
@@ -26,6 +32,7 @@ pub struct TileRef {
 pub struct AssetManager {
     tilesets: Vec<Tileset>,
     tileset_counter: usize,
+    next_id: usize,
 }
 
 impl AssetManager {
@@ -33,6 +40,7 @@ impl AssetManager {
         Self {
             tilesets: Vec::new(),
             tileset_counter: 0,
+            next_id: 0,
         }
     }
     
@@ -40,15 +48,16 @@ impl AssetManager {
     pub fn get_metadata(&self) -> Vec<crate::project::TilesetMetadata> {
         self.tilesets
             .iter()
-            .enumerate()
-            .map(|(id, tileset)| crate::project::TilesetMetadata {
-                id,
+            .map(|tileset| crate::project::TilesetMetadata {
+                id: tileset.id,
                 name: tileset.name.clone(),
                 path: tileset.path.to_string_lossy().to_string(),
                 tile_width: tileset.tile_width,
                 tile_height: tileset.tile_height,
                 columns: tileset.columns,
                 rows: tileset.rows,
+                tiles: tileset.tile_metadata.clone(),
+                terrains: tileset.terrains.clone(),
             })
             .collect()
     }
@@ -61,6 +70,123 @@ impl AssetManager {
         tile_width: u32,
         tile_height: u32,
     ) -> Result<usize, String> {
+        let mut tileset = self.read_tileset(ctx, path, tile_width, tile_height)?;
+        let id = self.allocate_id();
+        tileset.id = id;
+        self.tilesets.push(tileset);
+        Ok(id)
+    }
+
+    /// Recharge un tileset d'un projet en conservant son ID persisté.
+    /// Retourne false si l'image est introuvable (texture de remplacement),
+    /// une erreur si l'ID est déjà pris par un autre tileset du projet.
+    pub fn restore_tileset(&mut self, ctx: &Context, metadata: &TilesetMetadata) -> Result<bool, String> {
+        let id = self.reserve_id(metadata.id)?;
+        let path = PathBuf::from(&metadata.path);
+        match self.read_tileset(ctx, path, metadata.tile_width, metadata.tile_height) {
+            Ok(mut tileset) => {
+                tileset.id = id;
+                tileset.tile_metadata = metadata.tiles.clone();
+                tileset.terrains = metadata.terrains.clone();
+                self.tilesets.push(tileset);
+                Ok(true)
+            }
+            Err(_) => {
+                self.add_missing_tileset(ctx, id, metadata);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Réserve un nouvel ID
+    fn allocate_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Réserve un ID persisté. Refuse un doublon : les tiles qui le référencent
+    /// s'afficheraient avec le mauvais tileset.
+    fn reserve_id(&mut self, id: usize) -> Result<usize, String> {
+        if let Some(existing) = self.get_tileset(id) {
+            return Err(format!("ID {} déjà utilisé par le tileset '{}'", id, existing.name));
+        }
+        self.next_id = self.next_id.max(id + 1);
+        Ok(id)
+    }
+
+    /// Réserve la place d'un tileset dont l'image est introuvable : une texture
+    /// de remplacement garde les références des tiles valides jusqu'à la relocalisation
+    fn add_missing_tileset(&mut self, ctx: &Context, id: usize, metadata: &TilesetMetadata) {
+        let width = (metadata.columns * metadata.tile_width).max(1) as usize;
+        let height = (metadata.rows * metadata.tile_height).max(1) as usize;
+        let mut image = ColorImage::new([width, height], egui::Color32::BLACK);
+        for y in 0..height {
+            for x in 0..width {
+                if (x / 4 + y / 4) % 2 == 0 {
+                    image[(x, y)] = egui::Color32::from_rgb(255, 0, 255);
+                }
+            }
+        }
+
+        let texture = ctx.load_texture(
+            format!("tileset_{}", self.tileset_counter),
+            image,
+            TextureOptions::NEAREST,
+        );
+        self.tileset_counter += 1;
+
+        self.tilesets.push(Tileset {
+            id,
+            name: metadata.name.clone(),
+            path: PathBuf::from(&metadata.path),
+            texture,
+            tile_width: metadata.tile_width,
+            tile_height: metadata.tile_height,
+            columns: metadata.columns,
+            rows: metadata.rows,
+            tile_metadata: metadata.tiles.clone(),
+            terrains: metadata.terrains.clone(),
+            missing: true,
+        });
+    }
+
+    /// Remplace l'image d'un tileset (relocalisation) en gardant son ID et ses métadonnées
+    pub fn relocate_tileset(&mut self, ctx: &Context, id: usize, path: PathBuf) -> Result<(), String> {
+        let Some(position) = self.position(id) else {
+            return Err(format!("tileset {} inexistant", id));
+        };
+        let current = &self.tilesets[position];
+        let (tile_width, tile_height) = (current.tile_width, current.tile_height);
+        let mut tileset = self.read_tileset(ctx, path, tile_width, tile_height)?;
+        tileset.id = id;
+        tileset.tile_metadata = std::mem::take(&mut self.tilesets[position].tile_metadata);
+        tileset.terrains = std::mem::take(&mut self.tilesets[position].terrains);
+        self.tilesets[position] = tileset;
+        Ok(())
+    }
+
+    /// IDs des tilesets dont l'image n'a pas été trouvée
+    pub fn missing_tilesets(&self) -> Vec<usize> {
+        self.tilesets
+            .iter()
+            .filter(|tileset| tileset.missing)
+            .map(|tileset| tileset.id)
+            .collect()
+    }
+
+    /// Position d'un tileset dans la liste à partir de son ID
+    fn position(&self, id: usize) -> Option<usize> {
+        self.tilesets.iter().position(|tileset| tileset.id == id)
+    }
+
+    fn read_tileset(
+        &mut self,
+        ctx: &Context,
+        path: PathBuf,
+        tile_width: u32,
+        tile_height: u32,
+    ) -> Result<Tileset, String> {
         // Charger l'image
         let img = image::open(&path).map_err(|e| format!("Erreur de chargement: {}", e))?;
 
@@ -73,7 +199,7 @@ impl AssetManager {
         // Créer la texture
         let texture = ctx.load_texture(
             format!("tileset_{}", self.tileset_counter),
-            color_image.clone(),
+            color_image,
             TextureOptions::NEAREST,
         );
 
@@ -86,7 +212,10 @@ impl AssetManager {
             .unwrap_or("Unknown")
             .to_string();
 
-        let tileset = Tileset {
+        self.tileset_counter += 1;
+
+        Ok(Tileset {
+            id: 0,  // Attribué par l'appelant
             name,
             path,
             texture,
@@ -94,19 +223,15 @@ impl AssetManager {
             tile_height,
             columns,
             rows,
-            image: color_image,
-        };
-
-        let id = self.tileset_counter;
-        self.tilesets.push(tileset);
-        self.tileset_counter += 1;
-
-        Ok(id)
+            tile_metadata: BTreeMap::new(),
+            terrains: Vec::new(),
+            missing: false,
+        })
     }
 
     /// Récupère un tileset par son ID
     pub fn get_tileset(&self, id: usize) -> Option<&Tileset> {
-        self.tilesets.get(id)
+        self.tilesets.iter().find(|tileset| tileset.id == id)
     }
 
     /// Récupère tous les tilesets
@@ -114,13 +239,46 @@ impl AssetManager {
         &self.tilesets
     }
 
-    /// Supprime un tileset
-    pub fn remove_tileset(&mut self, id: usize) {
-        if id < self.tilesets.len() {
-            self.tilesets.remove(id);
+    /// Métadonnées d'un tile (None si le tile n'en a pas)
+    pub fn get_tile_metadata(&self, tile_ref: TileRef) -> Option<&TileMetadata> {
+        self.get_tileset(tile_ref.tileset_id)?.tile_metadata.get(&tile_ref.tile_index)
+    }
+
+    /// Tile à afficher à l'instant donné : l'image courante si le tile est animé
+    pub fn animated_tile(&self, tile_ref: TileRef, elapsed_ms: u64) -> TileRef {
+        let frame = self.get_tile_metadata(tile_ref).and_then(|metadata| metadata.frame_at(elapsed_ms));
+        TileRef { tile_index: frame.unwrap_or(tile_ref.tile_index), ..tile_ref }
+    }
+
+    /// Indique si un tile possède une animation
+    pub fn is_animated(&self, tile_ref: TileRef) -> bool {
+        self.get_tile_metadata(tile_ref).is_some_and(|metadata| !metadata.animation.is_empty())
+    }
+
+    /// Remplace les métadonnées d'un tile (retirées si vides)
+    pub fn set_tile_metadata(&mut self, tile_ref: TileRef, metadata: TileMetadata) {
+        if let Some(tileset) = self.tilesets.iter_mut().find(|t| t.id == tile_ref.tileset_id) {
+            if metadata.is_empty() {
+                tileset.tile_metadata.remove(&tile_ref.tile_index);
+            } else {
+                tileset.tile_metadata.insert(tile_ref.tile_index, metadata);
+            }
         }
     }
 
+    /// Remplace les terrains d'autotiling d'un tileset
+    pub fn set_terrains(&mut self, tileset_id: usize, terrains: Vec<TerrainSet>) {
+        if let Some(tileset) = self.tilesets.iter_mut().find(|t| t.id == tileset_id) {
+            tileset.terrains = terrains;
+        }
+    }
+
+    /// Supprime un tileset : les autres gardent leur ID
+    pub fn remove_tileset(&mut self, id: usize) -> Option<Tileset> {
+        let position = self.position(id)?;
+        Some(self.tilesets.remove(position))
+    }
+
     /// Calcule les coordonnées UV d'un tile dans un tileset
     pub fn get_tile_uv(&self, tile_ref: TileRef) -> Option<(f32, f32, f32, f32)> {
         let tileset = self.get_tileset(tile_ref.tileset_id)?;
@@ -135,12 +293,6 @@ impl AssetManager {
 
         Some((u1, v1, u2, v2))
     }
-
-    /// Retourne le nombre total de tiles dans un tileset
-    pub fn get_tileset_tile_count(&self, tileset_id: usize) -> Option<u32> {
-        let tileset = self.get_tileset(tileset_id)?;
-        Some(tileset.columns * tileset.rows)
-    }
 }
 
 impl Default for AssetManager {
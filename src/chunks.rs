@@ -0,0 +1,227 @@
+//! Rendu du canvas par blocs de tiles (chunks)
+//!
+//! Chaque calque est découpé en blocs de `CHUNK_SIZE`×`CHUNK_SIZE` cases. Un bloc
+//! visible est converti une fois en maillages texturés (un par texture), puis
+//! seulement mis à l'échelle et déplacé à chaque image : le coût d'une image
+//! dépend du nombre de cases visibles, pas de la taille de la carte. Les tiles
+//! animés restent dessinés un par un, leur image changeant avec le temps.
+
+use crate::asset_manager::{AssetManager, TileRef};
+use crate::editor::{paint_tile_image, push_tile_quad};
+use crate::level::{Layer, TileData, TileFlags};
+use egui::{Color32, Mesh, Pos2, Rect, TextureId, Vec2};
+use std::collections::HashMap;
+
+/// Côté d'un bloc, en cases
+pub const CHUNK_SIZE: i32 = 16;
+
+/// Opacité de l'aperçu des valeurs d'un calque de collision
+pub const LOGIC_ALPHA: u8 = 110;
+
+/// Plage de cases visibles (bornes incluses)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellRange {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+impl CellRange {
+    pub fn is_empty(&self) -> bool {
+        self.min.0 > self.max.0 || self.min.1 > self.max.1
+    }
+
+    fn chunks(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (x0, y0) = (self.min.0.div_euclid(CHUNK_SIZE), self.min.1.div_euclid(CHUNK_SIZE));
+        let (x1, y1) = (self.max.0.div_euclid(CHUNK_SIZE), self.max.1.div_euclid(CHUNK_SIZE));
+        (y0..=y1).flat_map(move |cy| (x0..=x1).map(move |cx| (cx, cy)))
+    }
+}
+
+/// Bloc d'un calque prêt à dessiner
+struct Chunk {
+    meshes: Vec<Mesh>,  // Un maillage par texture, en coordonnées de cases
+    animated: Vec<((i32, i32), TileRef, TileFlags)>,  // Dessinés à chaque image
+}
+
+/// Ce qui, dans un tileset, change l'apparence des blocs déjà construits :
+/// ID, texture, découpage et indices des tiles animés (exclus des maillages)
+type TilesetKey = (usize, TextureId, u32, u32, Vec<u32>);
+
+/// Blocs construits, par (calque, bloc x, bloc y)
+pub struct ChunkCache {
+    chunks: HashMap<(usize, i32, i32), Chunk>,
+    tilesets: Vec<TilesetKey>,
+    level_size: (u32, u32),  // Les blocs du bord s'arrêtent aux limites du niveau
+    revision: u64,
+}
+
+impl ChunkCache {
+    pub fn new() -> Self {
+        Self { chunks: HashMap::new(), tilesets: Vec::new(), level_size: (0, 0), revision: 0 }
+    }
+
+    /// Compteur incrémenté à chaque invalidation : toute modification des
+    /// tiles du niveau affiché le fait changer
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Oublie tous les blocs (calques ajoutés, retirés ou remplacés, annulation, autre niveau)
+    pub fn invalidate(&mut self) {
+        self.chunks.clear();
+        self.revision += 1;
+    }
+
+    /// Oublie le bloc qui contient une case modifiée
+    pub fn invalidate_cell(&mut self, layer: usize, x: i32, y: i32) {
+        self.chunks.remove(&(layer, x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE)));
+        self.revision += 1;
+    }
+
+    /// Vide le cache si un tileset a été chargé, retiré, relocalisé ou si ses animations ont changé
+    pub fn sync_tilesets(&mut self, assets: &AssetManager) {
+        let tilesets: Vec<TilesetKey> = assets
+            .get_all_tilesets()
+            .iter()
+            .map(|tileset| {
+                let animated = tileset
+                    .tile_metadata
+                    .iter()
+                    .filter(|(_, metadata)| !metadata.animation.is_empty())
+                    .map(|(&index, _)| index)
+                    .collect();
+                (tileset.id, tileset.texture.id(), tileset.columns, tileset.rows, animated)
+            })
+            .collect();
+        if tilesets != self.tilesets {
+            self.tilesets = tilesets;
+            self.chunks.clear();
+        }
+    }
+
+    /// Dessine les cases `visible` d'un calque d'un niveau de `level_size` cases.
+    /// `origin` est la position à l'écran de la case (0, 0) ; `shade` est la
+    /// teinte et l'opacité appliquées au calque. Retourne true si un tile animé a été dessiné.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_layer(
+        &mut self,
+        painter: &egui::Painter,
+        layer_index: usize,
+        layer: &Layer,
+        level_size: (u32, u32),
+        assets: &AssetManager,
+        visible: CellRange,
+        origin: Pos2,
+        tile_size: f32,
+        shade: [u8; 4],
+        elapsed_ms: u64,
+    ) -> bool {
+        let mut has_animation = false;
+        if visible.is_empty() {
+            return false;
+        }
+        if level_size != self.level_size {
+            self.level_size = level_size;
+            self.chunks.clear();
+        }
+        for (cx, cy) in visible.chunks() {
+            let chunk = self
+                .chunks
+                .entry((layer_index, cx, cy))
+                .or_insert_with(|| build_chunk(layer, level_size, assets, cx, cy));
+
+            for mesh in &chunk.meshes {
+                let mut mesh = mesh.clone();
+                for vertex in &mut mesh.vertices {
+                    vertex.pos = origin + vertex.pos.to_vec2() * tile_size;
+                    if shade != [255; 4] {
+                        vertex.color = multiply(vertex.color, shade);
+                    }
+                }
+                painter.add(egui::Shape::mesh(mesh));
+            }
+
+            for &((x, y), tile_ref, flags) in &chunk.animated {
+                has_animation = true;
+                let frame = assets.animated_tile(tile_ref, elapsed_ms);
+                if let (Some(tileset), Some((u1, v1, u2, v2))) =
+                    (assets.get_tileset(frame.tileset_id), assets.get_tile_uv(frame))
+                {
+                    let rect = Rect::from_min_size(
+                        origin + Vec2::new(x as f32, y as f32) * tile_size,
+                        Vec2::splat(tile_size),
+                    );
+                    let uv = Rect::from_min_max(Pos2::new(u1, v1), Pos2::new(u2, v2));
+                    let [r, g, b, a] = shade;
+                    let tint = Color32::from_rgba_unmultiplied(r, g, b, a);
+                    paint_tile_image(painter, tileset.texture.id(), rect, uv, flags, tint);
+                }
+            }
+        }
+        has_animation
+    }
+}
+
+/// Construit les maillages d'un bloc : une case vaut une unité. Les tiles
+/// hors du niveau ne sont pas dessinés.
+fn build_chunk(layer: &Layer, (width, height): (u32, u32), assets: &AssetManager, cx: i32, cy: i32) -> Chunk {
+    let mut meshes: Vec<Mesh> = Vec::new();
+    let mut animated = Vec::new();
+
+    let (max_x, max_y) = (width as i32, height as i32);
+    for y in (cy * CHUNK_SIZE).max(0)..((cy + 1) * CHUNK_SIZE).min(max_y) {
+        for x in (cx * CHUNK_SIZE).max(0)..((cx + 1) * CHUNK_SIZE).min(max_x) {
+            let rect = Rect::from_min_size(Pos2::new(x as f32, y as f32), Vec2::splat(1.0));
+            match layer.tiles.get(&(x, y)) {
+                None | Some(TileData::Empty) => {}
+                Some(&TileData::Color([r, g, b])) => {
+                    let uv = Rect::from_min_max(egui::epaint::WHITE_UV, egui::epaint::WHITE_UV);
+                    let mesh = mesh_for(&mut meshes, TextureId::default());
+                    push_tile_quad(mesh, rect, uv, TileFlags::NONE, Color32::from_rgb(r, g, b));
+                }
+                Some(&TileData::Value(value)) => {
+                    let [r, g, b] = layer.logic_value(value).map_or([128, 128, 128], |v| v.color);
+                    let uv = Rect::from_min_max(egui::epaint::WHITE_UV, egui::epaint::WHITE_UV);
+                    let mesh = mesh_for(&mut meshes, TextureId::default());
+                    let color = Color32::from_rgba_unmultiplied(r, g, b, LOGIC_ALPHA);
+                    push_tile_quad(mesh, rect, uv, TileFlags::NONE, color);
+                }
+                Some(&TileData::Texture { tileset_id, tile_index, flags }) => {
+                    let tile_ref = TileRef { tileset_id, tile_index };
+                    if assets.is_animated(tile_ref) {
+                        animated.push(((x, y), tile_ref, flags));
+                        continue;
+                    }
+                    if let (Some(tileset), Some((u1, v1, u2, v2))) =
+                        (assets.get_tileset(tileset_id), assets.get_tile_uv(tile_ref))
+                    {
+                        let uv = Rect::from_min_max(Pos2::new(u1, v1), Pos2::new(u2, v2));
+                        let mesh = mesh_for(&mut meshes, tileset.texture.id());
+                        push_tile_quad(mesh, rect, uv, flags, Color32::WHITE);
+                    }
+                }
+            }
+        }
+    }
+
+    Chunk { meshes, animated }
+}
+
+/// Couleur de sommet multipliée par une teinte RGBA
+fn multiply(color: Color32, shade: [u8; 4]) -> Color32 {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let mul = |c: u8, s: u8| ((c as u16 * s as u16) / 255) as u8;
+    Color32::from_rgba_unmultiplied(mul(r, shade[0]), mul(g, shade[1]), mul(b, shade[2]), mul(a, shade[3]))
+}
+
+/// Maillage du bloc pour une texture (créé au premier tile qui l'utilise)
+fn mesh_for(meshes: &mut Vec<Mesh>, texture_id: TextureId) -> &mut Mesh {
+    let index = match meshes.iter().position(|mesh| mesh.texture_id == texture_id) {
+        Some(index) => index,
+        None => {
+            meshes.push(Mesh::with_texture(texture_id));
+            meshes.len() - 1
+        }
+    };
+    &mut meshes[index]
+}
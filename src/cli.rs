@@ -0,0 +1,484 @@
+//! Mode ligne de commande (sans affichage) : validation, conversion,
+//! statistiques et rendu PNG des niveaux, utilisable en intégration continue.
+//!
+//! Codes de sortie : 0 = succès, 1 = erreur ou fichier invalide, 2 = usage incorrect.
+
+use crate::editor::EditorState;
+use eframe::egui;
+use editor_level::level::{Layer, LayerKind, Level, TileData, TileFlags};
+use editor_level::project::Project;
+use editor_level::validation::Severity;
+use image::{imageops, Rgba, RgbaImage};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+
+const USAGE: &str = "\
+Usage : editor_level <commande> [arguments]
+
+Sans commande, l'éditeur graphique est lancé.
+
+Commandes :
+  validate <fichier>...                Vérifie des niveaux (.json, .editorproj, .tmx)
+  convert <entrée> <sortie> [--level NOM]
+                                       Convertit selon les extensions (.json, .editorproj, .tmx) ;
+                                       --level choisit le niveau écrit en .json ou .tmx
+  stats <fichier>...                   Affiche les statistiques (tiles par calque, tilesets)
+  render <fichier> <sortie.png> [--scale N] [--background #RRGGBB] [--level NOM]
+                                       Génère une image PNG du niveau (le premier par défaut)
+  bench [--frames N]                   Mesure le coût d'une image du canvas selon la taille de la carte
+  help                                 Affiche cette aide";
+
+/// Erreur d'utilisation (arguments manquants ou invalides)
+#[derive(Debug)]
+struct UsageError(String);
+
+type CliResult = Result<bool, Box<dyn std::error::Error>>;
+
+/// Exécute une commande et retourne le code de sortie
+pub fn run(args: &[String]) -> ExitCode {
+    let Some((command, rest)) = args.split_first() else {
+        println!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = match command.as_str() {
+        "validate" => validate(rest),
+        "convert" => convert(rest),
+        "stats" => stats(rest),
+        "render" => render(rest),
+        "bench" => bench(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        other => Err(UsageError(format!("commande inconnue : {}", other)).into()),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => match e.downcast_ref::<UsageError>() {
+            Some(UsageError(message)) => {
+                eprintln!("❌ {}\n\n{}", message, USAGE);
+                ExitCode::from(2)
+            }
+            None => {
+                eprintln!("❌ {}", e);
+                ExitCode::from(1)
+            }
+        },
+    }
+}
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Problèmes d'un projet sous forme de texte (erreurs bloquantes, avertissements)
+fn check_project(project: &Project) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for problem in project.validate() {
+        // Avec plusieurs niveaux, chaque message indique le niveau concerné
+        let mut message = match problem.level {
+            Some(index) if project.levels.len() > 1 => {
+                format!("niveau '{}' : {}", project.levels[index].name, problem.message)
+            }
+            _ => problem.message,
+        };
+        match problem.cells[..] {
+            [] => {}
+            [(x, y)] => message.push_str(&format!(" (case {}, {})", x, y)),
+            [(x, y), ..] => message.push_str(&format!(" (première case {}, {})", x, y)),
+        }
+        match problem.severity {
+            Severity::Error => errors.push(message),
+            Severity::Warning => warnings.push(message),
+        }
+    }
+    (errors, warnings)
+}
+
+fn validate(files: &[String]) -> CliResult {
+    if files.is_empty() {
+        return Err(UsageError("validate : aucun fichier indiqué".to_string()).into());
+    }
+
+    let mut all_valid = true;
+    for file in files {
+        let project = match Project::open(file) {
+            Ok(project) => project,
+            Err(e) => {
+                println!("❌ {} : {}", file, e);
+                all_valid = false;
+                continue;
+            }
+        };
+        let (errors, warnings) = check_project(&project);
+        if errors.is_empty() {
+            println!("✅ {}", file);
+        } else {
+            println!("❌ {}", file);
+            all_valid = false;
+        }
+        for error in &errors {
+            println!("   erreur : {}", error);
+        }
+        for warning in &warnings {
+            println!("   ⚠️ {}", warning);
+        }
+    }
+    Ok(all_valid)
+}
+
+fn convert(args: &[String]) -> CliResult {
+    let mut positional = Vec::new();
+    let mut level_name = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--level" => {
+                level_name = Some(
+                    iter.next()
+                        .ok_or_else(|| UsageError("convert : --level attend un nom de niveau".to_string()))?,
+                );
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let [input, output] = positional[..] else {
+        return Err(UsageError("convert : <entrée> <sortie> attendus".to_string()).into());
+    };
+
+    let project = Project::open(input)?;
+    let output_extension = extension(output);
+    // Les formats .json et .tmx ne contiennent qu'un niveau : il doit être désigné
+    let level_index = match level_name {
+        Some(_) if output_extension == "editorproj" => {
+            return Err(UsageError("convert : --level ne s'applique qu'aux sorties .json et .tmx".to_string()).into())
+        }
+        Some(name) => project
+            .levels
+            .iter()
+            .position(|level| &level.name == name)
+            .ok_or_else(|| format!("{} : aucun niveau nommé '{}'", input, name))?,
+        None if project.levels.len() > 1 && output_extension != "editorproj" => {
+            return Err(format!(
+                "{} contient {} niveaux : choisir celui à écrire avec --level NOM",
+                input,
+                project.levels.len()
+            )
+            .into())
+        }
+        None => 0,
+    };
+    match output_extension.as_str() {
+        "editorproj" => project.save_to_file(output)?,
+        "json" => project.levels[level_index].save_to_file(output)?,
+        "tmx" => project.export_level_tmx(level_index, output)?,
+        other => {
+            return Err(UsageError(format!("convert : extension de sortie '.{}' non supportée", other)).into())
+        }
+    }
+    println!("✅ {} → {}", input, output);
+    Ok(true)
+}
+
+fn stats(files: &[String]) -> CliResult {
+    if files.is_empty() {
+        return Err(UsageError("stats : aucun fichier indiqué".to_string()).into());
+    }
+
+    for file in files {
+        let project = Project::open(file)?;
+        println!("📊 {}", file);
+
+        let mut tileset_usage: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut color_tiles = 0;
+        for level in &project.levels {
+            println!(
+                "   Niveau : {} ({}x{}, tiles de {}px, format v{})",
+                level.name, level.width, level.height, level.tile_size, level.format_version
+            );
+            println!("   Calques : {}", level.layers.len());
+            for (index, layer) in level.layers.iter().enumerate() {
+                let group = level.group_path(layer.group);
+                let name = if group.is_empty() { layer.name.clone() } else { format!("{} / {}", group, layer.name) };
+                let visibility = if level.is_layer_visible(index) { "" } else { " (masqué)" };
+                match layer.kind {
+                    LayerKind::Objects => println!("     - {}{} : {} objets", name, visibility, layer.objects.len()),
+                    LayerKind::Logic => {
+                        // Cases par valeur nommée
+                        let counts: Vec<String> = layer
+                            .logic_values
+                            .iter()
+                            .map(|v| (v, layer.tiles.values().filter(|t| **t == TileData::Value(v.value)).count()))
+                            .filter(|(_, count)| *count > 0)
+                            .map(|(v, count)| format!("{} {}", v.name, count))
+                            .collect();
+                        println!(
+                            "     - {}{} : {} cases de collision ({})",
+                            name,
+                            visibility,
+                            layer.tiles.len(),
+                            counts.join(", ")
+                        );
+                    }
+                    LayerKind::Tiles => println!("     - {}{} : {} tiles", name, visibility, layer.tiles.len()),
+                }
+                for tile in layer.tiles.values() {
+                    match tile {
+                        TileData::Color(_) => color_tiles += 1,
+                        TileData::Texture { tileset_id, .. } => *tileset_usage.entry(*tileset_id).or_insert(0) += 1,
+                        TileData::Empty | TileData::Value(_) => {}
+                    }
+                }
+            }
+        }
+
+        println!("   Tiles de couleur : {}", color_tiles);
+        println!("   Tilesets : {}", project.tilesets.len());
+        for tileset in &project.tilesets {
+            println!(
+                "     - #{} {} : {} tiles placés",
+                tileset.id,
+                tileset.name,
+                tileset_usage.remove(&tileset.id).unwrap_or(0)
+            );
+        }
+        for (tileset_id, count) in tileset_usage {
+            println!("     - #{} (non déclaré) : {} tiles placés", tileset_id, count);
+        }
+    }
+    Ok(true)
+}
+
+fn render(args: &[String]) -> CliResult {
+    let mut positional = Vec::new();
+    let mut scale = 1u32;
+    let mut background = Rgba([0, 0, 0, 0]);
+    let mut level_name = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--scale" => {
+                scale = iter
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|&s| s > 0)
+                    .ok_or_else(|| UsageError("render : --scale attend un entier positif".to_string()))?;
+            }
+            "--background" => {
+                let [r, g, b] = iter
+                    .next()
+                    .and_then(|v| crate::ui::parse_hex_color(v))
+                    .ok_or_else(|| UsageError("render : --background attend une couleur #RRGGBB".to_string()))?;
+                background = Rgba([r, g, b, 255]);
+            }
+            "--level" => {
+                level_name = Some(
+                    iter.next()
+                        .ok_or_else(|| UsageError("render : --level attend un nom de niveau".to_string()))?,
+                );
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let [input, output] = positional[..] else {
+        return Err(UsageError("render : <fichier> <sortie.png> attendus".to_string()).into());
+    };
+
+    let project = Project::open(input)?;
+    let level = match level_name {
+        Some(name) => project
+            .level_by_name(name)
+            .ok_or_else(|| format!("{} : aucun niveau nommé '{}'", input, name))?,
+        None => project.level(),
+    };
+    let image = render_level(&project, level, scale, background)?;
+    image.save(output)?;
+    println!("✅ {} → {} ({}x{})", input, output, image.width(), image.height());
+    Ok(true)
+}
+
+/// Plus grand côté de l'image générée par `render`, en pixels
+const MAX_RENDER_SIDE: u32 = 16384;
+
+/// Dessine les calques visibles d'un niveau du projet (tiles de couleur et texturés).
+/// Erreur d'utilisation si l'image dépasse `MAX_RENDER_SIDE` pixels de côté.
+fn render_level(project: &Project, level: &Level, scale: u32, background: Rgba<u8>) -> Result<RgbaImage, UsageError> {
+    let too_large = || {
+        UsageError(format!(
+            "render : image de plus de {} pixels de côté ({}x{} tiles de {}px, échelle {})",
+            MAX_RENDER_SIDE, level.width, level.height, level.tile_size, scale
+        ))
+    };
+    let tile_px = level.tile_size.checked_mul(scale).ok_or_else(too_large)?;
+    let side = |tiles: u32| tiles.checked_mul(tile_px).filter(|&px| px <= MAX_RENDER_SIDE).ok_or_else(too_large);
+    let mut canvas = RgbaImage::from_pixel(side(level.width)?, side(level.height)?, background);
+
+    // Tiles des tilesets, découpés et mis à l'échelle à la demande
+    let mut images: HashMap<usize, Option<RgbaImage>> = HashMap::new();
+    let mut tile_cache: HashMap<(usize, u32, TileFlags), Option<RgbaImage>> = HashMap::new();
+
+    // Les calques de collision ne sont pas des visuels
+    let layers = level
+        .layers
+        .iter()
+        .enumerate()
+        .filter(|&(index, layer)| level.is_layer_visible(index) && layer.kind != LayerKind::Logic);
+    for (_, layer) in layers {
+        // Ordre stable : le rendu ne dépend pas de l'ordre de la HashMap
+        let mut tiles: Vec<_> = layer.tiles.iter().collect();
+        tiles.sort_by_key(|(&(x, y), _)| (y, x));
+
+        // Décalage du calque (la parallaxe n'a pas d'effet sur une image fixe)
+        let (ox, oy) = (
+            (layer.offset.0 * scale as f32).round() as i64,
+            (layer.offset.1 * scale as f32).round() as i64,
+        );
+        let white = layer.shade([255, 255, 255]);
+        let mut tinted: HashMap<(usize, u32, TileFlags), RgbaImage> = HashMap::new();
+
+        for (&(x, y), tile) in tiles {
+            if x < 0 || y < 0 || x >= level.width as i32 || y >= level.height as i32 {
+                continue;
+            }
+            let (px, py) = (x as i64 * tile_px as i64 + ox, y as i64 * tile_px as i64 + oy);
+            match *tile {
+                TileData::Empty | TileData::Value(_) => {}
+                TileData::Color(rgb) => {
+                    let block = RgbaImage::from_pixel(tile_px, tile_px, Rgba(layer.shade(rgb)));
+                    imageops::overlay(&mut canvas, &block, px, py);
+                }
+                TileData::Texture { tileset_id, tile_index, flags } => {
+                    let tile_image = tile_cache.entry((tileset_id, tile_index, flags)).or_insert_with(|| {
+                        let tileset = project.tilesets.iter().find(|t| t.id == tileset_id)?;
+                        let sheet = images
+                            .entry(tileset_id)
+                            .or_insert_with(|| match image::open(&tileset.path) {
+                                Ok(img) => Some(img.to_rgba8()),
+                                Err(e) => {
+                                    eprintln!("⚠️ Tileset '{}' non chargé : {}", tileset.name, e);
+                                    None
+                                }
+                            })
+                            .as_ref()?;
+                        let columns = tileset.columns.max(1);
+                        let (sx, sy) = (
+                            (tile_index % columns) * tileset.tile_width,
+                            (tile_index / columns) * tileset.tile_height,
+                        );
+                        if sx + tileset.tile_width > sheet.width() || sy + tileset.tile_height > sheet.height() {
+                            return None;
+                        }
+                        let mut cropped = imageops::crop_imm(sheet, sx, sy, tileset.tile_width, tileset.tile_height).to_image();
+                        // Rotation d'abord, puis retournements (voir TileFlags)
+                        if flags.rotate {
+                            cropped = imageops::rotate90(&cropped);
+                        }
+                        if flags.flip_x {
+                            imageops::flip_horizontal_in_place(&mut cropped);
+                        }
+                        if flags.flip_y {
+                            imageops::flip_vertical_in_place(&mut cropped);
+                        }
+                        Some(imageops::resize(&cropped, tile_px, tile_px, imageops::FilterType::Nearest))
+                    });
+                    let Some(tile_image) = tile_image else {
+                        continue;
+                    };
+                    if white == [255; 4] {
+                        imageops::overlay(&mut canvas, tile_image, px, py);
+                    } else {
+                        // Teinte et opacité du calque, calculées une fois par tile
+                        let tile_image = tinted.entry((tileset_id, tile_index, flags)).or_insert_with(|| {
+                            let mut image = tile_image.clone();
+                            for pixel in image.pixels_mut() {
+                                for (channel, factor) in pixel.0.iter_mut().zip(white) {
+                                    *channel = (*channel as u16 * factor as u16 / 255) as u8;
+                                }
+                            }
+                            image
+                        });
+                        imageops::overlay(&mut canvas, tile_image, px, py);
+                    }
+                }
+            }
+        }
+    }
+    Ok(canvas)
+}
+
+/// Mesure le temps d'une image du canvas (sans fenêtre) pour des cartes de plus en
+/// plus grandes remplies sur sept calques : avec le rendu par blocs, il ne dépend
+/// que de la zone visible
+fn bench(args: &[String]) -> CliResult {
+    let frames = match args {
+        [] => 60,
+        [flag, value] if flag == "--frames" => value
+            .parse()
+            .ok()
+            .filter(|&n: &u32| n > 0)
+            .ok_or_else(|| UsageError("bench : --frames attend un entier positif".to_string()))?,
+        _ => return Err(UsageError("bench : seule l'option --frames N est acceptée".to_string()).into()),
+    };
+
+    let ctx = egui::Context::default();
+    let input = || egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1280.0, 720.0))),
+        ..Default::default()
+    };
+
+    println!("Canvas 1280x720, tiles de 16px, 7 calques, {} images par taille", frames);
+    println!("   Carte       Tiles    Image (ms)");
+    for size in [64u32, 128, 256, 512, 1024] {
+        let mut level = Level::new(format!("Bench {}", size), size, size, 16);
+        level.layers = (0..7u8)
+            .map(|index| {
+                let mut layer = Layer::new(format!("Calque {}", index + 1));
+                for y in 0..size as i32 {
+                    for x in 0..size as i32 {
+                        let shade = ((x + y) % 8) as u8 * 16;
+                        layer.set_tile(x, y, TileData::Color([shade, index * 30, 255 - shade]));
+                    }
+                }
+                layer
+            })
+            .collect();
+        let mut state = EditorState::new();
+        state.set_levels(level, Vec::new());
+
+        let frame = |state: &mut EditorState| {
+            let output = ctx.run(input(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| state.draw_canvas(ui));
+            });
+            ctx.tessellate(output.shapes, output.pixels_per_point);
+        };
+        // Premières images : construction des blocs visibles, préparation des polices
+        for _ in 0..5 {
+            frame(&mut state);
+        }
+        let start = Instant::now();
+        for _ in 0..frames {
+            frame(&mut state);
+        }
+        let per_frame = start.elapsed().as_secs_f64() * 1000.0 / frames as f64;
+        println!("   {:>4}x{:<4} {:>9}    {:>8.2}", size, size, size * size * 7, per_frame);
+    }
+    Ok(true)
+}
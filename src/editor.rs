@@ -1,7 +1,12 @@
-use crate::asset_manager::AssetManager;
-use crate::level::{Level, TileData, Layer};
+use crate::asset_manager::{AssetManager, TileRef};
+use crate::chunks::{CellRange, ChunkCache, LOGIC_ALPHA};
+use crate::history::{EditCommand, History, DEFAULT_HISTORY_BUDGET};
+use crate::level::{Anchor, Layer, LayerKind, Level, LevelObject, LogicValue, TileData, TileFlags};
+use crate::project::Project;
+use crate::reachability::{self, Reachability, ReachabilitySettings};
+use crate::validation::{Problem, Severity, ValidationSettings};
 use egui::{Color32, Pos2, Rect, Sense, Vec2};
-use std::collections::VecDeque;
+use std::collections::BTreeSet;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Tool {
@@ -10,13 +15,143 @@ pub enum Tool {
     Select,
     LineFill,  // Remplissage en ligne (horizontal ou vertical)
     RectFill,  // Remplissage en carré/rectangle
+    Fill,      // Pot de peinture (zone de tiles identiques)
+    Terrain,   // Pinceau d'autotiling (terrain du tileset)
+    MagicWand, // Baguette magique (sélection de tiles identiques)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillMode {
+    Contiguous,  // Zone contiguë de tiles identiques
+    Global,      // Tous les tiles identiques du calque
+}
+
+/// Combinaison d'une nouvelle zone avec la sélection (Maj : ajouter, Alt : retirer)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionMode {
+    Replace,
+    Add,
+    Subtract,
+}
+
+impl SelectionMode {
+    pub fn from_modifiers(modifiers: &egui::Modifiers) -> Self {
+        if modifiers.shift {
+            SelectionMode::Add
+        } else if modifiers.alt {
+            SelectionMode::Subtract
+        } else {
+            SelectionMode::Replace
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct SelectionData {
-    pub start: Option<(i32, i32)>,
+    pub start: Option<(i32, i32)>,  // Rectangle en cours (ou départ des remplissages ligne/rectangle)
     pub end: Option<(i32, i32)>,
-    pub is_active: bool,  // Indique si une sélection est active
+    pub mode: SelectionMode,  // Combinaison du rectangle en cours avec le masque
+    mask: BTreeSet<(i32, i32)>,  // Cases sélectionnées, de forme quelconque
+    bounds: Option<(i32, i32, i32, i32)>,  // Rectangle englobant du masque, tenu à jour
+    revision: u64,  // Incrémenté à chaque modification du masque
+}
+
+impl SelectionData {
+    pub fn new() -> Self {
+        Self { start: None, end: None, mode: SelectionMode::Replace, mask: BTreeSet::new(), bounds: None, revision: 0 }
+    }
+
+    /// Cases sélectionnées
+    pub fn mask(&self) -> &BTreeSet<(i32, i32)> {
+        &self.mask
+    }
+
+    /// Remplace les cases sélectionnées
+    pub fn set_mask(&mut self, mask: BTreeSet<(i32, i32)>) {
+        self.mask = mask;
+        self.update_bounds();
+    }
+
+    fn update_bounds(&mut self) {
+        self.revision += 1;
+        self.bounds = self.mask.iter().fold(None, |bounds, &(x, y)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((min_x, min_y, max_x, max_y)) => Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))),
+        });
+    }
+
+    /// Cases sélectionnées dans le rectangle `min`..=`max`, sans parcourir le
+    /// reste du masque (une colonne à la fois)
+    pub fn cells_in(&self, min: (i32, i32), max: (i32, i32)) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let (min, max) = match self.bounds {
+            Some((min_x, min_y, max_x, max_y)) => ((min.0.max(min_x), min.1.max(min_y)), (max.0.min(max_x), max.1.min(max_y))),
+            None => ((0, 0), (-1, -1)),
+        };
+        cells_in_rect(&self.mask, min, max)
+    }
+
+    /// Indique si une sélection est active
+    pub fn is_active(&self) -> bool {
+        !self.mask.is_empty()
+    }
+
+    /// Rectangle englobant (min_x, min_y, max_x, max_y) du masque
+    pub fn bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        self.bounds
+    }
+
+    /// Indique si la case fait partie de la sélection active
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.mask.contains(&(x, y))
+    }
+
+    /// Ajoute, retire ou remplace des cases
+    pub fn combine(&mut self, cells: impl IntoIterator<Item = (i32, i32)>, mode: SelectionMode) {
+        if mode == SelectionMode::Replace {
+            self.mask.clear();
+        }
+        for cell in cells {
+            if mode == SelectionMode::Subtract {
+                self.mask.remove(&cell);
+            } else {
+                self.mask.insert(cell);
+            }
+        }
+        self.update_bounds();
+    }
+
+    /// Annule la sélection et le rectangle en cours
+    pub fn clear(&mut self) {
+        self.start = None;
+        self.end = None;
+        self.mask.clear();
+        self.bounds = None;
+        self.revision += 1;
+    }
+}
+
+/// Cases d'un ensemble comprises dans le rectangle `min`..=`max`, colonne par colonne
+fn cells_in_rect(cells: &BTreeSet<(i32, i32)>, min: (i32, i32), max: (i32, i32)) -> impl Iterator<Item = (i32, i32)> + '_ {
+    let (y0, y1) = (min.1, max.1);
+    (min.0..=max.0).filter(move |_| y0 <= y1).flat_map(move |x| cells.range((x, y0)..=(x, y1)).copied())
+}
+
+/// Ce dont dépend l'aperçu du pot de peinture et de la baguette
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FillPreviewKey {
+    cell: (i32, i32),
+    tool: Tool,
+    fill_mode: FillMode,
+    level: usize,
+    layer: usize,
+    tiles_revision: u64,
+    selection_revision: u64,
+}
+
+/// Cases d'un rectangle (coins inclus, dans n'importe quel ordre)
+fn rect_cells(a: (i32, i32), b: (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+    let (min_x, max_x, min_y, max_y) = (a.0.min(b.0), a.0.max(b.0), a.1.min(b.1), a.1.max(b.1));
+    (min_y..=max_y).flat_map(move |y| (min_x..=max_x).map(move |x| (x, y)))
 }
 
 #[derive(Debug, Clone)]
@@ -27,61 +162,158 @@ pub struct SelectionContent {
     pub height: i32,
 }
 
-/// Structure pour l'historique (Undo/Redo)
-#[derive(Debug, Clone)]
-struct HistoryState {
-    layers: Vec<Layer>,
-    current_layer: usize,
-}
-
-struct History {
-    undo_stack: VecDeque<HistoryState>,
-    redo_stack: VecDeque<HistoryState>,
-    max_size: usize,
+/// Transformation du pinceau courant (raccourcis X, Y et R)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrushTransform {
+    FlipX,
+    FlipY,
+    Rotate,
 }
 
-impl History {
-    fn new(max_size: usize) -> Self {
-        Self {
-            undo_stack: VecDeque::new(),
-            redo_stack: VecDeque::new(),
-            max_size,
+impl BrushTransform {
+    /// Nouvelle position d'une case d'un bloc width x height
+    pub fn position(self, (dx, dy): (i32, i32), width: i32, height: i32) -> (i32, i32) {
+        match self {
+            BrushTransform::FlipX => (width - 1 - dx, dy),
+            BrushTransform::FlipY => (dx, height - 1 - dy),
+            BrushTransform::Rotate => (height - 1 - dy, dx),
         }
     }
 
-    fn push(&mut self, state: HistoryState) {
-        if self.undo_stack.len() >= self.max_size {
-            self.undo_stack.pop_front();
+    pub fn apply(self, flags: TileFlags) -> TileFlags {
+        match self {
+            BrushTransform::FlipX => flags.flipped_x(),
+            BrushTransform::FlipY => flags.flipped_y(),
+            BrushTransform::Rotate => flags.rotated(),
         }
-        self.undo_stack.push_back(state);
-        // Clear redo stack when a new action is performed
-        self.redo_stack.clear();
     }
+}
 
-    fn undo(&mut self) -> Option<HistoryState> {
-        self.undo_stack.pop_back()
+impl SelectionContent {
+    /// Contenu retourné ou tourné d'un quart de tour horaire : les positions
+    /// sont déplacées et chaque tile texturé reçoit la même transformation
+    pub fn transformed(&self, transform: BrushTransform) -> SelectionContent {
+        let (width, height) = match transform {
+            BrushTransform::Rotate => (self.height, self.width),
+            _ => (self.width, self.height),
+        };
+        let tiles = self
+            .tiles
+            .iter()
+            .map(|&(position, tile_data)| {
+                (
+                    transform.position(position, self.width, self.height),
+                    tile_data.with_flags(|flags| transform.apply(flags)),
+                )
+            })
+            .collect();
+        SelectionContent { tiles, origin: self.origin, width, height }
     }
 
-    fn redo(&mut self) -> Option<HistoryState> {
-        self.redo_stack.pop_back()
+    /// Premier et dernier tile s'il s'agit d'un rectangle découpé dans ce tileset
+    /// (tampon choisi dans la grille, à surligner)
+    pub fn tileset_bounds(&self, tileset_id: usize, columns: u32) -> Option<(u32, u32)> {
+        let columns = columns.max(1) as i32;
+        let (ox, oy) = self.origin;
+        if ox < 0 || oy < 0 || ox + self.width > columns {
+            return None;
+        }
+        let index = |dx: i32, dy: i32| ((oy + dy) * columns + ox + dx) as u32;
+        let is_rectangle = self.tiles.len() == (self.width * self.height) as usize
+            && self.tiles.iter().all(|&((dx, dy), tile_data)| {
+                tile_data == TileData::texture(tileset_id, index(dx, dy))
+            });
+        is_rectangle.then(|| (index(0, 0), index(self.width - 1, self.height - 1)))
     }
+}
+
+/// Taille (en pixels écran) de la poignée de redimensionnement d'un objet
+const OBJECT_HANDLE_SIZE: f32 = 8.0;
 
-    fn push_redo(&mut self, state: HistoryState) {
-        if self.redo_stack.len() >= self.max_size {
-            self.redo_stack.pop_front();
+/// Déplacement de la sélection en cours (outil Sélection)
+struct SelectionDrag {
+    grab: (i32, i32),          // Case saisie au début du glissé
+    offset: (i32, i32),        // Décalage courant en cases
+    content: SelectionContent, // Tiles déplacés (aperçu)
+}
+
+/// Déplacement ou redimensionnement d'objet en cours
+struct ObjectDrag {
+    start: Vec2,              // Position du clic (pixels du niveau)
+    resize: bool,             // Poignée saisie plutôt que l'objet
+    original: LevelObject,    // Objet au début du glissé
+    before: Vec<LevelObject>, // Objets du calque avant le glissé (historique)
+}
+
+/// Couleur d'affichage d'un objet selon son type
+fn object_color(object_type: &str) -> Color32 {
+    match object_type {
+        "spawn" => Color32::from_rgb(0, 255, 0),
+        "exit" | "door" => Color32::from_rgb(0, 191, 255),
+        "enemy" => Color32::from_rgb(255, 60, 60),
+        "trigger" => Color32::from_rgb(255, 165, 0),
+        "item" => Color32::from_rgb(255, 215, 0),
+        _ => {
+            // Teinte stable dérivée du nom du type
+            let hash = object_type.bytes().fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32));
+            egui::ecolor::Hsva::new((hash % 360) as f32 / 360.0, 0.6, 0.9, 1.0).into()
         }
-        self.redo_stack.push_back(state);
     }
+}
+
+/// Fenêtre de retrait ou de remplacement d'un tileset encore utilisé
+#[derive(Debug, Clone, Copy)]
+pub struct TilesetDialog {
+    pub tileset_id: usize,
+    pub remove: bool,            // Retirer le tileset après l'opération
+    pub target: Option<usize>,   // Tileset de remplacement choisi
+}
+
+/// Fenêtre de redimensionnement et de décalage du niveau
+#[derive(Debug, Clone, Copy)]
+pub struct ResizeDialog {
+    pub width: u32,
+    pub height: u32,
+    pub anchor: Anchor,
+    pub shift: (i32, i32),  // Décalage en cases
+}
+
+/// Niveau du projet avec son propre historique et son calque actif
+struct StoredLevel {
+    level: Level,
+    history: History,
+    current_layer: usize,
+}
 
-    fn can_undo(&self) -> bool {
-        !self.undo_stack.is_empty()
+impl StoredLevel {
+    fn new(level: Level, history_budget: usize) -> Self {
+        let current_layer = 1.min(level.layers.len().saturating_sub(1));
+        Self { level, history: History::new(history_budget), current_layer }
     }
+}
+
+/// Carte du monde : tous les niveaux du projet placés selon leur `world_position`
+#[derive(Debug, Clone, Copy)]
+pub struct WorldView {
+    pub zoom: f32,
+    pub offset: Vec2,
+    drag: Option<WorldDrag>,
+}
 
-    fn can_redo(&self) -> bool {
-        !self.redo_stack.is_empty()
+impl WorldView {
+    pub fn new() -> Self {
+        Self { zoom: 0.25, offset: Vec2::new(40.0, 40.0), drag: None }
     }
 }
 
+/// Niveau en cours de déplacement sur la carte du monde
+#[derive(Debug, Clone, Copy)]
+struct WorldDrag {
+    level: usize,
+    grab: Pos2,                // Position du pointeur au début du glisser (écran)
+    origin: (i32, i32),        // Position du niveau au début du glisser (pixels du monde)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PaintMode {
     ColorTile([u8; 3]), // RGB
@@ -89,51 +321,104 @@ pub enum PaintMode {
 }
 
 pub struct EditorState {
-    pub level: Level,
+    pub level: Level,  // Niveau en cours d'édition
+    levels: Vec<StoredLevel>,  // Niveaux du projet ; l'entrée du niveau courant est périmée (voir `level_at`)
+    pub current_level: usize,
+    pub world_view: Option<WorldView>,  // Carte du monde affichée à la place du canvas
     pub current_tool: Tool,
+    pub fill_mode: FillMode,
     pub paint_mode: PaintMode,
     pub current_layer: usize,
     pub zoom: f32,
     pub offset: Vec2,
     pub show_grid: bool,
-    pub is_dragging: bool,
+    pub parallax_preview: bool,  // Décalages et parallaxe appliqués, outils désactivés
     pub last_painted: Option<(i32, i32)>,
     pub asset_manager: AssetManager,
     pub selected_tileset: Option<usize>,
-    pub tileset_scroll: f32,
     pub selected_color: [u8; 3],
     pub color_hex_input: String,
+    pub tile_property_name: String,  // Nom de la propriété de tile à ajouter
+    pub tile_property_type: String,  // Type de la propriété de tile à ajouter
     pub notification: Option<(String, f32)>, // (message, temps restant)
     pub last_loaded_file: Option<String>,
     pub show_layer_config: bool,
+    pub show_relocate_dialog: bool,  // Fenêtre des tilesets introuvables
+    pub tileset_dialog: Option<TilesetDialog>,  // Retrait / remplacement d'un tileset
+    pub resize_dialog: Option<ResizeDialog>,  // Redimensionnement / décalage du niveau
     pub selection: SelectionData,  // Pour les outils de sélection
     history: History,  // Historique pour Undo/Redo
     pub clipboard: Option<SelectionContent>,  // Presse-papier pour copier/coller
+    pub stamp: Option<SelectionContent>,  // Tampon multi-tiles de l'outil Pinceau
+    pub brush_flags: TileFlags,  // Retournements / rotation du tile peint
+    pub tileset_drag: Option<(u32, u32)>,  // Rectangle en cours dans la grille du tileset (début, fin)
+    pub selected_terrain: Option<(usize, usize)>,  // (tileset, index du terrain) du pinceau de terrain
+    pub terrain_name: String,  // Nom du terrain à créer
+    pub selected_object: Option<u32>,  // Objet sélectionné (calque d'objets actif)
+    pub object_type_brush: String,  // Type des objets créés par double-clic
+    pub logic_brush: u8,  // Valeur peinte sur un calque de collision
+    pub validation: ValidationSettings,  // Contrôles et règles du projet
+    pub show_problems: bool,  // Panneau des problèmes
+    pub problems: Vec<Problem>,  // Résultat de la dernière vérification
+    pub focused_problem: Option<usize>,  // Problème dont les cases sont surlignées
+    pub reach_settings: ReachabilitySettings,  // Réglages de l'analyse de parcours
+    pub reach_result: Option<Reachability>,  // Dernière analyse du niveau courant (aperçu sur le canvas)
+    object_drag: Option<ObjectDrag>,
+    selection_drag: Option<SelectionDrag>,
+    chunk_cache: ChunkCache,  // Maillages des blocs de tiles déjà construits
+    fill_preview: Option<(FillPreviewKey, BTreeSet<(i32, i32)>)>,  // Zone survolée du pot ou de la baguette
 }
 
 impl EditorState {
     pub fn new() -> Self {
+        let level = Level::new("Nouveau Niveau".to_string(), 64, 48, 16);
         Self {
-            level: Level::new("Nouveau Niveau".to_string(), 64, 48, 16),
+            levels: vec![StoredLevel::new(level.clone(), DEFAULT_HISTORY_BUDGET)],
+            level,
+            current_level: 0,
+            world_view: None,
             current_tool: Tool::Paint,
+            fill_mode: FillMode::Contiguous,
             paint_mode: PaintMode::ColorTile([139, 69, 19]), // Marron par défaut
             current_layer: 1,
             zoom: 1.0,
             offset: Vec2::ZERO,
             show_grid: true,
-            is_dragging: false,
+            parallax_preview: false,
             last_painted: None,
             asset_manager: AssetManager::new(),
             selected_tileset: None,
-            tileset_scroll: 0.0,
             selected_color: [139, 69, 19],
             color_hex_input: "#8B4513".to_string(),
+            tile_property_name: String::new(),
+            tile_property_type: "bool".to_string(),
             notification: None,
             last_loaded_file: None,
             show_layer_config: false,
-            selection: SelectionData { start: None, end: None, is_active: false },
-            history: History::new(50),  // Max 50 étapes d'historique
+            show_relocate_dialog: false,
+            tileset_dialog: None,
+            resize_dialog: None,
+            selection: SelectionData::new(),
+            history: History::new(DEFAULT_HISTORY_BUDGET),
             clipboard: None,
+            stamp: None,
+            brush_flags: TileFlags::NONE,
+            tileset_drag: None,
+            selected_terrain: None,
+            terrain_name: String::new(),
+            selected_object: None,
+            object_type_brush: "spawn".to_string(),
+            logic_brush: 1,
+            validation: ValidationSettings::default(),
+            show_problems: false,
+            problems: Vec::new(),
+            focused_problem: None,
+            reach_settings: ReachabilitySettings::default(),
+            reach_result: None,
+            object_drag: None,
+            selection_drag: None,
+            chunk_cache: ChunkCache::new(),
+            fill_preview: None,
         }
     }
 
@@ -153,6 +438,8 @@ impl EditorState {
         }
 
         // Gestion des raccourcis clavier
+        let typing = ui.ctx().wants_keyboard_input();
+        let on_object_layer = self.is_object_layer(self.current_layer);
         ui.input(|i| {
             // Ctrl+Z : Annuler
             if i.modifiers.command && i.key_pressed(egui::Key::Z) {
@@ -167,15 +454,56 @@ impl EditorState {
                 self.copy_selection();
             }
             // Ctrl+V : Coller (sera géré au clic)
-            // Delete : Supprimer la sélection
-            if i.key_pressed(egui::Key::Delete) {
-                self.delete_selection();
+            // Delete : Supprimer la sélection (ou l'objet sélectionné)
+            if i.key_pressed(egui::Key::Delete) && !typing {
+                if on_object_layer {
+                    self.delete_selected_object();
+                } else {
+                    self.delete_selection();
+                }
+            }
+            // X / Y / R : retourner ou tourner la sélection active (outil Sélection),
+            // sinon le pinceau (ou le tampon)
+            if !typing && !i.modifiers.command {
+                let on_selection = matches!(self.current_tool, Tool::Select | Tool::MagicWand)
+                    && self.selection.is_active()
+                    && !on_object_layer;
+                for (key, transform) in [
+                    (egui::Key::X, BrushTransform::FlipX),
+                    (egui::Key::Y, BrushTransform::FlipY),
+                    (egui::Key::R, BrushTransform::Rotate),
+                ] {
+                    if i.key_pressed(key) {
+                        if on_selection {
+                            self.transform_selection(transform);
+                        } else {
+                            self.transform_brush(transform);
+                        }
+                    }
+                }
+                // Flèches : décaler la sélection d'une case
+                if on_selection {
+                    for (key, dx, dy) in [
+                        (egui::Key::ArrowLeft, -1, 0),
+                        (egui::Key::ArrowRight, 1, 0),
+                        (egui::Key::ArrowUp, 0, -1),
+                        (egui::Key::ArrowDown, 0, 1),
+                    ] {
+                        if i.key_pressed(key) {
+                            self.move_selection(dx, dy);
+                        }
+                    }
+                }
+            }
+            // Ctrl+I : Inverser la sélection
+            if i.modifiers.command && i.key_pressed(egui::Key::I) && !on_object_layer {
+                self.invert_selection();
             }
-            // Escape : Annuler la sélection en cours
+            // Escape : Annuler la sélection en cours (et revenir au pinceau simple)
             if i.key_pressed(egui::Key::Escape) {
-                self.selection.start = None;
-                self.selection.end = None;
-                self.selection.is_active = false;
+                self.selection.clear();
+                self.selection_drag = None;
+                self.stamp = None;
             }
         });
 
@@ -191,114 +519,148 @@ impl EditorState {
         // Dessiner l'arrière-plan
         painter.rect_filled(canvas_rect, 0.0, Color32::from_rgb(30, 30, 30));
 
-        // Dessiner les tiles
-        for layer_idx in 0..self.level.layers.len() {
-            let layer = &self.level.layers[layer_idx];
-            if !layer.visible {
+        // Horloge des tiles animés
+        let elapsed_ms = (ui.input(|i| i.time) * 1000.0) as u64;
+        let mut has_animation = false;
+
+        // Cases visibles, limitées au niveau : seuls leurs blocs sont parcourus
+        let origin = canvas_center + self.offset;
+        let visible = self.visible_cells(canvas_rect, origin, tile_size);
+
+        // Dessiner les tiles (un maillage par bloc et par texture)
+        self.chunk_cache.sync_tilesets(&self.asset_manager);
+        for (layer_idx, layer) in self.level.layers.iter().enumerate() {
+            if !self.is_layer_shown(layer_idx) {
                 continue;
             }
+            // Hors aperçu, les calques autres que le calque actif sont estompés
+            let dimmed = !self.parallax_preview && layer_idx != self.current_layer;
+            let mut shade = layer.shade([255, 255, 255]);
+            if dimmed {
+                shade[3] = (shade[3] as u16 * 100 / 255) as u8;
+            }
+            let layer_origin = self.layer_origin(layer, canvas_center);
+            has_animation |= self.chunk_cache.draw_layer(
+                &painter,
+                layer_idx,
+                layer,
+                (self.level.width, self.level.height),
+                &self.asset_manager,
+                self.visible_cells(canvas_rect, layer_origin, tile_size),
+                layer_origin,
+                tile_size,
+                shade,
+                elapsed_ms,
+            );
+        }
 
-            for (&(tx, ty), &tile_data) in &layer.tiles {
-                let screen_pos = canvas_center
-                    + self.offset
-                    + Vec2::new(tx as f32 * tile_size, ty as f32 * tile_size);
+        // Dessiner les objets
+        self.draw_objects(&painter, canvas_center);
+
+        // Dessiner la grille (partie visible du niveau)
+        if self.show_grid && tile_size >= 4.0 && !visible.is_empty() {
+            let stroke = egui::Stroke::new(1.0, Color32::from_rgba_unmultiplied(100, 100, 100, 50));
+            let point = |x: i32, y: i32| origin + Vec2::new(x as f32, y as f32) * tile_size;
+            for gx in visible.min.0..=visible.max.0 + 1 {
+                painter.line_segment([point(gx, visible.min.1), point(gx, visible.max.1 + 1)], stroke);
+            }
+            for gy in visible.min.1..=visible.max.1 + 1 {
+                painter.line_segment([point(visible.min.0, gy), point(visible.max.0 + 1, gy)], stroke);
+            }
+        }
 
-                let tile_rect = Rect::from_min_size(
-                    Pos2::new(screen_pos.x, screen_pos.y),
+        // Dessiner la sélection active (à sa position de dépôt pendant un déplacement)
+        if self.selection.is_active() {
+            let (dx, dy) = self.selection_drag.as_ref().map_or((0, 0), |drag| drag.offset);
+            let cell_rect = |x: i32, y: i32| {
+                Rect::from_min_size(
+                    canvas_center + self.offset + Vec2::new((x + dx) as f32 * tile_size, (y + dy) as f32 * tile_size),
                     Vec2::new(tile_size, tile_size),
-                );
+                )
+            };
+            if let Some(drag) = &self.selection_drag {
+                let (ox, oy) = drag.content.origin;
+                for &((tx, ty), tile_data) in &drag.content.tiles {
+                    let rect = cell_rect(ox + tx, oy + ty);
+                    if canvas_rect.intersects(rect) {
+                        self.draw_tile_ghost(&painter, rect, tile_data);
+                    }
+                }
+            }
 
-                if canvas_rect.intersects(tile_rect) {
-                    let alpha = if layer_idx == self.current_layer { 255 } else { 100 };
-                    
-                    match tile_data {
-                        TileData::Color(rgb) => {
-                            painter.rect_filled(
-                                tile_rect,
-                                0.0,
-                                Color32::from_rgba_unmultiplied(rgb[0], rgb[1], rgb[2], alpha),
-                            );
-                        }
-                        TileData::Texture { tileset_id, tile_index } => {
-                            if let Some(tileset) = self.asset_manager.get_tileset(tileset_id) {
-                                // Calculer les coordonnées UV du tile
-                                let tile_x = tile_index % tileset.columns;
-                                let tile_y = tile_index / tileset.columns;
-                                
-                                let uv = Rect::from_min_max(
-                                    Pos2::new(
-                                        tile_x as f32 / tileset.columns as f32,
-                                        tile_y as f32 / tileset.rows as f32,
-                                    ),
-                                    Pos2::new(
-                                        (tile_x + 1) as f32 / tileset.columns as f32,
-                                        (tile_y + 1) as f32 / tileset.rows as f32,
-                                    ),
-                                );
-                                
-                                let tint = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
-                                painter.image(tileset.texture.id(), tile_rect, uv, tint);
-                            }
-                        }
+            // Contour du masque : un bord est tracé là où la case voisine n'est pas sélectionnée.
+            // Seules les cases visibles sont parcourues (masque inversé sur une grande carte).
+            let stroke = (3.0, Color32::from_rgb(255, 255, 0));
+            let cell_at = |pos: Pos2| {
+                let cell = (pos - origin) / tile_size;
+                (cell.x.floor() as i32 - dx, cell.y.floor() as i32 - dy)
+            };
+            for (x, y) in self.selection.cells_in(cell_at(canvas_rect.min), cell_at(canvas_rect.max)) {
+                let rect = cell_rect(x, y);
+                painter.rect_filled(rect, 0.0, Color32::from_rgba_unmultiplied(255, 255, 0, 30));
+                for (nx, ny, from, to) in [
+                    (x, y - 1, rect.left_top(), rect.right_top()),
+                    (x + 1, y, rect.right_top(), rect.right_bottom()),
+                    (x, y + 1, rect.left_bottom(), rect.right_bottom()),
+                    (x - 1, y, rect.left_top(), rect.left_bottom()),
+                ] {
+                    if !self.selection.contains(nx, ny) {
+                        painter.line_segment([from, to], stroke);
                     }
                 }
             }
         }
 
-        // Dessiner la grille
-        if self.show_grid {
-            let grid_color = Color32::from_rgba_unmultiplied(100, 100, 100, 50);
-            for gx in -20..40 {
-                for gy in -20..40 {
-                    let screen_pos = canvas_center
-                        + self.offset
-                        + Vec2::new(gx as f32 * tile_size, gy as f32 * tile_size);
-
-                    let tile_rect = Rect::from_min_size(
-                        Pos2::new(screen_pos.x, screen_pos.y),
-                        Vec2::new(tile_size, tile_size),
-                    );
-
-                    if canvas_rect.intersects(tile_rect) {
-                        painter.rect_stroke(tile_rect, 0.0, (1.0, grid_color));
+        // Cases du problème choisi dans le panneau des problèmes
+        if let Some(problem) = self.focused_problem.and_then(|index| self.problems.get(index)) {
+            if problem.level.is_none_or(|level| level == self.current_level) {
+                let stroke = (2.0, Color32::from_rgb(255, 60, 60));
+                for &(x, y) in &problem.cells {
+                    let rect = Rect::from_min_size(origin + Vec2::new(x as f32, y as f32) * tile_size, Vec2::splat(tile_size));
+                    if canvas_rect.intersects(rect) {
+                        painter.rect_filled(rect, 0.0, Color32::from_rgba_unmultiplied(255, 60, 60, 50));
+                        painter.rect_stroke(rect.shrink(1.0), 0.0, stroke);
                     }
                 }
             }
         }
 
-        // Dessiner la sélection active
-        if self.selection.is_active {
-            if let (Some(start), Some(end)) = (self.selection.start, self.selection.end) {
-                let min_x = start.0.min(end.0);
-                let max_x = start.0.max(end.0);
-                let min_y = start.1.min(end.1);
-                let max_y = start.1.max(end.1);
-                
-                for tx in min_x..=max_x {
-                    for ty in min_y..=max_y {
-                        let tile_rect = Rect::from_min_size(
-                            canvas_center
-                                + self.offset
-                                + Vec2::new(tx as f32 * tile_size, ty as f32 * tile_size),
-                            Vec2::new(tile_size, tile_size),
-                        );
-                        painter.rect_stroke(tile_rect, 0.0, (2.0, Color32::from_rgb(255, 255, 0)));
+        // Analyse de parcours : zone accessible, chemin trouvé, pièces inaccessibles
+        if let Some(result) = &self.reach_result {
+            let cell_rect = |(x, y): (i32, i32)| Rect::from_min_size(origin + Vec2::new(x as f32, y as f32) * tile_size, Vec2::splat(tile_size));
+            if !visible.is_empty() {
+                for y in visible.min.1..=visible.max.1 {
+                    for x in visible.min.0..=visible.max.0 {
+                        if result.reachable.contains(&(x, y)) {
+                            painter.rect_filled(cell_rect((x, y)), 0.0, Color32::from_rgba_unmultiplied(0, 200, 120, 40));
+                        }
                     }
                 }
-                
-                // Bordure externe de la sélection
-                let selection_rect = Rect::from_min_size(
-                    canvas_center
-                        + self.offset
-                        + Vec2::new(min_x as f32 * tile_size, min_y as f32 * tile_size),
-                    Vec2::new((max_x - min_x + 1) as f32 * tile_size, (max_y - min_y + 1) as f32 * tile_size),
-                );
-                painter.rect_stroke(selection_rect, 0.0, (3.0, Color32::from_rgb(255, 255, 0)));
+            }
+            if result.path.len() > 1 {
+                let points: Vec<Pos2> = result.path.iter().map(|&cell| cell_rect(cell).center()).collect();
+                painter.add(egui::Shape::line(points, (3.0, Color32::from_rgb(0, 220, 255))));
+            }
+            painter.circle_stroke(cell_rect(result.spawn).center(), tile_size * 0.4, (2.0, Color32::from_rgb(0, 255, 0)));
+            for &coin in &result.unreachable_coins {
+                let rect = cell_rect(coin);
+                painter.circle_stroke(rect.center(), tile_size * 0.45, (2.0, Color32::from_rgb(255, 60, 60)));
+                painter.line_segment([rect.left_top(), rect.right_bottom()], (2.0, Color32::from_rgb(255, 60, 60)));
             }
         }
 
-        // Gestion des outils
-        if response.hovered() {
+        // Gestion des outils (un calque d'objets n'accepte pas de tiles)
+        if self.parallax_preview {
+            painter.text(
+                canvas_rect.left_top() + Vec2::new(8.0, 8.0),
+                egui::Align2::LEFT_TOP,
+                "🎞 Aperçu parallaxe : clic molette pour se déplacer, outils désactivés",
+                egui::FontId::proportional(13.0),
+                Color32::from_rgb(220, 220, 220),
+            );
+        } else if on_object_layer {
+            self.handle_objects(ui, &response, canvas_center);
+        } else if response.hovered() {
             if let Some(pointer_pos) = response.hover_pos() {
                 let rel_pos = pointer_pos.to_vec2() - canvas_center.to_vec2() - self.offset;
                 let tile_x = (rel_pos.x / tile_size).floor() as i32;
@@ -327,6 +689,9 @@ impl EditorState {
                     Tool::Select => Color32::from_rgba_unmultiplied(255, 255, 0, 100),
                     Tool::LineFill => Color32::from_rgba_unmultiplied(0, 255, 255, 100),
                     Tool::RectFill => Color32::from_rgba_unmultiplied(255, 165, 0, 100),
+                    Tool::Fill => Color32::from_rgba_unmultiplied(0, 255, 0, 80),
+                    Tool::Terrain => Color32::from_rgba_unmultiplied(180, 120, 255, 150),
+                    Tool::MagicWand => Color32::from_rgba_unmultiplied(255, 255, 0, 60),
                 };
 
                 painter.rect_stroke(preview_rect, 0.0, (2.0, preview_color));
@@ -369,12 +734,84 @@ impl EditorState {
                     }
                 }
 
+                // Rectangle de sélection en cours (rouge s'il retire des cases)
+                if self.current_tool == Tool::Select {
+                    if let Some(start) = self.selection.start {
+                        let corner = |x: i32, y: i32| canvas_center + self.offset + Vec2::new(x as f32 * tile_size, y as f32 * tile_size);
+                        let rect = Rect::from_two_pos(
+                            corner(start.0.min(tile_x), start.1.min(tile_y)),
+                            corner(start.0.max(tile_x) + 1, start.1.max(tile_y) + 1),
+                        );
+                        let color = if self.selection.mode == SelectionMode::Subtract {
+                            Color32::from_rgb(255, 80, 80)
+                        } else {
+                            Color32::from_rgb(255, 255, 0)
+                        };
+                        painter.rect_stroke(rect, 0.0, (1.0, color));
+                    }
+                }
+
+                // Prévisualisation de la zone pour le pot de peinture et la baguette
+                // (recalculée seulement quand la case survolée ou le niveau change)
+                if matches!(self.current_tool, Tool::Fill | Tool::MagicWand) {
+                    let key = FillPreviewKey {
+                        cell: (tile_x, tile_y),
+                        tool: self.current_tool,
+                        fill_mode: self.fill_mode,
+                        level: self.current_level,
+                        layer: self.current_layer,
+                        tiles_revision: self.chunk_cache.revision(),
+                        selection_revision: self.selection.revision,
+                    };
+                    if self.fill_preview.as_ref().is_none_or(|(cached, _)| *cached != key) {
+                        let region = if self.current_tool == Tool::Fill {
+                            self.fill_cells(tile_x, tile_y)
+                        } else {
+                            self.fill_region(tile_x, tile_y)
+                        };
+                        self.fill_preview = Some((key, region.into_iter().collect()));
+                    }
+                    let region = self.fill_preview.as_ref().map(|(_, cells)| cells);
+                    for (tx, ty) in region.into_iter().flat_map(|cells| cells_in_rect(cells, visible.min, visible.max)) {
+                        let tile_rect = Rect::from_min_size(
+                            canvas_center
+                                + self.offset
+                                + Vec2::new(tx as f32 * tile_size, ty as f32 * tile_size),
+                            Vec2::new(tile_size, tile_size),
+                        );
+                        painter.rect_filled(tile_rect, 0.0, preview_color);
+                    }
+                }
+
                 painter.rect_stroke(preview_rect, 0.0, (2.0, preview_color));
                 
-                // Prévisualisation de la texture si mode texture
+                // Prévisualisation fantôme du tampon
                 if self.current_tool == Tool::Paint {
+                    if let Some(stamp) = &self.stamp {
+                        for &((dx, dy), tile_data) in &stamp.tiles {
+                            let rect = Rect::from_min_size(
+                                canvas_center
+                                    + self.offset
+                                    + Vec2::new((tile_x + dx) as f32 * tile_size, (tile_y + dy) as f32 * tile_size),
+                                Vec2::new(tile_size, tile_size),
+                            );
+                            self.draw_tile_ghost(&painter, rect, tile_data);
+                        }
+                        let stamp_rect = Rect::from_min_size(
+                            preview_rect.min,
+                            Vec2::new(stamp.width as f32 * tile_size, stamp.height as f32 * tile_size),
+                        );
+                        painter.rect_stroke(stamp_rect, 0.0, (2.0, preview_color));
+                    }
+                }
+
+                // Prévisualisation de la texture si mode texture
+                if self.current_tool == Tool::Paint && self.stamp.is_none() {
                     if let PaintMode::TextureTile { tileset_id, tile_index } = self.paint_mode {
                         if let Some(tileset) = self.asset_manager.get_tileset(tileset_id) {
+                            let tile_ref = TileRef { tileset_id, tile_index };
+                            has_animation |= self.asset_manager.is_animated(tile_ref);
+                            let tile_index = self.asset_manager.animated_tile(tile_ref, elapsed_ms).tile_index;
                             let tile_x = tile_index % tileset.columns;
                             let tile_y = tile_index / tileset.columns;
                             
@@ -389,10 +826,12 @@ impl EditorState {
                                 ),
                             );
                             
-                            painter.image(
+                            paint_tile_image(
+                                &painter,
                                 tileset.texture.id(),
                                 preview_rect,
                                 uv,
+                                self.brush_flags,
                                 Color32::from_rgba_unmultiplied(255, 255, 255, 200),
                             );
                         }
@@ -405,43 +844,79 @@ impl EditorState {
                 let secondary_click = response.dragged_by(egui::PointerButton::Secondary)
                     || response.clicked_by(egui::PointerButton::Secondary);
                 
+                // Maj : ajouter à la sélection, Alt : en retirer
+                let mode = ui.input(|i| SelectionMode::from_modifiers(&i.modifiers));
+
                 // Gestion du mode Sélection
                 if self.current_tool == Tool::Select {
                     // Vérifier si on veut coller avec Ctrl+V
                     let want_paste = ui.input(|i| i.modifiers.command && i.key_pressed(egui::Key::V));
                     
+                    // Case où le bouton a été enfoncé (le glissé démarre un peu plus loin)
+                    let press_tile = ui.input(|i| i.pointer.press_origin()).map(|pos| {
+                        let rel = pos.to_vec2() - canvas_center.to_vec2() - self.offset;
+                        ((rel.x / tile_size).floor() as i32, (rel.y / tile_size).floor() as i32)
+                    });
+
                     if want_paste && self.clipboard.is_some() {
                         // Coller à la position de la souris
                         self.paste_selection(tile_x, tile_y);
-                    } else if response.clicked_by(egui::PointerButton::Primary) {
-                        if !self.selection.is_active {
-                            // Premier clic : démarrer la sélection
-                            self.selection.start = Some((tile_x, tile_y));
-                            self.selection.end = Some((tile_x, tile_y));
-                            self.selection.is_active = false;
+                    } else if let Some(drag) = &mut self.selection_drag {
+                        // Glisser la sélection : dépôt au relâchement
+                        drag.offset = (tile_x - drag.grab.0, tile_y - drag.grab.1);
+                        if !response.dragged() {
+                            let (dx, dy) = drag.offset;
+                            self.selection_drag = None;
+                            self.move_selection(dx, dy);
+                        }
+                    } else if let (Some((px, py)), true) =
+                        (press_tile, response.drag_started_by(egui::PointerButton::Primary))
+                    {
+                        if mode == SelectionMode::Replace && self.selection.contains(px, py) {
+                            // Glisser depuis l'intérieur de la sélection : la déplacer
+                            if let Some(content) = self.selection_content() {
+                                self.selection_drag = Some(SelectionDrag { grab: (px, py), offset: (0, 0), content });
+                            }
                         } else {
-                            // Clic en dehors : créer nouvelle sélection
-                            self.selection.start = Some((tile_x, tile_y));
+                            // Nouveau rectangle, combiné au masque au relâchement
+                            self.selection.start = Some((px, py));
                             self.selection.end = Some((tile_x, tile_y));
-                            self.selection.is_active = false;
+                            self.selection.mode = mode;
                         }
                     } else if response.dragged() && self.selection.start.is_some() {
                         // Mise à jour en temps réel pendant le drag
                         self.selection.end = Some((tile_x, tile_y));
                     } else if response.drag_stopped() && self.selection.start.is_some() {
-                        // Fin du drag : activer la sélection
+                        // Fin du drag : combiner le rectangle avec la sélection
                         self.selection.end = Some((tile_x, tile_y));
-                        self.selection.is_active = true;
-                        
-                        if let (Some(start), Some(end)) = (self.selection.start, self.selection.end) {
-                            let count = ((start.0 - end.0).abs() + 1) * ((start.1 - end.1).abs() + 1);
-                            self.show_notification(format!("📦 {} tiles sélectionnés", count));
+                        self.commit_selection_rect();
+                    } else if response.clicked_by(egui::PointerButton::Primary) {
+                        if self.selection.start.is_none() {
+                            // Premier clic : coin de départ
+                            self.selection.start = Some((tile_x, tile_y));
+                            self.selection.end = Some((tile_x, tile_y));
+                            self.selection.mode = mode;
+                        } else {
+                            // Second clic : coin opposé
+                            self.selection.end = Some((tile_x, tile_y));
+                            self.commit_selection_rect();
                         }
                     } else if response.clicked_by(egui::PointerButton::Secondary) {
                         // Clic droit : annuler la sélection
-                        self.selection.start = None;
-                        self.selection.end = None;
-                        self.selection.is_active = false;
+                        self.selection.clear();
+                    }
+                } else if self.current_tool == Tool::MagicWand {
+                    // Baguette magique : tiles identiques (contigus ou de tout le calque)
+                    if response.clicked_by(egui::PointerButton::Primary) {
+                        let region = self.fill_region(tile_x, tile_y);
+                        if region.is_empty() {
+                            self.show_notification("❌ Hors des limites du niveau".to_string());
+                        } else {
+                            self.selection.combine(region, mode);
+                            self.show_notification(format!("🪄 {} tiles sélectionnés", self.selection.mask().len()));
+                        }
+                    } else if response.clicked_by(egui::PointerButton::Secondary) {
+                        self.selection.clear();
                     }
                 } else if self.current_tool == Tool::LineFill || self.current_tool == Tool::RectFill {
                     // Gestion des outils de remplissage LineFill et RectFill
@@ -452,7 +927,6 @@ impl EditorState {
                         } else {
                             // Deuxième clic : remplir la zone
                             if let Some(start) = self.selection.start {
-                                self.save_history();  // Sauvegarder avant modification
                                 let (start_x, start_y) = start;
                                 
                                 // Calculer la zone à remplir
@@ -469,25 +943,22 @@ impl EditorState {
                                      start_y.min(tile_y), start_y.max(tile_y))
                                 };
                                 
-                                // Remplir la zone
-                                if let Some(layer) = self.level.layers.get_mut(self.current_layer) {
-                                    let tile_data = match self.paint_mode {
-                                        PaintMode::ColorTile(rgb) => TileData::Color(rgb),
-                                        PaintMode::TextureTile { tileset_id, tile_index } => {
-                                            TileData::Texture { tileset_id, tile_index }
-                                        }
-                                    };
-                                    
-                                    for tx in min_x..=max_x {
-                                        for ty in min_y..=max_y {
-                                            layer.set_tile(tx, ty, tile_data);
+                                // Remplir la zone (limitée à la sélection active)
+                                let tile_data = self.brush_tile();
+
+                                let mut cells = Vec::new();
+                                for tx in min_x..=max_x {
+                                    for ty in min_y..=max_y {
+                                        if !self.selection.is_active() || self.selection.contains(tx, ty) {
+                                            cells.push(((tx, ty), tile_data));
                                         }
                                     }
-                                    
-                                    let count = ((max_x - min_x + 1) * (max_y - min_y + 1)) as usize;
-                                    let tool_name = if self.current_tool == Tool::LineFill { "ligne" } else { "rectangle" };
-                                    self.show_notification(format!("✅ {} tiles remplis en {}", count, tool_name));
                                 }
+                                let count = cells.len();
+                                self.apply_tiles(cells);
+
+                                let tool_name = if self.current_tool == Tool::LineFill { "ligne" } else { "rectangle" };
+                                self.show_notification(format!("✅ {} tiles remplis en {}", count, tool_name));
                                 
                                 // Réinitialiser la sélection
                                 self.selection.start = None;
@@ -499,42 +970,59 @@ impl EditorState {
                         self.selection.start = None;
                         self.selection.end = None;
                     }
+                } else if self.current_tool == Tool::Fill {
+                    // Pot de peinture : clic gauche remplit, clic droit vide la zone
+                    if response.clicked_by(egui::PointerButton::Primary)
+                        || response.clicked_by(egui::PointerButton::Secondary)
+                    {
+                        let tile_data = if response.clicked_by(egui::PointerButton::Secondary) {
+                            TileData::empty()
+                        } else {
+                            self.brush_tile()
+                        };
+                        self.fill_at(tile_x, tile_y, tile_data);
+                    }
+                } else if self.current_tool == Tool::Terrain {
+                    // Pinceau de terrain : clic gauche peint, clic droit efface (voisins recalculés)
+                    if (primary_click || secondary_click) && self.last_painted != Some((tile_x, tile_y)) {
+                        self.paint_terrain(tile_x, tile_y, primary_click);
+                        self.last_painted = Some((tile_x, tile_y));
+                    }
                 } else if primary_click || secondary_click {
                     // Comportement normal pour les autres outils
                     if self.last_painted != Some((tile_x, tile_y)) {
-                        // Sauvegarder l'historique au premier clic
-                        if response.clicked() && self.last_painted.is_none() {
-                            self.save_history();
-                        }
-                        
-                        if let Some(layer) = self.level.layers.get_mut(self.current_layer) {
-                            // Logique inversée : clic droit = gomme par défaut
-                            // Si outil Gomme sélectionné : clic gauche = gomme, clic droit = paint
-                            let should_erase = if self.current_tool == Tool::Erase {
-                                primary_click  // Avec gomme active : clic gauche efface
-                            } else {
-                                secondary_click  // Sinon : clic droit efface
-                            };
-                            
-                            let should_paint = if self.current_tool == Tool::Erase {
-                                secondary_click  // Avec gomme active : clic droit peint
-                            } else {
-                                primary_click  // Sinon : clic gauche peint
-                            };
-                            
-                            if should_erase {
-                                layer.set_tile(tile_x, tile_y, TileData::empty());
-                            } else if should_paint && self.current_tool != Tool::Select {
-                                let tile_data = match self.paint_mode {
-                                    PaintMode::ColorTile(rgb) => {
-                                        TileData::Color(rgb)
-                                    }
-                                    PaintMode::TextureTile { tileset_id, tile_index } => {
-                                        TileData::Texture { tileset_id, tile_index }
-                                    }
-                                };
-                                layer.set_tile(tile_x, tile_y, tile_data);
+                        // Logique inversée : clic droit = gomme par défaut
+                        // Si outil Gomme sélectionné : clic gauche = gomme, clic droit = paint
+                        let should_erase = if self.current_tool == Tool::Erase {
+                            primary_click  // Avec gomme active : clic gauche efface
+                        } else {
+                            secondary_click  // Sinon : clic droit efface
+                        };
+
+                        let should_paint = if self.current_tool == Tool::Erase {
+                            secondary_click  // Avec gomme active : clic droit peint
+                        } else {
+                            primary_click  // Sinon : clic gauche peint
+                        };
+
+                        if should_erase {
+                            self.paint_stroke(tile_x, tile_y, TileData::empty());
+                        } else if should_paint && self.current_tool == Tool::Paint && self.stamp.is_some() {
+                            if let Some(stamp) = self.stamp.take() {
+                                for &((dx, dy), tile_data) in &stamp.tiles {
+                                    self.paint_stroke(tile_x + dx, tile_y + dy, tile_data);
+                                }
+                                let layer = self.level.layers.get(self.current_layer);
+                                if stamp.tiles.iter().any(|(_, tile_data)| !layer.is_some_and(|l| l.accepts(tile_data))) {
+                                    self.show_notification(
+                                        "⚠️ Tiles du tampon ignorés : type incompatible avec le calque".to_string(),
+                                    );
+                                }
+                                self.stamp = Some(stamp);
                             }
+                        } else if should_paint && self.current_tool != Tool::Select {
+                            let tile_data = self.brush_tile();
+                            self.paint_stroke(tile_x, tile_y, tile_data);
                         }
                         self.last_painted = Some((tile_x, tile_y));
                     }
@@ -542,9 +1030,134 @@ impl EditorState {
             }
         }
 
-        if response.drag_stopped() {
+        // Les tiles animés demandent un rafraîchissement continu
+        if has_animation {
+            ui.ctx().request_repaint();
+        }
+
+        // Bouton relâché : le trait de pinceau est terminé (une seule entrée d'historique)
+        if response.drag_stopped() || !ui.input(|i| i.pointer.any_down()) {
             self.last_painted = None;
+            self.history.end_stroke();
+        }
+    }
+
+    /// Carte du monde : glisser un niveau le déplace (aligné sur ses tiles),
+    /// un double-clic l'ouvre, clic molette pour naviguer et molette pour zoomer
+    pub fn draw_world_view(&mut self, ui: &mut egui::Ui) {
+        let Some(mut view) = self.world_view else {
+            return;
+        };
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        let canvas_rect = response.rect;
+        painter.rect_filled(canvas_rect, 0.0, Color32::from_gray(25));
+
+        if response.hovered() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                view.zoom = (view.zoom * (1.0 + scroll * 0.001)).clamp(0.02, 4.0);
+            }
+        }
+        if response.dragged_by(egui::PointerButton::Middle) {
+            view.offset += response.drag_delta();
+        }
+
+        // Rectangles des niveaux à l'écran
+        let origin = canvas_rect.min + view.offset;
+        let screen_rects: Vec<Rect> = (0..self.levels.len())
+            .map(|index| {
+                let level = self.level_at(index);
+                let (x, y) = level.world_position;
+                let size = Vec2::new((level.width * level.tile_size) as f32, (level.height * level.tile_size) as f32);
+                Rect::from_min_size(origin + Vec2::new(x as f32, y as f32) * view.zoom, size * view.zoom)
+            })
+            .collect();
+        // Le dernier niveau dessiné est au-dessus : il est prioritaire sous le pointeur
+        let level_under = |pos: Pos2| screen_rects.iter().rposition(|rect| rect.contains(pos));
+
+        let pointer = response.interact_pointer_pos();
+        if let Some(pos) = pointer.filter(|_| response.drag_started_by(egui::PointerButton::Primary)) {
+            view.drag = level_under(pos).map(|index| WorldDrag {
+                level: index,
+                grab: pos,
+                origin: self.level_at(index).world_position,
+            });
+        }
+        if let (Some(drag), Some(pos)) = (view.drag, pointer) {
+            if response.dragged_by(egui::PointerButton::Primary) {
+                let tile_size = self.level_at(drag.level).tile_size.max(1) as f32;
+                let delta = (pos - drag.grab) / view.zoom;
+                let snap = |d: f32| ((d / tile_size).round() * tile_size) as i32;
+                self.set_level_world_position(drag.level, (drag.origin.0 + snap(delta.x), drag.origin.1 + snap(delta.y)));
+            }
+        }
+        if response.drag_stopped() {
+            view.drag = None;
+        }
+
+        let mut open = false;
+        if response.clicked() || response.double_clicked() {
+            if let Some(index) = pointer.and_then(level_under) {
+                self.switch_level(index);
+                open = response.double_clicked();
+            }
+        }
+
+        for (index, screen_rect) in screen_rects.iter().enumerate() {
+            if !canvas_rect.intersects(*screen_rect) {
+                continue;
+            }
+            let level = self.level_at(index);
+            painter.rect_filled(*screen_rect, 0.0, Color32::from_gray(45));
+
+            // Tiles dessinés seulement s'ils restent lisibles
+            let tile_px = level.tile_size as f32 * view.zoom;
+            if tile_px >= 2.0 {
+                // Les calques de collision n'ont pas de rendu sur la carte du monde
+                let layers = level.layers.iter().enumerate().filter(|&(i, layer)| {
+                    level.is_layer_visible(i) && layer.kind != LayerKind::Logic
+                });
+                for (_, layer) in layers {
+                    for (&(tx, ty), &tile_data) in &layer.tiles {
+                        if !level.in_bounds(tx, ty) {
+                            continue;
+                        }
+                        let tile_rect = Rect::from_min_size(
+                            screen_rect.min + Vec2::new(tx as f32, ty as f32) * tile_px,
+                            Vec2::splat(tile_px),
+                        );
+                        if canvas_rect.intersects(tile_rect) {
+                            self.draw_tile(&painter, tile_rect, tile_data, 255);
+                        }
+                    }
+                }
+            }
+
+            let is_current = index == self.current_level;
+            let stroke = if is_current {
+                egui::Stroke::new(2.0, Color32::YELLOW)
+            } else {
+                egui::Stroke::new(1.0, Color32::GRAY)
+            };
+            painter.rect_stroke(*screen_rect, 0.0, stroke);
+            painter.text(
+                screen_rect.left_top() - Vec2::new(0.0, 2.0),
+                egui::Align2::LEFT_BOTTOM,
+                format!("{} ({}x{})", level.name, level.width, level.height),
+                egui::FontId::proportional(13.0),
+                if is_current { Color32::YELLOW } else { Color32::LIGHT_GRAY },
+            );
         }
+
+        painter.text(
+            canvas_rect.left_bottom() + Vec2::new(8.0, -8.0),
+            egui::Align2::LEFT_BOTTOM,
+            "Glisser : déplacer un niveau | Double-clic : ouvrir | Clic molette : naviguer",
+            egui::FontId::proportional(12.0),
+            Color32::GRAY,
+        );
+
+        self.world_view = if open { None } else { Some(view) };
     }
 
     pub fn show_notification(&mut self, message: String) {
@@ -560,106 +1173,1145 @@ impl EditorState {
         }
     }
 
-    /// Sauvegarde l'état actuel dans l'historique
-    pub fn save_history(&mut self) {
-        let state = HistoryState {
-            layers: self.level.layers.clone(),
-            current_layer: self.current_layer,
+    /// Calcule la zone touchée par le pot de peinture en (x, y) selon le mode actif
+    pub fn fill_region(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        let Some(layer) = self.level.layers.get(self.current_layer) else {
+            return Vec::new();
         };
-        self.history.push(state);
+        match self.fill_mode {
+            FillMode::Contiguous => layer.flood_region(x, y, self.level.width, self.level.height),
+            FillMode::Global => layer.matching_region(x, y, self.level.width, self.level.height),
+        }
     }
 
-    /// Annule la dernière action (Ctrl+Z)
-    pub fn undo(&mut self) {
-        if let Some(previous_state) = self.history.undo() {
-            // Sauvegarder l'état actuel dans la pile redo avant de le remplacer
-            let current_state = HistoryState {
-                layers: self.level.layers.clone(),
-                current_layer: self.current_layer,
-            };
-            self.history.push_redo(current_state);
-            
-            // Restaurer l'état précédent
-            self.level.layers = previous_state.layers;
-            self.current_layer = previous_state.current_layer;
-            self.show_notification("↶ Annulation".to_string());
-        } else {
-            self.show_notification("❌ Rien à annuler".to_string());
+    /// Zone remplie par le pot de peinture : celle de `fill_region`, limitée
+    /// à la sélection active s'il y en a une
+    pub fn fill_cells(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        let mut region = self.fill_region(x, y);
+        if self.selection.is_active() {
+            region.retain(|&(cx, cy)| self.selection.contains(cx, cy));
         }
+        region
     }
 
-    /// Refait la dernière action annulée (Ctrl+Y)
-    pub fn redo(&mut self) {
-        if let Some(next_state) = self.history.redo() {
-            // Sauvegarder l'état actuel dans la pile undo
-            let current_state = HistoryState {
-                layers: self.level.layers.clone(),
-                current_layer: self.current_layer,
-            };
-            self.history.push(current_state);
-            
-            // Restaurer l'état suivant
-            self.level.layers = next_state.layers;
-            self.current_layer = next_state.current_layer;
-            self.show_notification("↷ Rétablir".to_string());
-        } else {
-            self.show_notification("❌ Rien à rétablir".to_string());
+    /// Remplit la zone en (x, y) avec un tile (une seule étape d'historique)
+    pub fn fill_at(&mut self, x: i32, y: i32, tile_data: TileData) {
+        let Some(layer) = self.level.layers.get(self.current_layer) else {
+            return;
+        };
+        if layer.get_tile(x, y) == tile_data {
+            return;
+        }
+
+        let region = self.fill_cells(x, y);
+        if region.is_empty() {
+            let message = if self.selection.is_active() { "❌ Hors de la sélection" } else { "❌ Hors des limites du niveau" };
+            self.show_notification(message.to_string());
+            return;
         }
+
+        let count = self.apply_tiles(region.into_iter().map(|pos| (pos, tile_data)).collect());
+        self.show_notification(format!("🪣 {} tiles remplis", count));
     }
 
-    /// Copie la sélection dans le presse-papier (Ctrl+C)
-    pub fn copy_selection(&mut self) {
-        if self.selection.is_active {
-            if let (Some(start), Some(end)) = (self.selection.start, self.selection.end) {
-                let min_x = start.0.min(end.0);
-                let max_x = start.0.max(end.0);
-                let min_y = start.1.min(end.1);
-                let max_y = start.1.max(end.1);
-                
-                let mut tiles = Vec::new();
-                if let Some(layer) = self.level.layers.get(self.current_layer) {
-                    for tx in min_x..=max_x {
-                        for ty in min_y..=max_y {
-                            if let Some(&tile_data) = layer.tiles.get(&(tx, ty)) {
-                                if !tile_data.is_empty() {
-                                    tiles.push(((tx - min_x, ty - min_y), tile_data));
-                                }
-                            }
-                        }
+    /// Indique si le calque donné est un calque d'objets
+    pub fn is_object_layer(&self, index: usize) -> bool {
+        self.level
+            .layers
+            .get(index)
+            .is_some_and(|layer| layer.kind == LayerKind::Objects)
+    }
+
+    /// Objet sélectionné dans le calque actif
+    pub fn selected_object(&self) -> Option<&LevelObject> {
+        let id = self.selected_object?;
+        self.level
+            .layers
+            .get(self.current_layer)?
+            .objects
+            .iter()
+            .find(|object| object.id == id)
+    }
+
+    /// Remplace un objet du calque actif (retouche depuis l'inspecteur)
+    pub fn update_object(&mut self, object: LevelObject) {
+        let layer_index = self.current_layer;
+        if self.refuse_locked(layer_index) {
+            return;
+        }
+        let Some(layer) = self.level.layers.get_mut(layer_index) else {
+            return;
+        };
+        let Some(slot) = layer.objects.iter().position(|o| o.id == object.id) else {
+            return;
+        };
+        if layer.objects[slot] == object {
+            return;
+        }
+        let before = layer.objects.clone();
+        layer.objects[slot] = object.clone();
+        let after = layer.objects.clone();
+        self.history.record_object_edit(layer_index, object.id, before, after);
+    }
+
+    /// Crée un objet dans le calque actif et le sélectionne
+    pub fn create_object(&mut self, x: f32, y: f32) {
+        if self.refuse_locked(self.current_layer) {
+            return;
+        }
+        let id = self.level.next_object_id();
+        let size = self.level.tile_size as f32;
+        let object = LevelObject::new(id, &self.object_type_brush, x, y, size, size);
+        let layer_index = self.current_layer;
+        if let Some(layer) = self.level.layers.get_mut(layer_index) {
+            let before = layer.objects.clone();
+            layer.objects.push(object);
+            let after = layer.objects.clone();
+            self.history.push(EditCommand::Objects { layer: layer_index, before, after }, layer_index, layer_index);
+            self.selected_object = Some(id);
+            self.show_notification(format!("🎯 Objet #{} créé", id));
+        }
+    }
+
+    /// Supprime l'objet sélectionné (Delete)
+    pub fn delete_selected_object(&mut self) {
+        if self.refuse_locked(self.current_layer) {
+            return;
+        }
+        let Some(id) = self.selected_object.take() else {
+            self.show_notification("❌ Aucun objet sélectionné".to_string());
+            return;
+        };
+        let layer_index = self.current_layer;
+        if let Some(layer) = self.level.layers.get_mut(layer_index) {
+            let before = layer.objects.clone();
+            layer.objects.retain(|object| object.id != id);
+            if layer.objects.len() != before.len() {
+                let after = layer.objects.clone();
+                self.history.push(EditCommand::Objects { layer: layer_index, before, after }, layer_index, layer_index);
+                self.show_notification(format!("🗑️ Objet #{} supprimé", id));
+            }
+        }
+    }
+
+    /// Cases d'un calque visibles dans `canvas_rect`, limitées au niveau
+    fn visible_cells(&self, canvas_rect: Rect, origin: Pos2, tile_size: f32) -> CellRange {
+        let cell_at = |pos: Pos2| {
+            let cell = (pos - origin) / tile_size;
+            (cell.x.floor() as i32, cell.y.floor() as i32)
+        };
+        let (min, max) = (cell_at(canvas_rect.min), cell_at(canvas_rect.max));
+        CellRange {
+            min: (min.0.max(0), min.1.max(0)),
+            max: (max.0.min(self.level.width as i32 - 1), max.1.min(self.level.height as i32 - 1)),
+        }
+    }
+
+    /// Position à l'écran de la case (0, 0) d'un calque. En aperçu, le calque suit
+    /// le déplacement de la vue selon son facteur de parallaxe, puis son décalage.
+    fn layer_origin(&self, layer: &Layer, canvas_center: Pos2) -> Pos2 {
+        if !self.parallax_preview {
+            return canvas_center + self.offset;
+        }
+        let parallax = Vec2::new(layer.parallax.0, layer.parallax.1);
+        let offset = Vec2::new(layer.offset.0, layer.offset.1) * self.zoom;
+        canvas_center + self.offset * parallax + offset
+    }
+
+    /// Dessine les objets des calques d'objets visibles
+    fn draw_objects(&self, painter: &egui::Painter, canvas_center: Pos2) {
+        for (layer_idx, layer) in self.level.layers.iter().enumerate() {
+            if !self.is_layer_shown(layer_idx) || layer.kind != LayerKind::Objects {
+                continue;
+            }
+            let origin = self.layer_origin(layer, canvas_center);
+            let to_screen = |(x, y): (f32, f32)| origin + Vec2::new(x, y) * self.zoom;
+            let is_current = layer_idx == self.current_layer;
+
+            for object in &layer.objects {
+                let color = object_color(&object.object_type);
+                let alpha = (if is_current { 90.0 } else { 40.0 } * layer.opacity) as u8;
+                let points: Vec<Pos2> = object.corners().into_iter().map(to_screen).collect();
+                let is_selected = is_current && self.selected_object == Some(object.id);
+                let stroke_color = if is_selected { Color32::YELLOW } else { color };
+
+                painter.add(egui::Shape::convex_polygon(
+                    points.clone(),
+                    Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha),
+                    (if is_selected { 2.0 } else { 1.0 }, stroke_color),
+                ));
+                painter.text(
+                    points[0] + Vec2::new(2.0, -2.0),
+                    egui::Align2::LEFT_BOTTOM,
+                    &object.name,
+                    egui::FontId::proportional(11.0),
+                    stroke_color,
+                );
+
+                // Poignée de redimensionnement (coin bas-droit)
+                if is_selected {
+                    painter.rect_filled(
+                        Rect::from_center_size(points[2], Vec2::splat(OBJECT_HANDLE_SIZE)),
+                        1.0,
+                        Color32::YELLOW,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Interactions sur un calque d'objets : sélection, déplacement,
+    /// redimensionnement (poignée) et création (double-clic)
+    fn handle_objects(&mut self, ui: &egui::Ui, response: &egui::Response, canvas_center: Pos2) {
+        let (offset, zoom) = (self.offset, self.zoom);
+        let to_world = move |pos: Pos2| (pos - canvas_center - offset) / zoom;
+        let layer_index = self.current_layer;
+
+        if response.drag_started_by(egui::PointerButton::Primary) {
+            let Some(origin) = ui.input(|i| i.pointer.press_origin()) else {
+                return;
+            };
+            if self.refuse_locked(layer_index) {
+                return;
+            }
+            let start = to_world(origin);
+            let handle_radius = OBJECT_HANDLE_SIZE / self.zoom;
+
+            // Poignée de l'objet sélectionné, sinon objet sous le curseur
+            let on_handle = self.selected_object().filter(|object| {
+                let (hx, hy) = object.to_world(object.width, object.height);
+                (Vec2::new(hx, hy) - start).length() <= handle_radius
+            });
+            let target = on_handle.cloned().map(|object| (object, true)).or_else(|| {
+                self.level.layers[layer_index]
+                    .object_at(start.x, start.y)
+                    .cloned()
+                    .map(|object| (object, false))
+            });
+
+            match target {
+                Some((object, resize)) => {
+                    self.selected_object = Some(object.id);
+                    self.object_drag = Some(ObjectDrag {
+                        start,
+                        resize,
+                        original: object,
+                        before: self.level.layers[layer_index].objects.clone(),
+                    });
+                }
+                None => self.selected_object = None,
+            }
+        }
+
+        if response.dragged_by(egui::PointerButton::Primary) {
+            if let (Some(drag), Some(pointer)) = (&self.object_drag, response.interact_pointer_pos()) {
+                let world = to_world(pointer);
+                let mut object = drag.original.clone();
+                if drag.resize {
+                    let (lx, ly) = drag.original.to_local(world.x, world.y);
+                    object.width = lx.max(1.0);
+                    object.height = ly.max(1.0);
+                } else {
+                    let delta = world - drag.start;
+                    object.x += delta.x;
+                    object.y += delta.y;
+                }
+                if let Some(slot) = self.level.layers[layer_index]
+                    .objects
+                    .iter_mut()
+                    .find(|o| o.id == object.id)
+                {
+                    *slot = object;
+                }
+            }
+        }
+
+        if response.drag_stopped() {
+            if let Some(drag) = self.object_drag.take() {
+                let after = self.level.layers[layer_index].objects.clone();
+                if after != drag.before {
+                    self.history.push(
+                        EditCommand::Objects { layer: layer_index, before: drag.before, after },
+                        layer_index,
+                        layer_index,
+                    );
+                }
+            }
+        }
+
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let world = to_world(pointer);
+            if response.double_clicked_by(egui::PointerButton::Primary) {
+                if self.level.layers[layer_index].object_at(world.x, world.y).is_none() {
+                    // Nouvel objet calé sur la case sous le curseur
+                    let tile = self.level.tile_size as f32;
+                    self.create_object((world.x / tile).floor() * tile, (world.y / tile).floor() * tile);
+                }
+            } else if response.clicked_by(egui::PointerButton::Primary) {
+                self.selected_object = self.level.layers[layer_index]
+                    .object_at(world.x, world.y)
+                    .map(|object| object.id);
+            } else if response.clicked_by(egui::PointerButton::Secondary) {
+                self.selected_object = None;
+            }
+        }
+    }
+
+    /// Modifie des cases du calque actif en une seule étape d'historique
+    /// et retourne le nombre de cases réellement changées
+    pub fn apply_tiles(&mut self, cells: Vec<((i32, i32), TileData)>) -> usize {
+        if self.refuse_locked(self.current_layer) {
+            return 0;
+        }
+        let Some(layer) = self.level.layers.get_mut(self.current_layer) else {
+            return 0;
+        };
+
+        let mut changes = Vec::new();
+        for ((x, y), after) in cells {
+            let before = layer.get_tile(x, y);
+            if before != after && layer.accepts(&after) {
+                layer.set_tile(x, y, after);
+                self.chunk_cache.invalidate_cell(self.current_layer, x, y);
+                changes.push(((x, y), before, after));
+            }
+        }
+
+        let count = changes.len();
+        if count > 0 {
+            self.history.push(
+                EditCommand::Tiles { layer: self.current_layer, changes },
+                self.current_layer,
+                self.current_layer,
+            );
+        }
+        count
+    }
+
+    /// Peint une case pendant un trait : les cases d'un même glissé
+    /// sont fusionnées dans une seule entrée d'historique
+    fn paint_stroke(&mut self, x: i32, y: i32, tile_data: TileData) {
+        if self.refuse_locked(self.current_layer) {
+            return;
+        }
+        if let Some(layer) = self.level.layers.get_mut(self.current_layer) {
+            let before = layer.get_tile(x, y);
+            if before != tile_data && layer.accepts(&tile_data) {
+                layer.set_tile(x, y, tile_data);
+                self.chunk_cache.invalidate_cell(self.current_layer, x, y);
+                self.history.record_stroke(self.current_layer, ((x, y), before, tile_data));
+            }
+        }
+    }
+
+    /// Ajoute un calque de tiles après les autres et le rend actif
+    pub fn add_layer(&mut self, name: String) {
+        self.push_layer(Layer::new(name));
+    }
+
+    /// Ajoute un calque d'objets après les autres et le rend actif
+    pub fn add_object_layer(&mut self, name: String) {
+        self.push_layer(Layer::new_objects(name));
+    }
+
+    /// Ajoute un calque de collision après les autres et le rend actif
+    pub fn add_logic_layer(&mut self, name: String) {
+        self.push_layer(Layer::new_logic(name));
+    }
+
+    /// Indique si le calque donné est un calque de collision
+    pub fn is_logic_layer(&self, index: usize) -> bool {
+        self.level.layers.get(index).is_some_and(|layer| layer.kind == LayerKind::Logic)
+    }
+
+    /// Remplace les valeurs (noms, couleurs) d'un calque de collision
+    pub fn set_logic_values(&mut self, index: usize, values: Vec<LogicValue>) {
+        if let Some(layer) = self.level.layers.get_mut(index) {
+            if layer.logic_values != values {
+                layer.logic_values = values;
+                self.chunk_cache.invalidate();
+            }
+        }
+    }
+
+    fn push_layer(&mut self, layer: Layer) {
+        let index = self.level.layers.len();
+        self.level.layers.push(layer.clone());
+        self.chunk_cache.invalidate();
+        self.history.push(EditCommand::AddLayer { index, layer }, self.current_layer, index);
+        self.current_layer = index;
+    }
+
+    /// Supprime un calque (il en reste toujours au moins un)
+    pub fn remove_layer(&mut self, index: usize) -> bool {
+        let Some(layer) = self.level.layers.get(index).cloned() else {
+            return false;
+        };
+        if !self.level.remove_layer(index) {
+            return false;
+        }
+        self.chunk_cache.invalidate();
+        let before = self.current_layer;
+        if self.current_layer >= self.level.layers.len() {
+            self.current_layer = self.level.layers.len().saturating_sub(1);
+        }
+        self.history.push(EditCommand::RemoveLayer { index, layer }, before, self.current_layer);
+        true
+    }
+
+    /// Déplace un calque d'un cran (vers le haut si `up`) et le garde actif
+    pub fn move_layer(&mut self, index: usize, up: bool) -> bool {
+        let moved = if up {
+            self.level.move_layer_up(index)
+        } else {
+            self.level.move_layer_down(index)
+        };
+        if !moved {
+            return false;
+        }
+        self.chunk_cache.invalidate();
+        let to = if up { index - 1 } else { index + 1 };
+        let before = self.current_layer;
+        self.current_layer = to;
+        self.history.push(EditCommand::MoveLayer { from: index, to }, before, to);
+        true
+    }
+
+    /// Renomme un calque
+    pub fn rename_layer(&mut self, index: usize, new_name: String) {
+        let Some(layer) = self.level.layers.get(index) else {
+            return;
+        };
+        let old_name = layer.name.clone();
+        if old_name != new_name && self.level.rename_layer(index, new_name.clone()) {
+            self.history.push(
+                EditCommand::RenameLayer { index, old_name, new_name },
+                self.current_layer,
+                self.current_layer,
+            );
+        }
+    }
+
+    /// Indique si un calque (ou l'un de ses groupes) est verrouillé, en le signalant
+    fn refuse_locked(&mut self, index: usize) -> bool {
+        if !self.level.is_layer_locked(index) {
+            return false;
+        }
+        self.show_notification(format!("🔒 Calque '{}' verrouillé", self.level.layers[index].name));
+        true
+    }
+
+    /// Le calque est affiché dans l'éditeur : un calque solo masque tous les
+    /// autres, sinon sa visibilité et celle de ses groupes s'appliquent
+    pub fn is_layer_shown(&self, index: usize) -> bool {
+        if self.level.layers.iter().any(|layer| layer.solo) {
+            return self.level.layers.get(index).is_some_and(|layer| layer.solo);
+        }
+        self.level.is_layer_visible(index)
+    }
+
+    /// Vide un calque (annulable)
+    pub fn clear_layer(&mut self, index: usize) {
+        if self.refuse_locked(index) {
+            return;
+        }
+        let Some(layer) = self.level.layers.get_mut(index) else {
+            return;
+        };
+        let changes: Vec<_> = layer
+            .tiles
+            .drain()
+            .map(|(pos, before)| (pos, before, TileData::empty()))
+            .collect();
+        self.chunk_cache.invalidate();
+        if !changes.is_empty() {
+            self.history.push(
+                EditCommand::Tiles { layer: index, changes },
+                self.current_layer,
+                self.current_layer,
+            );
+        }
+    }
+
+    /// Remplace tous les calques (presets) et active `current_layer`
+    pub fn replace_layers(&mut self, layers: Vec<Layer>, current_layer: usize) {
+        let before = std::mem::replace(&mut self.level.layers, layers.clone());
+        self.chunk_cache.invalidate();
+        let layer_before = self.current_layer;
+        self.current_layer = current_layer.min(self.level.layers.len().saturating_sub(1));
+        self.history.push(
+            EditCommand::ReplaceLayers { before, after: layers },
+            layer_before,
+            self.current_layer,
+        );
+    }
+
+    /// Nombre de tiles qui référencent un tileset, dans tous les niveaux du projet
+    pub fn count_tileset_references(&self, tileset_id: usize) -> usize {
+        (0..self.levels.len()).map(|index| self.level_at(index).count_tileset_references(tileset_id)).sum()
+    }
+
+    /// Redirige les tiles d'un tileset vers un autre dans tous les niveaux
+    /// (annulable niveau par niveau). Retourne le nombre de tiles modifiés.
+    pub fn remap_tileset(&mut self, from: usize, to: usize) -> usize {
+        self.edit_all_levels(|level| level.remap_tileset(from, to))
+    }
+
+    /// Efface les tiles d'un tileset dans tous les calques de tous les niveaux
+    /// (annulable niveau par niveau)
+    pub fn clear_tileset_tiles(&mut self, tileset_id: usize) -> usize {
+        self.edit_all_levels(|level| level.clear_tileset(tileset_id))
+    }
+
+    /// Applique une modification de calques à chaque niveau du projet ; chaque
+    /// niveau modifié reçoit sa propre étape d'historique
+    fn edit_all_levels(&mut self, operation: impl Fn(&mut Level) -> usize) -> usize {
+        let before = self.level.layers.clone();
+        let mut total = operation(&mut self.level);
+        self.push_layers_edit(before, total);
+
+        for (index, stored) in self.levels.iter_mut().enumerate() {
+            if index == self.current_level {
+                continue;
+            }
+            let before = stored.level.layers.clone();
+            let count = operation(&mut stored.level);
+            if count > 0 {
+                stored.history.push(
+                    EditCommand::ReplaceLayers { before, after: stored.level.layers.clone() },
+                    stored.current_layer,
+                    stored.current_layer,
+                );
+                total += count;
+            }
+        }
+        total
+    }
+
+    fn push_layers_edit(&mut self, before: Vec<Layer>, count: usize) {
+        if count > 0 {
+            self.chunk_cache.invalidate();
+            self.history.push(
+                EditCommand::ReplaceLayers { before, after: self.level.layers.clone() },
+                self.current_layer,
+                self.current_layer,
+            );
+        }
+    }
+
+    /// Opération sur la taille ou la position du contenu du niveau, en une seule
+    /// étape d'historique. La sélection (en coordonnées de cases) est annulée.
+    fn reshape_level(&mut self, operation: impl FnOnce(&mut Level) -> usize) -> usize {
+        let before = self.level.layers.clone();
+        let old_size = (self.level.width, self.level.height);
+        let removed = operation(&mut self.level);
+        self.chunk_cache.invalidate();
+        self.history.push(
+            EditCommand::Resize {
+                old_size,
+                new_size: (self.level.width, self.level.height),
+                before,
+                after: self.level.layers.clone(),
+            },
+            self.current_layer,
+            self.current_layer,
+        );
+        self.selection.clear();
+        self.selection_drag = None;
+        removed
+    }
+
+    /// Notification d'un redimensionnement : tiles retirés et objets hors limites
+    fn notify_reshape(&mut self, message: String, removed: usize) {
+        let mut message = message;
+        if removed > 0 {
+            message.push_str(&format!(" — {} tiles hors limites retirés", removed));
+        }
+        let objects = self.level.objects_out_of_bounds();
+        if objects > 0 {
+            message.push_str(&format!(" — ⚠️ {} objets hors limites", objects));
+        }
+        self.show_notification(message);
+    }
+
+    /// Redimensionne le niveau autour d'un ancrage (annulable)
+    pub fn resize_level(&mut self, width: u32, height: u32, anchor: Anchor) {
+        if (width, height) == (self.level.width, self.level.height) {
+            return;
+        }
+        let removed = self.reshape_level(|level| level.resize(width, height, anchor));
+        self.notify_reshape(format!("📐 Niveau redimensionné en {}x{}", width, height), removed);
+    }
+
+    /// Recadre le niveau sur les tiles utilisés (annulable)
+    pub fn crop_level_to_content(&mut self) {
+        if self.level.used_bounds().is_none() {
+            self.show_notification("❌ Aucun tile à conserver".to_string());
+            return;
+        }
+        let removed = self.reshape_level(|level| level.crop_to_content().unwrap_or(0));
+        let message = format!("✂️ Niveau recadré en {}x{}", self.level.width, self.level.height);
+        self.notify_reshape(message, removed);
+    }
+
+    /// Décale tout le niveau de (dx, dy) cases (annulable)
+    pub fn shift_level(&mut self, dx: i32, dy: i32) {
+        if (dx, dy) == (0, 0) {
+            return;
+        }
+        let removed = self.reshape_level(|level| {
+            level.shift(dx, dy);
+            level.remove_out_of_bounds()
+        });
+        self.notify_reshape(format!("↔ Niveau décalé de ({}, {})", dx, dy), removed);
+    }
+
+    /// Remplace tous les niveaux du projet (nouveau projet ou fichier chargé) ;
+    /// `first` est ouvert et les historiques sont vidés
+    pub fn set_levels(&mut self, first: Level, rest: Vec<Level>) {
+        let budget = self.history.budget();
+        self.levels = std::iter::once(first).chain(rest).map(|level| StoredLevel::new(level, budget)).collect();
+        self.current_level = 0;
+        self.level = self.levels[0].level.clone();
+        self.history.clear();
+        self.current_layer = self.levels[0].current_layer;
+        self.forget_level_state();
+        self.problems.clear();
+        self.focused_problem = None;
+    }
+
+    /// Nombre de niveaux du projet
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Niveau du projet, à jour même s'il est en cours d'édition
+    pub fn level_at(&self, index: usize) -> &Level {
+        if index == self.current_level {
+            &self.level
+        } else {
+            &self.levels[index].level
+        }
+    }
+
+    /// Projet complet tel qu'il serait sauvegardé (niveaux, tilesets, règles)
+    pub fn project(&self) -> Project {
+        let rest = (1..self.levels.len()).map(|index| self.level_at(index).clone()).collect();
+        let mut project = Project::with_levels(self.level_at(0).clone(), rest);
+        project.tilesets = self.asset_manager.get_metadata();
+        project.validation = self.validation.clone();
+        project
+    }
+
+    /// Vérifie tout le projet et ouvre le panneau des problèmes
+    pub fn run_validation(&mut self) {
+        self.problems = self.project().validate();
+        self.focused_problem = None;
+        self.show_problems = true;
+        let errors = self.problems.iter().filter(|p| p.severity == Severity::Error).count();
+        let message = match (errors, self.problems.len()) {
+            (_, 0) => "✅ Aucun problème détecté".to_string(),
+            (0, warnings) => format!("⚠️ {} avertissement(s)", warnings),
+            (errors, total) => format!("❌ {} erreur(s), {} avertissement(s)", errors, total - errors),
+        };
+        self.show_notification(message);
+    }
+
+    /// Ouvre le niveau et le calque d'un problème et centre la vue sur sa première case
+    pub fn focus_problem(&mut self, index: usize) {
+        let Some(problem) = self.problems.get(index) else {
+            return;
+        };
+        let (level, layer, first) = (problem.level, problem.layer, problem.cells.first().copied());
+        self.world_view = None;
+        if let Some(level) = level {
+            self.switch_level(level);
+        }
+        if let Some(layer) = layer.filter(|&layer| layer < self.level.layers.len()) {
+            self.current_layer = layer;
+        }
+        if let Some(cell) = first {
+            self.center_on(cell);
+        }
+        self.focused_problem = Some(index);
+    }
+
+    /// Centre la vue sur une case du niveau courant
+    pub fn center_on(&mut self, (x, y): (i32, i32)) {
+        let tile_size = self.level.tile_size as f32 * self.zoom;
+        self.offset = -Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * tile_size;
+    }
+
+    /// Analyse le parcours du niveau courant et l'affiche sur le canvas
+    pub fn run_reachability(&mut self) {
+        match reachability::analyze(&self.level, &self.reach_settings) {
+            Ok(result) => {
+                let coins = match result.unreachable_coins.len() {
+                    0 => String::new(),
+                    count => format!(", {} pièce(s) inaccessible(s)", count),
+                };
+                let message = if result.exits.is_empty() {
+                    format!("🧭 Aucune sortie dans le niveau{}", coins)
+                } else if result.exit_reached() {
+                    format!("✅ Sortie accessible en {} cases{}", result.path.len(), coins)
+                } else {
+                    format!("❌ Sortie inaccessible depuis le départ{}", coins)
+                };
+                self.show_notification(message);
+                self.reach_result = Some(result);
+            }
+            Err(e) => {
+                self.show_notification(format!("❌ Analyse de parcours : {}", e));
+                self.reach_result = None;
+            }
+        }
+    }
+
+    /// Oublie ce qui ne concerne que le niveau quitté (sélection, glisser en cours, blocs dessinés)
+    fn forget_level_state(&mut self) {
+        self.selection.clear();
+        self.selection_drag = None;
+        self.object_drag = None;
+        self.selected_object = None;
+        self.last_painted = None;
+        self.resize_dialog = None;
+        self.reach_result = None;
+        self.chunk_cache.invalidate();
+    }
+
+    /// Ouvre un autre niveau du projet ; chaque niveau garde son historique
+    pub fn switch_level(&mut self, index: usize) {
+        if index == self.current_level || index >= self.levels.len() {
+            return;
+        }
+        self.history.end_stroke();
+        let current = &mut self.levels[self.current_level];
+        std::mem::swap(&mut self.level, &mut current.level);
+        std::mem::swap(&mut self.history, &mut current.history);
+        current.current_layer = self.current_layer;
+
+        let target = &mut self.levels[index];
+        std::mem::swap(&mut self.level, &mut target.level);
+        std::mem::swap(&mut self.history, &mut target.history);
+        self.current_layer = target.current_layer.min(self.level.layers.len().saturating_sub(1));
+        self.current_level = index;
+        self.forget_level_state();
+    }
+
+    /// Nom libre dérivé de `base` (« base 2 », « base 3 »...)
+    fn unique_level_name(&self, base: &str) -> String {
+        let taken = |name: &str| (0..self.levels.len()).any(|index| self.level_at(index).name == name);
+        if !taken(base) {
+            return base.to_string();
+        }
+        (2..).map(|n| format!("{} {}", base, n)).find(|name| !taken(name)).unwrap()
+    }
+
+    /// Position libre sur la carte du monde, à droite de tous les niveaux
+    fn next_world_position(&self) -> (i32, i32) {
+        let right = (0..self.levels.len())
+            .map(|index| {
+                let level = self.level_at(index);
+                level.world_position.0 + (level.width * level.tile_size) as i32
+            })
+            .max()
+            .unwrap_or(0);
+        (right + self.level.tile_size as i32 * 4, self.level.world_position.1)
+    }
+
+    /// Ajoute un niveau vide de mêmes dimensions que le niveau courant et l'ouvre
+    pub fn add_level(&mut self) {
+        let name = self.unique_level_name(&format!("Niveau {}", self.levels.len() + 1));
+        let mut level = Level::new(name.clone(), self.level.width, self.level.height, self.level.tile_size);
+        level.world_position = self.next_world_position();
+        self.push_level(level);
+        self.show_notification(format!("➕ Niveau '{}' ajouté", name));
+    }
+
+    /// Ajoute une copie du niveau courant et l'ouvre
+    pub fn duplicate_level(&mut self) {
+        let mut level = self.level.clone();
+        level.name = self.unique_level_name(&format!("{} (copie)", self.level.name));
+        level.world_position = self.next_world_position();
+        let name = level.name.clone();
+        self.push_level(level);
+        self.show_notification(format!("📄 Niveau dupliqué : '{}'", name));
+    }
+
+    fn push_level(&mut self, level: Level) {
+        self.levels.push(StoredLevel::new(level, self.history.budget()));
+        self.switch_level(self.levels.len() - 1);
+    }
+
+    /// Supprime un niveau du projet (le dernier niveau restant est conservé)
+    pub fn remove_level(&mut self, index: usize) {
+        if self.levels.len() <= 1 || index >= self.levels.len() {
+            self.show_notification("❌ Un projet contient au moins un niveau".to_string());
+            return;
+        }
+        if index == self.current_level {
+            self.switch_level(if index == 0 { 1 } else { index - 1 });
+        }
+        let removed = self.levels.remove(index);
+        if index < self.current_level {
+            self.current_level -= 1;
+        }
+        self.show_notification(format!("🗑 Niveau '{}' supprimé", removed.level.name));
+    }
+
+    pub fn rename_level(&mut self, index: usize, name: String) {
+        if name.trim().is_empty() || index >= self.levels.len() {
+            return;
+        }
+        if index == self.current_level {
+            self.level.name = name;
+        } else {
+            self.levels[index].level.name = name;
+        }
+    }
+
+    /// Place un niveau sur la carte du monde (coin haut-gauche, en pixels)
+    pub fn set_level_world_position(&mut self, index: usize, position: (i32, i32)) {
+        if index == self.current_level {
+            self.level.world_position = position;
+        } else if let Some(stored) = self.levels.get_mut(index) {
+            stored.level.world_position = position;
+        }
+    }
+
+    /// Retire un tileset du projet. Les tiles qui le référencent encore
+    /// sont laissés tels quels : les rediriger ou les effacer avant.
+    pub fn remove_tileset(&mut self, tileset_id: usize) {
+        let Some(tileset) = self.asset_manager.remove_tileset(tileset_id) else {
+            return;
+        };
+        if self.selected_tileset == Some(tileset_id) {
+            self.selected_tileset = None;
+        }
+        if self.selected_terrain.is_some_and(|(id, _)| id == tileset_id) {
+            self.selected_terrain = None;
+        }
+        if matches!(self.paint_mode, PaintMode::TextureTile { tileset_id: id, .. } if id == tileset_id) {
+            self.paint_mode = PaintMode::ColorTile(self.selected_color);
+        }
+        let uses_tileset = |content: &SelectionContent| {
+            content
+                .tiles
+                .iter()
+                .any(|(_, tile)| matches!(tile, TileData::Texture { tileset_id: id, .. } if *id == tileset_id))
+        };
+        if self.stamp.as_ref().is_some_and(uses_tileset) {
+            self.stamp = None;
+        }
+        self.show_notification(format!("🗑 Tileset '{}' retiré", tileset.name));
+    }
+
+    /// Budget mémoire de l'historique (en octets)
+    pub fn history_budget(&self) -> usize {
+        self.history.budget()
+    }
+
+    pub fn set_history_budget(&mut self, budget: usize) {
+        self.history.set_budget(budget);
+        for stored in &mut self.levels {
+            stored.history.set_budget(budget);
+        }
+    }
+
+    /// Mémoire estimée occupée par l'historique (en octets)
+    pub fn history_usage(&self) -> usize {
+        self.history.used()
+    }
+
+    /// Annule la dernière action (Ctrl+Z)
+    pub fn undo(&mut self) {
+        if let Some(layer) = self.history.undo(&mut self.level) {
+            self.chunk_cache.invalidate();
+            self.current_layer = layer.min(self.level.layers.len().saturating_sub(1));
+            self.show_notification("↶ Annulation".to_string());
+        } else {
+            self.show_notification("❌ Rien à annuler".to_string());
+        }
+    }
+
+    /// Refait la dernière action annulée (Ctrl+Y)
+    pub fn redo(&mut self) {
+        if let Some(layer) = self.history.redo(&mut self.level) {
+            self.chunk_cache.invalidate();
+            self.current_layer = layer.min(self.level.layers.len().saturating_sub(1));
+            self.show_notification("↷ Rétablir".to_string());
+        } else {
+            self.show_notification("❌ Rien à rétablir".to_string());
+        }
+    }
+
+    /// Peint (ou efface) une case avec le terrain sélectionné et ajuste ses voisines
+    fn paint_terrain(&mut self, x: i32, y: i32, paint: bool) {
+        let Some((tileset_id, index)) = self.selected_terrain else {
+            self.show_notification("❌ Aucun terrain sélectionné".to_string());
+            return;
+        };
+        let Some(terrain) = self
+            .asset_manager
+            .get_tileset(tileset_id)
+            .and_then(|tileset| tileset.terrains.get(index))
+        else {
+            return;
+        };
+        if terrain.tiles.is_empty() {
+            self.show_notification(format!("❌ Terrain '{}' sans tiles", terrain.name));
+            return;
+        }
+        let Some(layer) = self.level.layers.get(self.current_layer) else {
+            return;
+        };
+        for ((cx, cy), tile_data) in terrain.brush_changes(layer, tileset_id, x, y, paint) {
+            self.paint_stroke(cx, cy, tile_data);
+        }
+    }
+
+    /// Dessine un tile en transparence (aperçu du tampon sous le curseur)
+    fn draw_tile_ghost(&self, painter: &egui::Painter, rect: Rect, tile_data: TileData) {
+        self.draw_tile(painter, rect, tile_data, 150);
+    }
+
+    /// Dessine un tile (sans animation) avec l'opacité `alpha`
+    fn draw_tile(&self, painter: &egui::Painter, rect: Rect, tile_data: TileData, alpha: u8) {
+        match tile_data {
+            TileData::Empty => {}
+            TileData::Color(rgb) => {
+                painter.rect_filled(rect, 0.0, Color32::from_rgba_unmultiplied(rgb[0], rgb[1], rgb[2], alpha));
+            }
+            TileData::Value(value) => {
+                // Couleur définie par le calque de collision actif
+                let [r, g, b] = self
+                    .level
+                    .layers
+                    .get(self.current_layer)
+                    .and_then(|layer| layer.logic_value(value))
+                    .map_or([128, 128, 128], |v| v.color);
+                let alpha = (alpha as u16 * LOGIC_ALPHA as u16 / 255) as u8;
+                painter.rect_filled(rect, 0.0, Color32::from_rgba_unmultiplied(r, g, b, alpha));
+            }
+            TileData::Texture { tileset_id, tile_index, flags } => {
+                let tile_ref = TileRef { tileset_id, tile_index };
+                if let (Some(tileset), Some((u1, v1, u2, v2))) = (
+                    self.asset_manager.get_tileset(tileset_id),
+                    self.asset_manager.get_tile_uv(tile_ref),
+                ) {
+                    let uv = Rect::from_min_max(Pos2::new(u1, v1), Pos2::new(u2, v2));
+                    let tint = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
+                    paint_tile_image(painter, tileset.texture.id(), rect, uv, flags, tint);
+                }
+            }
+        }
+    }
+
+    /// Tile posé par le pinceau, le remplissage et les rectangles
+    pub fn brush_tile(&self) -> TileData {
+        if self.is_logic_layer(self.current_layer) {
+            return TileData::Value(self.logic_brush);
+        }
+        match self.paint_mode {
+            PaintMode::ColorTile(rgb) => TileData::Color(rgb),
+            PaintMode::TextureTile { tileset_id, tile_index } => {
+                TileData::Texture { tileset_id, tile_index, flags: self.brush_flags }
+            }
+        }
+    }
+
+    /// Retourne ou tourne le tampon s'il y en a un, sinon le tile du pinceau
+    pub fn transform_brush(&mut self, transform: BrushTransform) {
+        if let Some(stamp) = &self.stamp {
+            self.stamp = Some(stamp.transformed(transform));
+        } else {
+            self.brush_flags = transform.apply(self.brush_flags);
+        }
+    }
+
+    /// Crée un tampon à partir d'un rectangle de tiles du tileset
+    /// (coins inclus, en indices de tiles). Un seul tile redevient un pinceau simple.
+    pub fn set_tileset_stamp(&mut self, tileset_id: usize, first: u32, last: u32) {
+        let Some(columns) = self.asset_manager.get_tileset(tileset_id).map(|t| t.columns.max(1)) else {
+            return;
+        };
+        let (x0, x1) = ((first % columns).min(last % columns), (first % columns).max(last % columns));
+        let (y0, y1) = ((first / columns).min(last / columns), (first / columns).max(last / columns));
+
+        self.paint_mode = PaintMode::TextureTile { tileset_id, tile_index: y0 * columns + x0 };
+        self.current_tool = Tool::Paint;
+        if x0 == x1 && y0 == y1 {
+            self.stamp = None;
+            return;
+        }
+
+        let mut tiles = Vec::new();
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let tile_index = y * columns + x;
+                tiles.push((((x - x0) as i32, (y - y0) as i32), TileData::texture(tileset_id, tile_index)));
+            }
+        }
+        let (width, height) = ((x1 - x0 + 1) as i32, (y1 - y0 + 1) as i32);
+        self.stamp = Some(SelectionContent { tiles, origin: (x0 as i32, y0 as i32), width, height });
+        self.show_notification(format!("🖌️ Tampon {}x{}", width, height));
+    }
+
+    /// Utilise le presse-papier comme tampon réutilisable de l'outil Pinceau
+    pub fn use_clipboard_as_stamp(&mut self) {
+        let Some(clipboard) = self.clipboard.clone() else {
+            self.show_notification("❌ Presse-papier vide".to_string());
+            return;
+        };
+        self.show_notification(format!("🖌️ Tampon {}x{}", clipboard.width, clipboard.height));
+        self.stamp = Some(clipboard);
+        self.current_tool = Tool::Paint;
+    }
+
+    /// Tiles de la sélection active sur le calque courant (positions relatives
+    /// au coin haut-gauche du rectangle englobant)
+    fn selection_content(&self) -> Option<SelectionContent> {
+        let (min_x, min_y, max_x, max_y) = self.selection.bounds()?;
+
+        let mut tiles = Vec::new();
+        if let Some(layer) = self.level.layers.get(self.current_layer) {
+            for &(tx, ty) in self.selection.mask() {
+                if let Some(&tile_data) = layer.tiles.get(&(tx, ty)) {
+                    if !tile_data.is_empty() {
+                        tiles.push(((tx - min_x, ty - min_y), tile_data));
                     }
                 }
-                
-                self.clipboard = Some(SelectionContent {
-                    tiles,
-                    origin: (min_x, min_y),
-                    width: max_x - min_x + 1,
-                    height: max_y - min_y + 1,
-                });
-                
-                let count = (max_x - min_x + 1) * (max_y - min_y + 1);
-                self.show_notification(format!("📋 {} tiles copiés", count));
             }
+        }
+
+        Some(SelectionContent {
+            tiles,
+            origin: (min_x, min_y),
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        })
+    }
+
+    /// Copie la sélection dans le presse-papier (Ctrl+C)
+    pub fn copy_selection(&mut self) {
+        if let Some(content) = self.selection_content() {
+            let count = self.selection.mask().len();
+            self.clipboard = Some(content);
+            self.show_notification(format!("📋 {} tiles copiés", count));
         } else {
             self.show_notification("❌ Aucune sélection active".to_string());
         }
     }
 
+    /// Décale les tiles de la sélection (glisser ou flèches), en une étape d'historique
+    pub fn move_selection(&mut self, dx: i32, dy: i32) {
+        if (dx, dy) == (0, 0) {
+            return;
+        }
+        if let Some(content) = self.selection_content() {
+            let (x, y) = content.origin;
+            let mask = self.selection.mask().iter().map(|&(cx, cy)| (cx + dx, cy + dy)).collect();
+            self.replace_selection(content.clone(), (x + dx, y + dy), mask);
+        }
+    }
+
+    /// Retourne ou tourne la sélection autour de son coin haut-gauche ; les
+    /// tiles texturés reçoivent aussi la transformation
+    pub fn transform_selection(&mut self, transform: BrushTransform) {
+        if let Some(content) = self.selection_content() {
+            let (x, y) = content.origin;
+            let mask = self
+                .selection
+                .mask()
+                .iter()
+                .map(|&(cx, cy)| {
+                    let (tx, ty) = transform.position((cx - x, cy - y), content.width, content.height);
+                    (x + tx, y + ty)
+                })
+                .collect();
+            self.replace_selection(content.transformed(transform), content.origin, mask);
+        }
+    }
+
+    /// Vide les cases sélectionnées puis pose `after` en `origin` (une seule
+    /// commande d'historique) ; la sélection devient `mask`, limité au niveau.
+    /// Refusé si un tile sortait du niveau, où il ne serait plus visible.
+    fn replace_selection(&mut self, after: SelectionContent, origin: (i32, i32), mask: BTreeSet<(i32, i32)>) {
+        if self.refuse_locked(self.current_layer) {
+            return;
+        }
+        if after.tiles.iter().any(|&((dx, dy), _)| !self.level.in_bounds(origin.0 + dx, origin.1 + dy)) {
+            self.show_notification("❌ La sélection sortirait du niveau".to_string());
+            return;
+        }
+        let mask = mask.into_iter().filter(|&(x, y)| self.level.in_bounds(x, y)).collect();
+        let mut cells: Vec<_> = self.selection.mask().iter().map(|&cell| (cell, TileData::empty())).collect();
+        for &((dx, dy), tile_data) in &after.tiles {
+            cells.push(((origin.0 + dx, origin.1 + dy), tile_data));
+        }
+        self.apply_tiles(cells);
+        self.selection.set_mask(mask);
+    }
+
+    /// Combine le rectangle en cours (start → end) avec la sélection
+    fn commit_selection_rect(&mut self) {
+        if let (Some(start), Some(end)) = (self.selection.start.take(), self.selection.end.take()) {
+            self.selection.combine(rect_cells(start, end), self.selection.mode);
+            self.show_notification(format!("📦 {} tiles sélectionnés", self.selection.mask().len()));
+        }
+    }
+
+    /// Sélectionne les cases du niveau qui ne le sont pas, et inversement (Ctrl+I)
+    pub fn invert_selection(&mut self) {
+        let all = rect_cells((0, 0), (self.level.width as i32 - 1, self.level.height as i32 - 1));
+        let inverted = all.filter(|&(x, y)| !self.selection.contains(x, y)).collect();
+        self.selection.set_mask(inverted);
+        self.selection.start = None;
+        self.selection.end = None;
+        self.show_notification(format!("🔄 {} tiles sélectionnés", self.selection.mask().len()));
+    }
+
+    /// Remplit toutes les cases sélectionnées avec le tile du pinceau
+    pub fn fill_selection(&mut self) {
+        let tile_data = self.brush_tile();
+        let cells: Vec<_> = self.selection.mask().iter().map(|&cell| (cell, tile_data)).collect();
+        if cells.is_empty() {
+            self.show_notification("❌ Aucune sélection active".to_string());
+            return;
+        }
+        let count = self.apply_tiles(cells);
+        self.show_notification(format!("🪣 {} tiles remplis", count));
+    }
+
     /// Colle le contenu du presse-papier (Ctrl+V)
     pub fn paste_selection(&mut self, paste_x: i32, paste_y: i32) {
         // Clone le clipboard pour éviter le conflit de borrowing
         if let Some(clipboard) = self.clipboard.clone() {
-            self.save_history();
-            
-            if let Some(layer) = self.level.layers.get_mut(self.current_layer) {
-                for ((rel_x, rel_y), tile_data) in &clipboard.tiles {
-                    let abs_x = paste_x + rel_x;
-                    let abs_y = paste_y + rel_y;
-                    layer.set_tile(abs_x, abs_y, *tile_data);
-                }
+            // Un calque de collision ne reçoit que des valeurs, les autres jamais
+            let Some(layer) = self.level.layers.get(self.current_layer) else {
+                return;
+            };
+            let (cells, rejected): (Vec<_>, Vec<_>) = clipboard
+                .tiles
+                .iter()
+                .map(|&((rel_x, rel_y), tile_data)| ((paste_x + rel_x, paste_y + rel_y), tile_data))
+                .partition(|(_, tile_data)| layer.accepts(tile_data));
+            if cells.is_empty() && !rejected.is_empty() {
+                self.show_notification("❌ Le contenu copié ne convient pas à ce type de calque".to_string());
+                return;
+            }
+            let count = cells.len();
+            self.apply_tiles(cells);
+
+            if rejected.is_empty() {
+                self.show_notification(format!("✅ {} tiles collés", count));
+            } else {
+                self.show_notification(format!(
+                    "⚠️ {} tiles collés, {} ignorés (type incompatible avec le calque)",
+                    count,
+                    rejected.len()
+                ));
             }
-            
-            let count = clipboard.tiles.len();
-            self.show_notification(format!("✅ {} tiles collés", count));
         } else {
             self.show_notification("❌ Presse-papier vide".to_string());
         }
@@ -667,31 +2319,13 @@ impl EditorState {
 
     /// Supprime les tiles dans la sélection (Delete)
     pub fn delete_selection(&mut self) {
-        if self.selection.is_active {
-            if let (Some(start), Some(end)) = (self.selection.start, self.selection.end) {
-                self.save_history();
-                
-                let min_x = start.0.min(end.0);
-                let max_x = start.0.max(end.0);
-                let min_y = start.1.min(end.1);
-                let max_y = start.1.max(end.1);
-                
-                if let Some(layer) = self.level.layers.get_mut(self.current_layer) {
-                    let mut count = 0;
-                    for tx in min_x..=max_x {
-                        for ty in min_y..=max_y {
-                            layer.set_tile(tx, ty, TileData::empty());
-                            count += 1;
-                        }
-                    }
-                    self.show_notification(format!("🗑️ {} tiles supprimés", count));
-                }
-                
-                // Désactiver la sélection
-                self.selection.is_active = false;
-                self.selection.start = None;
-                self.selection.end = None;
-            }
+        if self.selection.is_active() {
+            let cells = self.selection.mask().iter().map(|&cell| (cell, TileData::empty())).collect();
+            let count = self.apply_tiles(cells);
+            self.show_notification(format!("🗑️ {} tiles supprimés", count));
+
+            // Désactiver la sélection
+            self.selection.clear();
         } else {
             self.show_notification("❌ Aucune sélection active".to_string());
         }
@@ -705,3 +2339,113 @@ impl EditorState {
         self.history.can_redo()
     }
 }
+
+/// Dessine la zone `uv` d'une texture dans `rect` en appliquant les
+/// transformations du tile (maillage aux coins permutés si nécessaire)
+pub fn paint_tile_image(
+    painter: &egui::Painter,
+    texture_id: egui::TextureId,
+    rect: Rect,
+    uv: Rect,
+    flags: TileFlags,
+    tint: Color32,
+) {
+    if flags.is_none() {
+        painter.image(texture_id, rect, uv, tint);
+        return;
+    }
+    let mut mesh = egui::Mesh::with_texture(texture_id);
+    push_tile_quad(&mut mesh, rect, uv, flags, tint);
+    painter.add(egui::Shape::mesh(mesh));
+}
+
+/// Ajoute à un maillage le quadrilatère d'un tile (voir `paint_tile_image`)
+pub fn push_tile_quad(mesh: &mut egui::Mesh, rect: Rect, uv: Rect, flags: TileFlags, color: Color32) {
+    let first = mesh.vertices.len() as u32;
+    for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+        let (u, v) = flags.source_point(x, y);
+        mesh.vertices.push(egui::epaint::Vertex {
+            pos: rect.lerp_inside(Vec2::new(x, y)),
+            uv: uv.lerp_inside(Vec2::new(u, v)),
+            color,
+        });
+    }
+    mesh.add_triangle(first, first + 1, first + 2);
+    mesh.add_triangle(first, first + 2, first + 3);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transformed_content() {
+        let content = SelectionContent {
+            tiles: vec![((0, 0), TileData::texture(1, 4)), ((1, 2), TileData::Color([1, 2, 3]))],
+            origin: (5, 5),
+            width: 2,
+            height: 3,
+        };
+
+        let rotated = content.transformed(BrushTransform::Rotate);
+        assert_eq!((rotated.width, rotated.height, rotated.origin), (3, 2, (5, 5)));
+        assert_eq!(
+            rotated.tiles,
+            vec![
+                ((2, 0), TileData::texture(1, 4).with_flags(TileFlags::rotated)),
+                ((0, 1), TileData::Color([1, 2, 3])),
+            ]
+        );
+
+        let flipped = content.transformed(BrushTransform::FlipX);
+        assert_eq!((flipped.width, flipped.height), (2, 3));
+        assert_eq!(flipped.tiles[0], ((1, 0), TileData::texture(1, 4).with_flags(TileFlags::flipped_x)));
+        assert_eq!(flipped.tiles[1], ((0, 2), TileData::Color([1, 2, 3])));
+    }
+
+    /// Éditeur dont la sélection couvre `cells`, chacune portant un tile distinct
+    fn state_with_selection(cells: &[(i32, i32)]) -> EditorState {
+        let mut state = EditorState::new();
+        for (i, &(x, y)) in cells.iter().enumerate() {
+            state.level.layers[state.current_layer].set_tile(x, y, TileData::texture(0, i as u32));
+        }
+        state.selection.set_mask(cells.iter().copied().collect());
+        state
+    }
+
+    #[test]
+    fn transform_selection_remaps_mask() {
+        let mut state = state_with_selection(&[(2, 2), (2, 3), (3, 3)]);
+        state.transform_selection(BrushTransform::Rotate);
+
+        assert_eq!(state.selection.mask(), &BTreeSet::from([(2, 2), (3, 2), (2, 3)]));
+        let layer = &state.level.layers[state.current_layer];
+        assert_eq!(layer.get_tile(3, 2), TileData::texture(0, 0).with_flags(TileFlags::rotated));
+        assert_eq!(layer.get_tile(2, 2), TileData::texture(0, 1).with_flags(TileFlags::rotated));
+        assert_eq!(layer.get_tile(2, 3), TileData::texture(0, 2).with_flags(TileFlags::rotated));
+        assert_eq!(layer.tiles.len(), 3);
+    }
+
+    #[test]
+    fn move_selection_stays_in_level() {
+        let mut state = state_with_selection(&[(0, 0), (1, 0)]);
+        state.move_selection(-1, 0);
+        assert_eq!(state.selection.mask(), &BTreeSet::from([(0, 0), (1, 0)]));
+        assert_eq!(state.level.layers[state.current_layer].get_tile(0, 0), TileData::texture(0, 0));
+        assert!(state.level.layers[state.current_layer].get_tile(-1, 0).is_empty());
+
+        state.move_selection(2, 1);
+        assert_eq!(state.selection.mask(), &BTreeSet::from([(2, 1), (3, 1)]));
+        assert_eq!(state.level.layers[state.current_layer].get_tile(3, 1), TileData::texture(0, 1));
+    }
+
+    #[test]
+    fn rotation_refused_past_the_edge() {
+        let bottom = EditorState::new().level.height as i32 - 1;
+        let mut state = state_with_selection(&[(0, bottom), (1, bottom), (2, bottom)]);
+        state.transform_selection(BrushTransform::Rotate);
+        assert_eq!(state.selection.mask().len(), 3);
+        assert_eq!(state.level.layers[state.current_layer].get_tile(0, bottom), TileData::texture(0, 0));
+        assert!(!state.can_undo());
+    }
+}
@@ -0,0 +1,396 @@
+use crate::level::{Layer, Level, LevelObject, TileData};
+use std::collections::VecDeque;
+
+/// Budget mémoire par défaut de l'historique (16 Mo)
+pub const DEFAULT_HISTORY_BUDGET: usize = 16 * 1024 * 1024;
+
+/// Modification d'une case : (position, avant, après)
+pub type TileChange = ((i32, i32), TileData, TileData);
+
+/// Commande réversible enregistrée dans l'historique
+#[derive(Debug, Clone)]
+pub enum EditCommand {
+    Tiles { layer: usize, changes: Vec<TileChange> },
+    AddLayer { index: usize, layer: Layer },
+    RemoveLayer { index: usize, layer: Layer },
+    MoveLayer { from: usize, to: usize },
+    RenameLayer { index: usize, old_name: String, new_name: String },
+    ReplaceLayers { before: Vec<Layer>, after: Vec<Layer> },
+    // Redimensionnement, recadrage ou décalage : taille (largeur, hauteur) et calques
+    Resize { old_size: (u32, u32), new_size: (u32, u32), before: Vec<Layer>, after: Vec<Layer> },
+    Objects { layer: usize, before: Vec<LevelObject>, after: Vec<LevelObject> },
+}
+
+impl EditCommand {
+    /// Applique (ou réapplique) la commande sur le niveau
+    pub fn apply(&self, level: &mut Level) {
+        match self {
+            EditCommand::Tiles { layer, changes } => {
+                if let Some(layer) = level.layers.get_mut(*layer) {
+                    for &((x, y), _, after) in changes {
+                        layer.set_tile(x, y, after);
+                    }
+                }
+            }
+            EditCommand::AddLayer { index, layer } => {
+                level.layers.insert(*index, layer.clone());
+            }
+            EditCommand::RemoveLayer { index, .. } => {
+                level.layers.remove(*index);
+            }
+            EditCommand::MoveLayer { from, to } => {
+                let layer = level.layers.remove(*from);
+                level.layers.insert(*to, layer);
+            }
+            EditCommand::RenameLayer { index, new_name, .. } => {
+                level.rename_layer(*index, new_name.clone());
+            }
+            EditCommand::ReplaceLayers { after, .. } => {
+                level.layers = after.clone();
+            }
+            EditCommand::Resize { new_size, after, .. } => {
+                (level.width, level.height) = *new_size;
+                level.layers = after.clone();
+            }
+            EditCommand::Objects { layer, after, .. } => {
+                if let Some(layer) = level.layers.get_mut(*layer) {
+                    layer.objects = after.clone();
+                }
+            }
+        }
+    }
+
+    /// Annule l'effet de la commande sur le niveau
+    pub fn revert(&self, level: &mut Level) {
+        match self {
+            EditCommand::Tiles { layer, changes } => {
+                if let Some(layer) = level.layers.get_mut(*layer) {
+                    // Ordre inverse : une case modifiée plusieurs fois retrouve sa valeur initiale
+                    for &((x, y), before, _) in changes.iter().rev() {
+                        layer.set_tile(x, y, before);
+                    }
+                }
+            }
+            EditCommand::AddLayer { index, .. } => {
+                level.layers.remove(*index);
+            }
+            EditCommand::RemoveLayer { index, layer } => {
+                level.layers.insert(*index, layer.clone());
+            }
+            EditCommand::MoveLayer { from, to } => {
+                let layer = level.layers.remove(*to);
+                level.layers.insert(*from, layer);
+            }
+            EditCommand::RenameLayer { index, old_name, .. } => {
+                level.rename_layer(*index, old_name.clone());
+            }
+            EditCommand::ReplaceLayers { before, .. } => {
+                level.layers = before.clone();
+            }
+            EditCommand::Resize { old_size, before, .. } => {
+                (level.width, level.height) = *old_size;
+                level.layers = before.clone();
+            }
+            EditCommand::Objects { layer, before, .. } => {
+                if let Some(layer) = level.layers.get_mut(*layer) {
+                    layer.objects = before.clone();
+                }
+            }
+        }
+    }
+
+    /// Estimation de la mémoire occupée par la commande (en octets)
+    fn memory_size(&self) -> usize {
+        let objects_size = |objects: &[LevelObject]| {
+            objects
+                .iter()
+                .map(|o| {
+                    std::mem::size_of::<LevelObject>()
+                        + o.name.len()
+                        + o.object_type.len()
+                        + o.properties.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
+                })
+                .sum::<usize>()
+        };
+        let layer_size = |layer: &Layer| {
+            layer.name.len()
+                + layer.tiles.len() * std::mem::size_of::<((i32, i32), TileData)>()
+                + objects_size(&layer.objects)
+        };
+        std::mem::size_of::<Self>()
+            + match self {
+                EditCommand::Tiles { changes, .. } => changes.len() * std::mem::size_of::<TileChange>(),
+                EditCommand::AddLayer { layer, .. } | EditCommand::RemoveLayer { layer, .. } => layer_size(layer),
+                EditCommand::MoveLayer { .. } => 0,
+                EditCommand::RenameLayer { old_name, new_name, .. } => old_name.len() + new_name.len(),
+                EditCommand::ReplaceLayers { before, after } | EditCommand::Resize { before, after, .. } => {
+                    before.iter().chain(after).map(layer_size).sum()
+                }
+                EditCommand::Objects { before, after, .. } => objects_size(before) + objects_size(after),
+            }
+    }
+}
+
+/// Entrée de l'historique : commande et calque actif avant/après
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    command: EditCommand,
+    layer_before: usize,
+    layer_after: usize,
+    size: usize,
+}
+
+/// Historique Undo/Redo basé sur des commandes, limité par un budget mémoire
+pub struct History {
+    undo_stack: VecDeque<HistoryEntry>,
+    redo_stack: VecDeque<HistoryEntry>,
+    budget: usize,
+    used: usize,
+    stroke_open: bool, // Le dernier trait de pinceau accepte encore des cases
+    object_edit: Option<(usize, u32)>, // Objet (calque, id) dont les retouches sont fusionnées
+}
+
+impl History {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: VecDeque::new(),
+            budget,
+            used: 0,
+            stroke_open: false,
+            object_edit: None,
+        }
+    }
+
+    /// Enregistre une commande déjà appliquée au niveau
+    pub fn push(&mut self, command: EditCommand, layer_before: usize, layer_after: usize) {
+        self.stroke_open = false;
+        self.object_edit = None;
+        let size = command.memory_size();
+        self.clear_redo();
+        self.undo_stack.push_back(HistoryEntry { command, layer_before, layer_after, size });
+        self.used += size;
+        self.enforce_budget();
+    }
+
+    /// Ajoute une case au trait en cours, ou démarre un nouveau trait
+    pub fn record_stroke(&mut self, layer: usize, change: TileChange) {
+        if self.stroke_open {
+            if let Some(HistoryEntry {
+                command: EditCommand::Tiles { layer: stroke_layer, changes },
+                size,
+                ..
+            }) = self.undo_stack.back_mut()
+            {
+                if *stroke_layer == layer {
+                    changes.push(change);
+                    *size += std::mem::size_of::<TileChange>();
+                    self.used += std::mem::size_of::<TileChange>();
+                    self.enforce_budget();
+                    return;
+                }
+            }
+        }
+        self.push(EditCommand::Tiles { layer, changes: vec![change] }, layer, layer);
+        self.stroke_open = true;
+    }
+
+    /// Enregistre une retouche d'objet (inspecteur) : les retouches successives
+    /// du même objet sont fusionnées tant qu'aucune autre commande n'intervient
+    pub fn record_object_edit(&mut self, layer: usize, object_id: u32, before: Vec<LevelObject>, after: Vec<LevelObject>) {
+        if self.object_edit == Some((layer, object_id)) {
+            if let Some(entry) = self.undo_stack.back_mut() {
+                if let EditCommand::Objects { after: last_after, .. } = &mut entry.command {
+                    *last_after = after;
+                    let size = entry.command.memory_size();
+                    self.used = self.used - entry.size + size;
+                    entry.size = size;
+                    self.enforce_budget();
+                    return;
+                }
+            }
+        }
+        self.push(EditCommand::Objects { layer, before, after }, layer, layer);
+        self.object_edit = Some((layer, object_id));
+    }
+
+    /// Termine le trait en cours : la prochaine case ouvrira une nouvelle entrée
+    pub fn end_stroke(&mut self) {
+        self.stroke_open = false;
+    }
+
+    /// Annule la dernière commande et retourne le calque actif à restaurer
+    pub fn undo(&mut self, level: &mut Level) -> Option<usize> {
+        self.stroke_open = false;
+        self.object_edit = None;
+        let entry = self.undo_stack.pop_back()?;
+        entry.command.revert(level);
+        let layer = entry.layer_before;
+        self.redo_stack.push_back(entry);
+        Some(layer)
+    }
+
+    /// Réapplique la dernière commande annulée et retourne le calque actif
+    pub fn redo(&mut self, level: &mut Level) -> Option<usize> {
+        self.stroke_open = false;
+        self.object_edit = None;
+        let entry = self.redo_stack.pop_back()?;
+        entry.command.apply(level);
+        let layer = entry.layer_after;
+        self.undo_stack.push_back(entry);
+        Some(layer)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Vide l'historique (nouveau niveau ou chargement)
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.used = 0;
+        self.stroke_open = false;
+        self.object_edit = None;
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.enforce_budget();
+    }
+
+    /// Mémoire estimée occupée par l'historique (en octets)
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    fn clear_redo(&mut self) {
+        for entry in self.redo_stack.drain(..) {
+            self.used -= entry.size;
+        }
+    }
+
+    /// Supprime les entrées les plus anciennes tant que le budget est dépassé
+    /// (la dernière action reste toujours annulable)
+    fn enforce_budget(&mut self) {
+        while self.used > self.budget {
+            let removed = if let Some(entry) = self.redo_stack.pop_front() {
+                entry
+            } else if self.undo_stack.len() > 1 {
+                self.undo_stack.pop_front().unwrap()
+            } else {
+                break;
+            };
+            self.used -= removed.size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level() -> Level {
+        Level::new("Test".to_string(), 8, 8, 16)
+    }
+
+    /// Pose un tile sur le niveau et l'enregistre dans le trait en cours
+    fn paint(history: &mut History, level: &mut Level, x: i32, y: i32, tile: TileData) {
+        let before = level.layers[1].get_tile(x, y);
+        level.layers[1].set_tile(x, y, tile);
+        history.record_stroke(1, ((x, y), before, tile));
+    }
+
+    fn object(x: f32) -> LevelObject {
+        LevelObject::new(1, "coin", x, 0.0, 16.0, 16.0)
+    }
+
+    #[test]
+    fn stroke_cells_merge_into_one_entry() {
+        let mut history = History::new(DEFAULT_HISTORY_BUDGET);
+        let mut level = level();
+        paint(&mut history, &mut level, 0, 0, TileData::Color([1, 1, 1]));
+        paint(&mut history, &mut level, 1, 0, TileData::Color([1, 1, 1]));
+        history.end_stroke();
+        paint(&mut history, &mut level, 2, 0, TileData::Color([1, 1, 1]));
+
+        assert_eq!(history.undo_stack.len(), 2);
+        assert_eq!(history.used(), history.undo_stack.iter().map(|e| e.size).sum::<usize>());
+        history.undo(&mut level);
+        history.undo(&mut level);
+        assert!(level.layers[1].tiles.is_empty());
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn undo_redo_restore_exact_tiles() {
+        let mut history = History::new(DEFAULT_HISTORY_BUDGET);
+        let mut level = level();
+        level.layers[1].set_tile(3, 3, TileData::texture(0, 5));
+        let initial = level.layers[1].tiles.clone();
+
+        // Une case repeinte deux fois dans le même trait retrouve sa valeur d'origine
+        paint(&mut history, &mut level, 3, 3, TileData::Color([9, 9, 9]));
+        paint(&mut history, &mut level, 4, 3, TileData::Color([9, 9, 9]));
+        paint(&mut history, &mut level, 3, 3, TileData::empty());
+        let painted = level.layers[1].tiles.clone();
+
+        assert_eq!(history.undo(&mut level), Some(1));
+        assert_eq!(level.layers[1].tiles, initial);
+        assert_eq!(history.redo(&mut level), Some(1));
+        assert_eq!(level.layers[1].tiles, painted);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn object_edits_merge_and_keep_accounting() {
+        let mut history = History::new(DEFAULT_HISTORY_BUDGET);
+        let mut level = level();
+        level.layers[1].objects = vec![object(0.0)];
+
+        let mut renamed = object(16.0);
+        history.record_object_edit(1, 1, vec![object(0.0)], vec![object(16.0)]);
+        renamed.name = "pièce au nom bien plus long qu'avant".to_string();
+        history.record_object_edit(1, 1, vec![object(16.0)], vec![renamed.clone()]);
+        level.layers[1].objects = vec![renamed.clone()];
+
+        assert_eq!(history.undo_stack.len(), 1);
+        assert_eq!(history.used(), history.undo_stack[0].command.memory_size());
+        history.undo(&mut level);
+        assert_eq!(level.layers[1].objects[0].x, 0.0);
+        history.redo(&mut level);
+        assert_eq!(level.layers[1].objects[0].name, renamed.name);
+
+        // Un autre objet ouvre une nouvelle entrée
+        history.record_object_edit(1, 2, Vec::new(), Vec::new());
+        assert_eq!(history.undo_stack.len(), 2);
+    }
+
+    #[test]
+    fn budget_evicts_oldest_but_keeps_last_entry() {
+        let mut level = level();
+        let single = EditCommand::Tiles { layer: 1, changes: vec![((0, 0), TileData::Empty, TileData::Empty)] }.memory_size();
+        let mut history = History::new(single * 2);
+        for x in 0..3 {
+            paint(&mut history, &mut level, x, 0, TileData::Color([2, 2, 2]));
+            history.end_stroke();
+        }
+        assert_eq!(history.undo_stack.len(), 2);
+        assert!(history.used() <= history.budget());
+
+        // Une entrée plus grosse que le budget reste annulable
+        history.set_budget(1);
+        assert_eq!(history.undo_stack.len(), 1);
+        history.undo(&mut level);
+        assert!(level.layers[1].get_tile(2, 0).is_empty());
+        assert_eq!(level.layers[1].get_tile(1, 0), TileData::Color([2, 2, 2]));
+    }
+}
@@ -1,20 +1,108 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Version actuelle du format des niveaux
+pub const LEVEL_FORMAT_VERSION: u32 = 2;
+
+/// Couleur qui signifiait "aucun tile" avant la version 2 du format
+const LEGACY_EMPTY_COLOR: [u8; 3] = [40, 40, 40];
 
 /// Référence vers un tile (peut être une couleur ou une texture)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TileData {
+    Empty,          // Aucun tile (jamais stocké dans un calque)
     Color([u8; 3]), // RGB direct
-    Texture { tileset_id: usize, tile_index: u32 },
+    Texture {
+        tileset_id: usize,
+        tile_index: u32,
+        // Absent des anciens fichiers et omis quand le tile n'est pas transformé
+        #[serde(default, skip_serializing_if = "TileFlags::is_none")]
+        flags: TileFlags,
+    },
+    Value(u8),      // Valeur d'un calque de collision (voir `LogicValue`)
 }
 
 impl TileData {
     pub fn empty() -> Self {
-        TileData::Color([40, 40, 40])
+        TileData::Empty
     }
     
     pub fn is_empty(&self) -> bool {
-        matches!(self, TileData::Color([40, 40, 40]))
+        matches!(self, TileData::Empty)
+    }
+
+    /// Tile texturé sans transformation
+    pub fn texture(tileset_id: usize, tile_index: u32) -> Self {
+        TileData::Texture { tileset_id, tile_index, flags: TileFlags::NONE }
+    }
+
+    /// Modifie les transformations d'un tile texturé (les autres sont inchangés)
+    pub fn with_flags(self, transform: impl FnOnce(TileFlags) -> TileFlags) -> Self {
+        match self {
+            TileData::Texture { tileset_id, tile_index, flags } => {
+                TileData::Texture { tileset_id, tile_index, flags: transform(flags) }
+            }
+            other => other,
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Transformations d'un tile texturé : rotation de 90° dans le sens horaire
+/// (appliquée en premier), puis retournements horizontal et vertical
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TileFlags {
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub flip_x: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub flip_y: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub rotate: bool,
+}
+
+impl TileFlags {
+    pub const NONE: TileFlags = TileFlags { flip_x: false, flip_y: false, rotate: false };
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Ajoute un retournement horizontal
+    pub fn flipped_x(self) -> Self {
+        TileFlags { flip_x: !self.flip_x, ..self }
+    }
+
+    /// Ajoute un retournement vertical
+    pub fn flipped_y(self) -> Self {
+        TileFlags { flip_y: !self.flip_y, ..self }
+    }
+
+    /// Ajoute une rotation de 90° dans le sens horaire
+    pub fn rotated(self) -> Self {
+        // Tourner après un retournement horizontal revient à retourner
+        // verticalement avant de tourner (et inversement) ; deux quarts de
+        // tour équivalent aux deux retournements
+        if self.rotate {
+            TileFlags { flip_x: !self.flip_y, flip_y: !self.flip_x, rotate: false }
+        } else {
+            TileFlags { flip_x: self.flip_y, flip_y: self.flip_x, rotate: true }
+        }
+    }
+
+    /// Point de l'image source (coordonnées 0..1 dans le tile) affiché au
+    /// point (x, y) du tile transformé
+    pub fn source_point(self, x: f32, y: f32) -> (f32, f32) {
+        let x = if self.flip_x { 1.0 - x } else { x };
+        let y = if self.flip_y { 1.0 - y } else { y };
+        if self.rotate {
+            (y, 1.0 - x)
+        } else {
+            (x, y)
+        }
     }
 }
 
@@ -72,14 +160,318 @@ impl TileType {
     }
 }
 
+/// Encodage des tiles d'un calque dans le fichier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TileEncoding {
+    #[default]
+    Map,  // Une entrée "x,y" par tile
+    Rows, // Palette + une ligne compressée (RLE) par rangée
+}
+
+impl TileEncoding {
+    fn is_map(&self) -> bool {
+        *self == TileEncoding::Map
+    }
+}
+
+/// Nature d'un calque
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LayerKind {
+    #[default]
+    Tiles,   // Grille de tiles
+    Objects, // Objets librement positionnés
+    Logic,   // Grille de valeurs (collision, échelles, eau...) sans rendu dans le jeu
+}
+
+impl LayerKind {
+    fn is_tiles(&self) -> bool {
+        *self == LayerKind::Tiles
+    }
+}
+
+/// Valeur d'un calque de collision : nom utilisé par le jeu et couleur de
+/// l'aperçu dans l'éditeur. La valeur 0 signifie toujours « rien ».
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogicValue {
+    pub value: u8,
+    pub name: String,
+    pub color: [u8; 3],
+}
+
+impl LogicValue {
+    pub fn new(value: u8, name: &str, color: [u8; 3]) -> Self {
+        Self { value, name: name.to_string(), color }
+    }
+
+    /// Valeurs d'un nouveau calque de collision
+    pub fn defaults() -> Vec<LogicValue> {
+        vec![
+            LogicValue::new(1, "solid", [220, 50, 50]),
+            LogicValue::new(2, "one_way", [240, 170, 40]),
+            LogicValue::new(3, "ladder", [150, 100, 40]),
+            LogicValue::new(4, "water", [40, 120, 230]),
+            LogicValue::new(5, "hazard", [200, 40, 200]),
+        ]
+    }
+}
+
+/// Grille typée d'un calque de collision : une valeur par case du niveau
+/// (0 = rien), avec les noms et couleurs de ses valeurs
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogicGrid {
+    pub width: u32,
+    pub height: u32,
+    pub cells: Vec<u8>,  // Rangée par rangée, `width * height` valeurs
+    pub values: Vec<LogicValue>,
+}
+
+impl LogicGrid {
+    /// Valeur d'une case (0 hors de la grille)
+    pub fn get(&self, x: i32, y: i32) -> u8 {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return 0;
+        }
+        self.cells[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    /// Valeur qui porte un nom ("solid", "water"...)
+    pub fn value(&self, name: &str) -> Option<u8> {
+        self.values.iter().find(|value| value.name == name).map(|value| value.value)
+    }
+
+    /// Nom de la valeur d'une case, s'il y en a une
+    pub fn name_at(&self, x: i32, y: i32) -> Option<&str> {
+        let value = self.get(x, y);
+        self.values.iter().find(|v| v.value == value).map(|v| v.name.as_str())
+    }
+
+    /// La case porte la valeur nommée `name`
+    pub fn is(&self, x: i32, y: i32, name: &str) -> bool {
+        self.value(name).is_some_and(|value| value != 0 && self.get(x, y) == value)
+    }
+
+    /// Cases qui portent la valeur nommée `name`
+    pub fn cells_named(&self, name: &str) -> Vec<(i32, i32)> {
+        let Some(value) = self.value(name) else {
+            return Vec::new();
+        };
+        (0..self.height as i32)
+            .flat_map(|y| (0..self.width as i32).map(move |x| (x, y)))
+            .filter(|&(x, y)| value != 0 && self.get(x, y) == value)
+            .collect()
+    }
+}
+
+/// Objet librement positionné (point de départ, ennemi, déclencheur, porte...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelObject {
+    pub id: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub object_type: String,
+    // Position du coin haut-gauche et taille, en pixels
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    // Rotation en degrés (sens horaire) autour du coin haut-gauche, comme Tiled
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+impl LevelObject {
+    pub fn new(id: u32, object_type: &str, x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            id,
+            name: format!("{} {}", object_type, id),
+            object_type: object_type.to_string(),
+            x,
+            y,
+            width,
+            height,
+            rotation: 0.0,
+            properties: BTreeMap::new(),
+        }
+    }
+
+    /// Convertit un point (en pixels) dans le repère local de l'objet
+    pub fn to_local(&self, px: f32, py: f32) -> (f32, f32) {
+        let (sin, cos) = (-self.rotation.to_radians()).sin_cos();
+        let (dx, dy) = (px - self.x, py - self.y);
+        (dx * cos - dy * sin, dx * sin + dy * cos)
+    }
+
+    /// Convertit un point du repère local de l'objet en pixels
+    pub fn to_world(&self, lx: f32, ly: f32) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        (self.x + lx * cos - ly * sin, self.y + lx * sin + ly * cos)
+    }
+
+    /// Indique si un point (en pixels) est à l'intérieur de l'objet
+    pub fn contains(&self, px: f32, py: f32) -> bool {
+        let (lx, ly) = self.to_local(px, py);
+        lx >= 0.0 && ly >= 0.0 && lx <= self.width && ly <= self.height
+    }
+
+    /// Coins de l'objet en pixels (haut-gauche, haut-droit, bas-droit, bas-gauche)
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        [
+            self.to_world(0.0, 0.0),
+            self.to_world(self.width, 0.0),
+            self.to_world(self.width, self.height),
+            self.to_world(0.0, self.height),
+        ]
+    }
+}
+
 /// Représente une couche (layer) du niveau
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Layer {
     pub name: String,
     pub visible: bool,
-    // Utiliser Vec pour la sérialisation JSON (HashMap ne supporte pas les tuples comme clés)
-    #[serde(serialize_with = "serialize_tiles", deserialize_with = "deserialize_tiles")]
+    #[serde(default)]
+    pub kind: LayerKind,
+    #[serde(default)]
+    pub encoding: TileEncoding,
+    // Les clés (x, y) sont écrites en "x,y" ou en rangées compressées selon `encoding`
+    #[serde(default, deserialize_with = "deserialize_tiles")]
     pub tiles: HashMap<(i32, i32), TileData>,
+    #[serde(default)]
+    pub objects: Vec<LevelObject>,
+    // Apparence : opacité (0 à 1), teinte multipliée aux couleurs des tiles,
+    // décalage en pixels et facteurs de défilement (1 = suit la caméra, 0 = fixe)
+    #[serde(default = "full_opacity")]
+    pub opacity: f32,
+    #[serde(default = "white")]
+    pub tint: [u8; 3],
+    #[serde(default)]
+    pub offset: (f32, f32),
+    #[serde(default = "unit_parallax")]
+    pub parallax: (f32, f32),
+    // Verrou (aucune modification dans l'éditeur), solo (seuls les calques solo
+    // sont affichés dans l'éditeur) et groupe parent (`LayerGroup::id`)
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub solo: bool,
+    #[serde(default)]
+    pub group: Option<u32>,
+    // Valeurs d'un calque de collision (`LayerKind::Logic`)
+    #[serde(default)]
+    pub logic_values: Vec<LogicValue>,
+}
+
+fn full_opacity() -> f32 {
+    1.0
+}
+
+fn is_full_opacity(opacity: &f32) -> bool {
+    *opacity == 1.0
+}
+
+fn white() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+fn is_white(tint: &[u8; 3]) -> bool {
+    *tint == white()
+}
+
+fn is_zero_offset(offset: &(f32, f32)) -> bool {
+    *offset == (0.0, 0.0)
+}
+
+fn unit_parallax() -> (f32, f32) {
+    (1.0, 1.0)
+}
+
+fn is_unit_parallax(parallax: &(f32, f32)) -> bool {
+    *parallax == unit_parallax()
+}
+
+// Sérialisation manuelle pour que l'encodage des tiles dépende du champ `encoding`
+impl Serialize for Layer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct LayerRef<'a> {
+            name: &'a str,
+            visible: bool,
+            #[serde(skip_serializing_if = "LayerKind::is_tiles")]
+            kind: LayerKind,
+            #[serde(skip_serializing_if = "TileEncoding::is_map")]
+            encoding: TileEncoding,
+            tiles: EncodedTiles<'a>,
+            #[serde(skip_serializing_if = "<[_]>::is_empty")]
+            objects: &'a [LevelObject],
+            #[serde(skip_serializing_if = "is_full_opacity")]
+            opacity: f32,
+            #[serde(skip_serializing_if = "is_white")]
+            tint: [u8; 3],
+            #[serde(skip_serializing_if = "is_zero_offset")]
+            offset: (f32, f32),
+            #[serde(skip_serializing_if = "is_unit_parallax")]
+            parallax: (f32, f32),
+            #[serde(skip_serializing_if = "is_false")]
+            locked: bool,
+            #[serde(skip_serializing_if = "is_false")]
+            solo: bool,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            group: Option<u32>,
+            #[serde(skip_serializing_if = "<[_]>::is_empty")]
+            logic_values: &'a [LogicValue],
+        }
+
+        LayerRef {
+            name: &self.name,
+            visible: self.visible,
+            kind: self.kind,
+            encoding: self.encoding,
+            tiles: EncodedTiles {
+                tiles: &self.tiles,
+                encoding: self.encoding,
+            },
+            objects: &self.objects,
+            opacity: self.opacity,
+            tint: self.tint,
+            offset: self.offset,
+            parallax: self.parallax,
+            locked: self.locked,
+            solo: self.solo,
+            group: self.group,
+            logic_values: &self.logic_values,
+        }
+        .serialize(serializer)
+    }
+}
+
+struct EncodedTiles<'a> {
+    tiles: &'a HashMap<(i32, i32), TileData>,
+    encoding: TileEncoding,
+}
+
+impl Serialize for EncodedTiles<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Des tiles trop écartés pour tenir dans une rangée relisible restent en "x,y"
+        match self.encoding {
+            TileEncoding::Rows if rows_fit(self.tiles) => encode_rows(self.tiles).serialize(serializer),
+            _ => serialize_tiles(self.tiles, serializer),
+        }
+    }
+}
+
+/// Positions des tiles triées ligne par ligne (y puis x) pour une sortie stable
+fn sorted_positions(tiles: &HashMap<(i32, i32), TileData>) -> Vec<(i32, i32)> {
+    let mut positions: Vec<(i32, i32)> = tiles.keys().copied().collect();
+    positions.sort_by_key(|&(x, y)| (y, x));
+    positions
 }
 
 // Fonctions de sérialisation personnalisées pour HashMap<(i32, i32), TileData>
@@ -92,20 +484,126 @@ where
 {
     use serde::ser::SerializeMap;
     let mut map = serializer.serialize_map(Some(tiles.len()))?;
-    for ((x, y), tile) in tiles {
+    for (x, y) in sorted_positions(tiles) {
         let key = format!("{},{}", x, y);
-        map.serialize_entry(&key, tile)?;
+        map.serialize_entry(&key, &tiles[&(x, y)])?;
     }
     map.end()
 }
 
+/// Forme compacte d'un calque : chaque rangée est une suite de plages
+/// "n*i" (n cases du tile i de la palette), "." désignant une case vide
+#[derive(Serialize)]
+struct RowsRef {
+    origin: (i32, i32),
+    palette: Vec<TileData>,
+    rows: Vec<String>,
+}
+
+fn encode_rows(tiles: &HashMap<(i32, i32), TileData>) -> RowsRef {
+    let positions = sorted_positions(tiles);
+    let (Some(&(_, min_y)), Some(&(_, max_y))) = (positions.first(), positions.last()) else {
+        return RowsRef { origin: (0, 0), palette: Vec::new(), rows: Vec::new() };
+    };
+    let min_x = positions.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let max_x = positions.iter().map(|&(x, _)| x).max().unwrap_or(0);
+
+    let mut palette: Vec<TileData> = Vec::new();
+    let mut rows = Vec::new();
+    for y in min_y..=max_y {
+        // Indices de palette de la rangée, sans les cases vides de fin
+        let mut cells: Vec<Option<usize>> = (min_x..=max_x)
+            .map(|x| {
+                tiles.get(&(x, y)).map(|tile| {
+                    palette.iter().position(|p| p == tile).unwrap_or_else(|| {
+                        palette.push(*tile);
+                        palette.len() - 1
+                    })
+                })
+            })
+            .collect();
+        while cells.last() == Some(&None) {
+            cells.pop();
+        }
+
+        let mut runs = Vec::new();
+        let mut i = 0;
+        while i < cells.len() {
+            let run = cells[i..].iter().take_while(|c| **c == cells[i]).count();
+            let symbol = cells[i].map_or(".".to_string(), |idx| idx.to_string());
+            runs.push(if run > 1 { format!("{}*{}", run, symbol) } else { symbol });
+            i += run;
+        }
+        rows.push(runs.join(" "));
+    }
+
+    RowsRef { origin: (min_x, min_y), palette, rows }
+}
+
+/// Largeur maximale d'une rangée compressée, en cases : borne la mémoire
+/// allouée pour un fichier corrompu ou malveillant
+const MAX_ROW_WIDTH: i32 = 1 << 16;
+
+/// Indique si les tiles tiennent dans des rangées d'au plus `MAX_ROW_WIDTH` cases
+fn rows_fit(tiles: &HashMap<(i32, i32), TileData>) -> bool {
+    let min_x = tiles.keys().map(|&(x, _)| x as i64).min().unwrap_or(0);
+    let max_x = tiles.keys().map(|&(x, _)| x as i64).max().unwrap_or(0);
+    max_x - min_x < MAX_ROW_WIDTH as i64
+}
+
+fn decode_rows(
+    origin: (i32, i32),
+    palette: &[TileData],
+    rows: &[String],
+) -> Result<HashMap<(i32, i32), TileData>, String> {
+    let mut map = HashMap::new();
+    for (dy, row) in rows.iter().enumerate() {
+        let y = i32::try_from(dy)
+            .ok()
+            .and_then(|dy| origin.1.checked_add(dy))
+            .ok_or_else(|| "trop de rangées".to_string())?;
+        let mut x = origin.0;
+        let mut width = 0;
+        for run in row.split_whitespace() {
+            let (count, symbol) = match run.split_once('*') {
+                Some((count, symbol)) => (
+                    count
+                        .parse::<i32>()
+                        .ok()
+                        .filter(|&count| count > 0)
+                        .ok_or_else(|| format!("plage invalide '{}'", run))?,
+                    symbol,
+                ),
+                None => (1, run),
+            };
+            if count > MAX_ROW_WIDTH - width {
+                return Err(format!("rangée plus large que {} cases", MAX_ROW_WIDTH));
+            }
+            width += count;
+            let next_x = x.checked_add(count).ok_or_else(|| format!("plage hors limites '{}'", run))?;
+            if symbol != "." {
+                let tile = symbol
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| palette.get(idx))
+                    .ok_or_else(|| format!("index de palette invalide '{}'", symbol))?;
+                for x in x..next_x {
+                    map.insert((x, y), *tile);
+                }
+            }
+            x = next_x;
+        }
+    }
+    Ok(map)
+}
+
 fn deserialize_tiles<'de, D>(
     deserializer: D,
 ) -> Result<HashMap<(i32, i32), TileData>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    use serde::de::{MapAccess, Visitor};
+    use serde::de::{Error, MapAccess, Visitor};
     use std::fmt;
 
     struct TilesVisitor;
@@ -114,7 +612,7 @@ where
         type Value = HashMap<(i32, i32), TileData>;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("a map with string keys in 'x,y' format")
+            formatter.write_str("a map with string keys in 'x,y' format or compact rows")
         }
 
         fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
@@ -122,14 +620,31 @@ where
             M: MapAccess<'de>,
         {
             let mut map = HashMap::new();
-            while let Some((key, value)) = access.next_entry::<String, TileData>()? {
-                let parts: Vec<&str> = key.split(',').collect();
-                if parts.len() == 2 {
-                    if let (Ok(x), Ok(y)) = (parts[0].parse::<i32>(), parts[1].parse::<i32>()) {
-                        map.insert((x, y), value);
+            let mut origin = None;
+            let mut palette = None;
+            let mut rows = None;
+            while let Some(key) = access.next_key::<String>()? {
+                match key.as_str() {
+                    "origin" => origin = Some(access.next_value::<(i32, i32)>()?),
+                    "palette" => palette = Some(access.next_value::<Vec<TileData>>()?),
+                    "rows" => rows = Some(access.next_value::<Vec<String>>()?),
+                    _ => {
+                        let value = access.next_value::<TileData>()?;
+                        let parts: Vec<&str> = key.split(',').collect();
+                        if parts.len() == 2 {
+                            if let (Ok(x), Ok(y)) = (parts[0].parse::<i32>(), parts[1].parse::<i32>()) {
+                                map.insert((x, y), value);
+                            }
+                        }
                     }
                 }
             }
+            if let Some(rows) = rows {
+                let palette = palette.unwrap_or_default();
+                map.extend(
+                    decode_rows(origin.unwrap_or((0, 0)), &palette, &rows).map_err(M::Error::custom)?,
+                );
+            }
             Ok(map)
         }
     }
@@ -142,10 +657,84 @@ impl Layer {
         Self {
             name,
             visible: true,
+            kind: LayerKind::Tiles,
+            encoding: TileEncoding::Map,
             tiles: HashMap::new(),
+            objects: Vec::new(),
+            opacity: 1.0,
+            tint: white(),
+            offset: (0.0, 0.0),
+            parallax: unit_parallax(),
+            locked: false,
+            solo: false,
+            group: None,
+            logic_values: Vec::new(),
+        }
+    }
+
+    /// Couleur finale d'un pixel `rgb` du calque : multipliée par la teinte,
+    /// avec l'opacité du calque comme alpha
+    pub fn shade(&self, rgb: [u8; 3]) -> [u8; 4] {
+        let channel = |value: u8, tint: u8| (value as u16 * tint as u16 / 255) as u8;
+        [
+            channel(rgb[0], self.tint[0]),
+            channel(rgb[1], self.tint[1]),
+            channel(rgb[2], self.tint[2]),
+            (self.opacity.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ]
+    }
+
+    /// Crée un calque d'objets
+    pub fn new_objects(name: String) -> Self {
+        Self {
+            kind: LayerKind::Objects,
+            ..Self::new(name)
         }
     }
 
+    /// Crée un calque de collision avec les valeurs par défaut
+    pub fn new_logic(name: String) -> Self {
+        Self {
+            kind: LayerKind::Logic,
+            logic_values: LogicValue::defaults(),
+            ..Self::new(name)
+        }
+    }
+
+    /// Indique si un tile peut être posé dans ce calque : uniquement des
+    /// valeurs dans un calque de collision, jamais de valeur ailleurs
+    pub fn accepts(&self, tile: &TileData) -> bool {
+        match tile {
+            TileData::Empty => true,
+            TileData::Value(_) => self.kind == LayerKind::Logic,
+            TileData::Color(_) | TileData::Texture { .. } => self.kind != LayerKind::Logic,
+        }
+    }
+
+    /// Définition d'une valeur de ce calque de collision
+    pub fn logic_value(&self, value: u8) -> Option<&LogicValue> {
+        self.logic_values.iter().find(|v| v.value == value)
+    }
+
+    /// Grille typée des valeurs du calque sur `width`×`height` cases (les cases
+    /// qui ne portent pas de `TileData::Value` valent 0)
+    pub fn logic_grid(&self, width: u32, height: u32) -> LogicGrid {
+        let mut cells = vec![0; (width * height) as usize];
+        for (&(x, y), tile) in &self.tiles {
+            if let TileData::Value(value) = *tile {
+                if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                    cells[(y as u32 * width + x as u32) as usize] = value;
+                }
+            }
+        }
+        LogicGrid { width, height, cells, values: self.logic_values.clone() }
+    }
+
+    /// Retourne l'objet le plus haut (dernier dessiné) sous un point en pixels
+    pub fn object_at(&self, px: f32, py: f32) -> Option<&LevelObject> {
+        self.objects.iter().rev().find(|object| object.contains(px, py))
+    }
+
     pub fn set_tile(&mut self, x: i32, y: i32, tile_data: TileData) {
         if tile_data.is_empty() {
             self.tiles.remove(&(x, y));
@@ -164,26 +753,134 @@ impl Layer {
     pub fn clear(&mut self) {
         self.tiles.clear();
     }
+
+    /// Retourne la zone contiguë (4 voisins) de tiles identiques à celui en (x, y),
+    /// limitée aux bornes du niveau
+    pub fn flood_region(&self, x: i32, y: i32, width: u32, height: u32) -> Vec<(i32, i32)> {
+        let in_bounds = |tx: i32, ty: i32| tx >= 0 && ty >= 0 && tx < width as i32 && ty < height as i32;
+        if !in_bounds(x, y) {
+            return Vec::new();
+        }
+
+        let target = self.get_tile(x, y);
+        let mut visited = HashSet::new();
+        let mut stack = vec![(x, y)];
+        let mut region = Vec::new();
+        visited.insert((x, y));
+
+        while let Some((cx, cy)) = stack.pop() {
+            region.push((cx, cy));
+            for (nx, ny) in [(cx + 1, cy), (cx - 1, cy), (cx, cy + 1), (cx, cy - 1)] {
+                if in_bounds(nx, ny) && self.get_tile(nx, ny) == target && visited.insert((nx, ny)) {
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        region
+    }
+
+    /// Retourne toutes les positions du calque portant le même tile que (x, y),
+    /// limitées aux bornes du niveau
+    pub fn matching_region(&self, x: i32, y: i32, width: u32, height: u32) -> Vec<(i32, i32)> {
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+            return Vec::new();
+        }
+
+        let target = self.get_tile(x, y);
+        let mut region = Vec::new();
+        for ty in 0..height as i32 {
+            for tx in 0..width as i32 {
+                if self.get_tile(tx, ty) == target {
+                    region.push((tx, ty));
+                }
+            }
+        }
+        region
+    }
+
+    /// Décale les tiles et les objets du calque de (dx, dy) cases
+    pub fn shift(&mut self, dx: i32, dy: i32, tile_size: u32) {
+        self.tiles = self.tiles.drain().map(|((x, y), tile)| ((x + dx, y + dy), tile)).collect();
+        for object in &mut self.objects {
+            object.x += (dx * tile_size as i32) as f32;
+            object.y += (dy * tile_size as i32) as f32;
+        }
+    }
+}
+
+/// Point d'ancrage d'un redimensionnement dans la grille 3x3 :
+/// 0 = gauche (haut), 1 = centre, 2 = droite (bas)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Anchor {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Anchor {
+    pub const TOP_LEFT: Anchor = Anchor { x: 0, y: 0 };
+
+    /// Décalage du contenu quand une dimension passe de `old` à `new` cases
+    fn offset(old: u32, new: u32, anchor: u8) -> i32 {
+        (new as i32 - old as i32) * anchor.min(2) as i32 / 2
+    }
+}
+
+/// Groupe (dossier) de calques. Les groupes s'imbriquent par `parent` ; leur
+/// visibilité et leur verrou s'appliquent à tous les calques qu'ils contiennent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerGroup {
+    pub id: u32,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<u32>,
+    #[serde(default = "visible_group")]
+    pub visible: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub locked: bool,
+}
+
+fn visible_group() -> bool {
+    true
 }
 
 /// Représente un niveau complet
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Level {
+    // Absent des fichiers créés avant l'introduction de TileData::Empty
+    #[serde(default = "legacy_format_version")]
+    pub format_version: u32,
     pub name: String,
     pub width: u32,
     pub height: u32,
     pub tile_size: u32,
     pub layers: Vec<Layer>,
+    // Coin haut-gauche du niveau sur la carte du monde, en pixels
+    #[serde(default, skip_serializing_if = "is_origin")]
+    pub world_position: (i32, i32),
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<LayerGroup>,
+}
+
+fn legacy_format_version() -> u32 {
+    1
+}
+
+fn is_origin(position: &(i32, i32)) -> bool {
+    *position == (0, 0)
 }
 
 impl Level {
     pub fn new(name: String, width: u32, height: u32, tile_size: u32) -> Self {
         let mut level = Self {
+            format_version: LEVEL_FORMAT_VERSION,
             name,
             width,
             height,
             tile_size,
             layers: Vec::new(),
+            world_position: (0, 0),
+            groups: Vec::new(),
         };
         level.layers.push(Layer::new("Background".to_string()));
         level.layers.push(Layer::new("Main".to_string()));
@@ -195,6 +892,169 @@ impl Level {
         self.layers.push(Layer::new(name));
     }
 
+    pub fn group(&self, id: u32) -> Option<&LayerGroup> {
+        self.groups.iter().find(|group| group.id == id)
+    }
+
+    /// Groupe `id` puis ses parents, jusqu'à la racine (un parent absent ou une
+    /// boucle arrête la remontée)
+    pub fn group_chain(&self, id: Option<u32>) -> Vec<&LayerGroup> {
+        let mut chain: Vec<&LayerGroup> = Vec::new();
+        let mut next = id;
+        while let Some(group) = next.and_then(|id| self.group(id)) {
+            if chain.iter().any(|g| g.id == group.id) {
+                break;
+            }
+            chain.push(group);
+            next = group.parent;
+        }
+        chain
+    }
+
+    /// Nom complet d'un groupe ("Décor / Arbres"), vide hors groupe
+    pub fn group_path(&self, id: Option<u32>) -> String {
+        let names: Vec<&str> = self.group_chain(id).iter().rev().map(|group| group.name.as_str()).collect();
+        names.join(" / ")
+    }
+
+    /// Le calque est visible et aucun de ses groupes n'est masqué
+    pub fn is_layer_visible(&self, index: usize) -> bool {
+        self.layers
+            .get(index)
+            .is_some_and(|layer| layer.visible && self.group_chain(layer.group).iter().all(|group| group.visible))
+    }
+
+    /// Le calque ou l'un de ses groupes est verrouillé
+    pub fn is_layer_locked(&self, index: usize) -> bool {
+        self.layers
+            .get(index)
+            .is_some_and(|layer| layer.locked || self.group_chain(layer.group).iter().any(|group| group.locked))
+    }
+
+    /// Groupes dans l'ordre de l'arborescence, avec leur profondeur (les groupes
+    /// dont le parent n'existe plus sont à la racine)
+    pub fn group_tree(&self) -> Vec<(&LayerGroup, usize)> {
+        fn visit<'a>(level: &'a Level, parent: Option<u32>, depth: usize, out: &mut Vec<(&'a LayerGroup, usize)>) {
+            for group in level.groups.iter().filter(|group| group.parent == parent) {
+                if out.iter().any(|(g, _)| g.id == group.id) {
+                    continue;
+                }
+                out.push((group, depth));
+                visit(level, Some(group.id), depth + 1, out);
+            }
+        }
+
+        let mut tree = Vec::new();
+        visit(self, None, 0, &mut tree);
+        for group in &self.groups {
+            if !tree.iter().any(|(g, _)| g.id == group.id) {
+                tree.push((group, 0));
+                visit(self, Some(group.id), 1, &mut tree);
+            }
+        }
+        tree
+    }
+
+    /// Crée un groupe (dans `parent` s'il est donné) et retourne son identifiant
+    pub fn add_group(&mut self, name: String, parent: Option<u32>) -> u32 {
+        let id = self.groups.iter().map(|group| group.id + 1).max().unwrap_or(1);
+        self.groups.push(LayerGroup { id, name, parent, visible: true, locked: false });
+        id
+    }
+
+    /// Supprime un groupe : ses calques et sous-groupes remontent dans son parent
+    pub fn remove_group(&mut self, id: u32) {
+        let Some(index) = self.groups.iter().position(|group| group.id == id) else {
+            return;
+        };
+        let parent = self.groups.remove(index).parent;
+        for group in self.groups.iter_mut().filter(|group| group.parent == Some(id)) {
+            group.parent = parent;
+        }
+        for layer in self.layers.iter_mut().filter(|layer| layer.group == Some(id)) {
+            layer.group = parent;
+        }
+    }
+
+    /// Change le parent d'un groupe. Refusé (false) si le groupe deviendrait
+    /// son propre descendant.
+    pub fn set_group_parent(&mut self, id: u32, parent: Option<u32>) -> bool {
+        if self.group_chain(parent).iter().any(|group| group.id == id) {
+            return false;
+        }
+        match self.groups.iter_mut().find(|group| group.id == id) {
+            Some(group) => {
+                group.parent = parent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Grille typée du calque de collision `layer_name`
+    pub fn logic_grid(&self, layer_name: &str) -> Option<LogicGrid> {
+        self.layers
+            .iter()
+            .find(|layer| layer.kind == LayerKind::Logic && layer.name == layer_name)
+            .map(|layer| layer.logic_grid(self.width, self.height))
+    }
+
+    /// Identifiant libre pour un nouvel objet (unique dans tout le niveau)
+    pub fn next_object_id(&self) -> u32 {
+        self.layers
+            .iter()
+            .flat_map(|layer| &layer.objects)
+            .map(|object| object.id + 1)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Retourne tous les objets d'un type donné, tous calques confondus
+    pub fn find_objects_by_type(&self, object_type: &str) -> Vec<&LevelObject> {
+        self.layers
+            .iter()
+            .flat_map(|layer| &layer.objects)
+            .filter(|object| object.object_type == object_type)
+            .collect()
+    }
+
+    /// Nombre de tiles qui référencent un tileset, tous calques confondus
+    pub fn count_tileset_references(&self, tileset_id: usize) -> usize {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.tiles.values())
+            .filter(|tile| matches!(tile, TileData::Texture { tileset_id: id, .. } if *id == tileset_id))
+            .count()
+    }
+
+    /// Redirige toutes les références d'un tileset vers un autre (mêmes indices de tiles).
+    /// Retourne le nombre de tiles modifiés.
+    pub fn remap_tileset(&mut self, from: usize, to: usize) -> usize {
+        let mut count = 0;
+        for tile in self.layers.iter_mut().flat_map(|layer| layer.tiles.values_mut()) {
+            if let TileData::Texture { tileset_id, .. } = tile {
+                if *tileset_id == from {
+                    *tileset_id = to;
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Efface les tiles qui référencent un tileset. Retourne le nombre de tiles retirés.
+    pub fn clear_tileset(&mut self, tileset_id: usize) -> usize {
+        let mut count = 0;
+        for layer in &mut self.layers {
+            let before = layer.tiles.len();
+            layer
+                .tiles
+                .retain(|_, tile| !matches!(tile, TileData::Texture { tileset_id: id, .. } if *id == tileset_id));
+            count += before - layer.tiles.len();
+        }
+        count
+    }
+
     pub fn remove_layer(&mut self, index: usize) -> bool {
         if self.layers.len() > 1 && index < self.layers.len() {
             self.layers.remove(index);
@@ -231,15 +1091,218 @@ impl Level {
         }
     }
 
-    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// Indique si une case est dans les limites du niveau
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+    }
+
+    /// Décale tout le contenu (tiles et objets de tous les calques) de (dx, dy) cases
+    pub fn shift(&mut self, dx: i32, dy: i32) {
+        for layer in &mut self.layers {
+            layer.shift(dx, dy, self.tile_size);
+        }
+    }
+
+    /// Retire les tiles hors des limites du niveau. Retourne le nombre de tiles retirés.
+    pub fn remove_out_of_bounds(&mut self) -> usize {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let mut count = 0;
+        for layer in &mut self.layers {
+            let before = layer.tiles.len();
+            layer.tiles.retain(|&(x, y), _| x >= 0 && y >= 0 && x < width && y < height);
+            count += before - layer.tiles.len();
+        }
+        count
+    }
+
+    /// Nombre d'objets dont le coin haut-gauche est hors des limites (ils sont conservés)
+    pub fn objects_out_of_bounds(&self) -> usize {
+        let (width, height) = ((self.width * self.tile_size) as f32, (self.height * self.tile_size) as f32);
+        self.layers
+            .iter()
+            .flat_map(|layer| &layer.objects)
+            .filter(|object| object.x < 0.0 || object.y < 0.0 || object.x >= width || object.y >= height)
+            .count()
+    }
+
+    /// Redimensionne le niveau ; le contenu est placé selon l'ancrage et les
+    /// tiles qui sortent des limites sont retirés (nombre retourné)
+    pub fn resize(&mut self, width: u32, height: u32, anchor: Anchor) -> usize {
+        let dx = Anchor::offset(self.width, width, anchor.x);
+        let dy = Anchor::offset(self.height, height, anchor.y);
+        self.shift(dx, dy);
+        self.width = width;
+        self.height = height;
+        self.remove_out_of_bounds()
+    }
+
+    /// Rectangle (min_x, min_y, max_x, max_y) occupé par les tiles situés dans les limites
+    pub fn used_bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.tiles.keys())
+            .filter(|&&(x, y)| self.in_bounds(x, y))
+            .fold(None, |bounds, &(x, y)| match bounds {
+                None => Some((x, y, x, y)),
+                Some((min_x, min_y, max_x, max_y)) => Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))),
+            })
+    }
+
+    /// Recadre le niveau sur ses tiles. Retourne le nombre de tiles hors limites
+    /// retirés, ou None si le niveau ne contient aucun tile.
+    pub fn crop_to_content(&mut self) -> Option<usize> {
+        let (min_x, min_y, max_x, max_y) = self.used_bounds()?;
+        self.shift(-min_x, -min_y);
+        self.width = (max_x - min_x + 1) as u32;
+        self.height = (max_y - min_y + 1) as u32;
+        Some(self.remove_out_of_bounds())
+    }
+
+    /// Met à niveau un niveau chargé depuis une ancienne version du format
+    pub fn migrate(&mut self) {
+        if self.format_version < 2 {
+            // Avant la v2, la couleur [40, 40, 40] représentait une case vide
+            for layer in &mut self.layers {
+                layer.tiles.retain(|_, tile| *tile != TileData::Color(LEGACY_EMPTY_COLOR));
+            }
+        }
+        // Un tile vide ne doit jamais être stocké
+        for layer in &mut self.layers {
+            layer.tiles.retain(|_, tile| !tile.is_empty());
+        }
+        self.format_version = LEVEL_FORMAT_VERSION;
+    }
+
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
         let json = serde_json::to_string_pretty(self)?;
         std::fs::write(path, json)?;
         Ok(())
     }
 
-    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
         let json = std::fs::read_to_string(path)?;
-        let level = serde_json::from_str(&json)?;
+        let mut level: Level = serde_json::from_str(&json)?;
+        level.migrate();
         Ok(level)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(layer: &Layer) -> Layer {
+        serde_json::from_str(&serde_json::to_string(layer).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn rows_encoding_round_trip() {
+        let mut layer = Layer::new("Main".to_string());
+        layer.encoding = TileEncoding::Rows;
+        let flipped = TileData::texture(3, 7).with_flags(|flags| flags.rotated().flipped_x());
+        for x in -2..3 {
+            layer.set_tile(x, -1, TileData::Color([10, 20, 30]));
+        }
+        layer.set_tile(4, 0, flipped);
+        layer.set_tile(-2, 2, TileData::Value(1));
+
+        let restored = round_trip(&layer);
+        assert_eq!(restored.encoding, TileEncoding::Rows);
+        assert_eq!(restored.tiles, layer.tiles);
+    }
+
+    #[test]
+    fn rows_encoding_compresses_runs() {
+        let mut tiles = HashMap::new();
+        for x in 0..4 {
+            tiles.insert((x, 0), TileData::Color([1, 2, 3]));
+        }
+        tiles.insert((6, 0), TileData::texture(0, 5));
+        tiles.insert((0, 1), TileData::texture(0, 5));
+
+        let rows = encode_rows(&tiles);
+        assert_eq!(rows.origin, (0, 0));
+        assert_eq!(rows.rows, vec!["4*0 2*. 1", "1"]);
+        assert_eq!(decode_rows(rows.origin, &rows.palette, &rows.rows).unwrap(), tiles);
+    }
+
+    #[test]
+    fn rows_decoding_rejects_bad_runs() {
+        let palette = [TileData::Color([0, 0, 0])];
+        assert!(decode_rows((0, 0), &palette, &["x*0".to_string()]).is_err());
+        assert!(decode_rows((0, 0), &palette, &["2*1".to_string()]).is_err());
+        assert!(decode_rows((0, 0), &palette, &["-5*0".to_string()]).is_err());
+        assert!(decode_rows((0, 0), &palette, &["0*0".to_string()]).is_err());
+        assert!(decode_rows((0, 0), &palette, &["2000000000*0".to_string()]).is_err());
+        assert!(decode_rows((0, 0), &palette, &["40000*0 40000*.".to_string()]).is_err());
+        assert!(decode_rows((i32::MAX - 1, 0), &palette, &["3*0".to_string()]).is_err());
+        assert_eq!(decode_rows((0, 0), &palette, &["65536*.".to_string()]).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn rows_encoding_falls_back_when_too_wide() {
+        let mut layer = Layer::new("Main".to_string());
+        layer.encoding = TileEncoding::Rows;
+        layer.set_tile(-MAX_ROW_WIDTH, 0, TileData::Color([1, 2, 3]));
+        layer.set_tile(MAX_ROW_WIDTH, 0, TileData::Color([1, 2, 3]));
+        let json = serde_json::to_string(&layer).unwrap();
+        assert!(!json.contains("palette"));
+        assert_eq!(round_trip(&layer).tiles, layer.tiles);
+    }
+
+    #[test]
+    fn flags_compose() {
+        let none = TileFlags::NONE;
+        assert_eq!(none.rotated().rotated(), none.flipped_x().flipped_y());
+        assert_eq!(none.rotated().rotated().rotated().rotated(), none);
+        assert_eq!(none.flipped_x().flipped_x(), none);
+        // Quart de tour horaire : le coin haut gauche affiche le coin bas gauche de la source
+        assert_eq!(none.rotated().source_point(0.0, 0.0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn flags_omitted_when_untransformed() {
+        let json = serde_json::to_string(&TileData::texture(1, 2)).unwrap();
+        assert!(!json.contains("flags"));
+        let flipped = TileData::texture(1, 2).with_flags(TileFlags::flipped_y);
+        let restored: TileData = serde_json::from_str(&serde_json::to_string(&flipped).unwrap()).unwrap();
+        assert_eq!(restored, flipped);
+    }
+
+    /// Niveau écrit au format `version` (None : fichier antérieur au champ)
+    fn level_json(version: Option<u32>) -> String {
+        let version = version.map_or(String::new(), |v| format!("\"format_version\": {},", v));
+        format!(
+            r#"{{ {} "name": "Ancien", "width": 4, "height": 4, "tile_size": 16, "layers": [
+                {{ "name": "Main", "visible": true, "tiles": {{
+                    "0,0": {{"Color": [40, 40, 40]}},
+                    "1,0": {{"Color": [139, 69, 19]}},
+                    "2,0": "Empty"
+                }} }}
+            ] }}"#,
+            version
+        )
+    }
+
+    #[test]
+    fn migrate_v1_drops_legacy_empty_color() {
+        for version in [None, Some(1)] {
+            let mut level: Level = serde_json::from_str(&level_json(version)).unwrap();
+            assert_eq!(level.format_version, 1);
+            level.migrate();
+            assert_eq!(level.format_version, LEVEL_FORMAT_VERSION);
+            assert!(level.layers[0].get_tile(0, 0).is_empty());
+            assert!(level.layers[0].get_tile(2, 0).is_empty());
+            assert_eq!(level.layers[0].get_tile(1, 0), TileData::Color([139, 69, 19]));
+            assert_eq!(level.layers[0].tiles.len(), 1);
+        }
+    }
+
+    #[test]
+    fn migrate_v2_keeps_dark_grey() {
+        let mut level: Level = serde_json::from_str(&level_json(Some(2))).unwrap();
+        level.migrate();
+        assert_eq!(level.layers[0].get_tile(0, 0), TileData::Color([40, 40, 40]));
+        assert_eq!(level.layers[0].tiles.len(), 2);
+    }
+}
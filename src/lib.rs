@@ -0,0 +1,25 @@
+//! Modèle de données et formats de fichiers de l'éditeur de niveaux
+//!
+//! Cette bibliothèque ne dépend pas de l'interface graphique : elle expose les
+//! types du format (`Level`, `Layer`, `TileData`, `Project`...), le chargement,
+//! la sauvegarde, la migration des anciens fichiers et l'export/import Tiled.
+//! L'éditeur et les parsers (Bevy) l'utilisent tous deux, pour que les formats
+//! ne puissent pas diverger.
+//!
+//! ```toml
+//! [dependencies]
+//! editor_level = { path = "../editor_level", default-features = false }
+//! ```
+
+pub mod level;
+pub mod project;
+pub mod reachability;
+pub mod terrain;
+pub mod tiled;
+pub mod validation;
+
+pub use level::{Anchor, Layer, LayerGroup, LayerKind, Level, LevelObject, LogicGrid, LogicValue, TileData, TileEncoding, TileFlags, LEVEL_FORMAT_VERSION};
+pub use project::{AnimationFrame, CollisionShape, Project, TileMetadata, TileProperty, TilesetMetadata, PROJECT_FORMAT_VERSION};
+pub use terrain::{TerrainKind, TerrainSet};
+pub use reachability::{Movement, Reachability, ReachabilityError, ReachabilitySettings};
+pub use validation::{Problem, Severity, ValidationRule, ValidationSettings};
@@ -1,13 +1,23 @@
 mod asset_manager;
+mod chunks;
+mod cli;
 mod editor;
-mod level;
-mod project;
+mod history;
 mod ui;
 
+// Modèle de données et formats : bibliothèque partagée avec les parsers
+use editor_level::{level, project, reachability, terrain, validation};
+
 use editor::EditorState;
 use eframe::egui;
 
-fn main() -> eframe::Result<()> {
+fn main() -> std::process::ExitCode {
+    // Avec des arguments : mode ligne de commande, sans ouvrir de fenêtre
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1280.0, 720.0])
@@ -15,11 +25,17 @@ fn main() -> eframe::Result<()> {
         ..Default::default()
     };
 
-    eframe::run_native(
+    match eframe::run_native(
         "Level Editor",
         options,
         Box::new(|_cc| Ok(Box::new(EditorApp::default()))),
-    )
+    ) {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::ExitCode::FAILURE
+        }
+    }
 }
 
 struct EditorApp {
@@ -39,6 +55,7 @@ impl eframe::App for EditorApp {
         ui::draw_top_panel(ctx, &mut self.state);
         ui::draw_side_panel(ctx, &mut self.state);
         ui::draw_bottom_panel(ctx, &mut self.state);
+        ui::draw_inspector_panel(ctx, &mut self.state);
         ui::draw_central_panel(ctx, &mut self.state);
     }
 }
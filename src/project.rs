@@ -1,53 +1,456 @@
+use crate::level::{Layer, LayerKind, Level, LevelObject, TileData};
+use crate::terrain::TerrainSet;
+use crate::validation::ValidationSettings;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+/// Valeur typée d'une propriété de tile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TileProperty {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color([u8; 3]),
+}
+
+impl TileProperty {
+    /// Nom du type, affiché dans l'éditeur
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TileProperty::Bool(_) => "bool",
+            TileProperty::Int(_) => "int",
+            TileProperty::Float(_) => "float",
+            TileProperty::String(_) => "string",
+            TileProperty::Color(_) => "color",
+        }
+    }
+
+    /// Valeur par défaut pour un nom de type (voir `type_name`)
+    pub fn default_for(type_name: &str) -> Option<Self> {
+        match type_name {
+            "bool" => Some(TileProperty::Bool(false)),
+            "int" => Some(TileProperty::Int(0)),
+            "float" => Some(TileProperty::Float(0.0)),
+            "string" => Some(TileProperty::String(String::new())),
+            "color" => Some(TileProperty::Color([255, 255, 255])),
+            _ => None,
+        }
+    }
+}
+
+/// Forme de collision d'un tile, en pixels relatifs au coin haut-gauche du tile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CollisionShape {
+    Rect { x: f32, y: f32, width: f32, height: f32 },
+    Polygon { points: Vec<[f32; 2]> },
+}
+
+/// Image d'une animation de tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnimationFrame {
+    pub tile_index: u32,
+    pub duration_ms: u32,
+}
+
+/// Métadonnées d'un tile : propriétés libres, formes de collision et animation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TileMetadata {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, TileProperty>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collision: Vec<CollisionShape>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub animation: Vec<AnimationFrame>,  // Images jouées en boucle à la place du tile
+}
+
+impl TileMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty() && self.collision.is_empty() && self.animation.is_empty()
+    }
+
+    /// Tile de l'animation à afficher après `elapsed_ms` millisecondes
+    /// (None si le tile n'est pas animé)
+    pub fn frame_at(&self, elapsed_ms: u64) -> Option<u32> {
+        let total: u64 = self.animation.iter().map(|frame| frame.duration_ms.max(1) as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut time = elapsed_ms % total;
+        for frame in &self.animation {
+            let duration = frame.duration_ms.max(1) as u64;
+            if time < duration {
+                return Some(frame.tile_index);
+            }
+            time -= duration;
+        }
+        None
+    }
+}
 
 /// Métadonnées d'un tileset dans le projet
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TilesetMetadata {
-    pub id: usize,
+    pub id: usize,  // Identifiant stable référencé par les tiles (pas une position dans la liste)
     pub name: String,
-    pub path: String,  // Chemin relatif ou absolu
+    pub path: String,  // Relatif au fichier .editorproj sur disque, résolu au chargement
     pub tile_width: u32,
     pub tile_height: u32,
     pub columns: u32,
     pub rows: u32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tiles: BTreeMap<u32, TileMetadata>,  // Par index de tile (seuls les tiles renseignés)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub terrains: Vec<TerrainSet>,  // Règles d'autotiling
 }
 
-/// Projet complet avec niveau et tilesets
+/// Version du format de fichier .editorproj
+pub const PROJECT_FORMAT_VERSION: &str = "2.0";
+
+/// Projet complet : niveaux et tilesets partagés
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "ProjectFile")]
 pub struct Project {
     pub version: String,
-    pub level: crate::level::Level,
+    pub levels: Vec<Level>,  // Toujours au moins un niveau ; le premier est ouvert par défaut
     pub tilesets: Vec<TilesetMetadata>,
+    #[serde(default, skip_serializing_if = "ValidationSettings::is_default")]
+    pub validation: ValidationSettings,  // Contrôles et règles du panneau des problèmes
+}
+
+/// Forme lue sur disque : les projets 1.x contiennent un seul `level`
+#[derive(Deserialize)]
+struct ProjectFile {
+    version: String,
+    #[serde(default)]
+    level: Option<Level>,
+    #[serde(default)]
+    levels: Vec<Level>,
+    tilesets: Vec<TilesetMetadata>,
+    #[serde(default)]
+    validation: ValidationSettings,
+}
+
+impl TryFrom<ProjectFile> for Project {
+    type Error = String;
+
+    fn try_from(file: ProjectFile) -> Result<Self, Self::Error> {
+        let levels: Vec<Level> = file.level.into_iter().chain(file.levels).collect();
+        if levels.is_empty() {
+            return Err("projet sans niveau".to_string());
+        }
+        Ok(Self {
+            version: file.version,
+            levels,
+            tilesets: file.tilesets,
+            validation: file.validation,
+        })
+    }
+}
+
+/// Supprime les `.` et `..` d'un chemin sans accéder au disque
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let can_pop = matches!(normalized.components().next_back(), Some(Component::Normal(_)));
+                if can_pop {
+                    normalized.pop();
+                } else if !normalized.has_root() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn absolute_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        normalize_path(path)
+    } else {
+        let current_dir = std::env::current_dir().unwrap_or_default();
+        normalize_path(&current_dir.join(path))
+    }
+}
+
+/// Résout un chemin de fichier enregistré relativement à `base_dir`
+/// (un chemin absolu, issu d'un ancien projet, est conservé)
+pub fn resolve_path(path: &str, base_dir: &Path) -> PathBuf {
+    normalize_path(&base_dir.join(path))
+}
+
+/// Exprime `path` relativement à `base_dir`, avec des `/` quel que soit le système.
+/// Le chemin reste absolu s'il n'a pas de racine commune (autre lecteur sous Windows).
+pub fn relative_path(path: &Path, base_dir: &Path) -> String {
+    let path = absolute_path(path);
+    let base_dir = absolute_path(base_dir);
+    let mut path_components = path.components().peekable();
+    let mut base_components = base_dir.components().peekable();
+
+    if path_components.peek() != base_components.peek() {
+        return path.to_string_lossy().to_string();
+    }
+    while path_components.peek().is_some() && path_components.peek() == base_components.peek() {
+        path_components.next();
+        base_components.next();
+    }
+
+    let parts: Vec<String> = base_components
+        .map(|_| "..".to_string())
+        .chain(path_components.map(|c| c.as_os_str().to_string_lossy().to_string()))
+        .collect();
+    parts.join("/")
 }
 
 impl Project {
-    pub fn new(level: crate::level::Level) -> Self {
+    pub fn new(level: Level) -> Self {
+        Self::with_levels(level, Vec::new())
+    }
+
+    /// Projet contenant plusieurs niveaux : `first`, ouvert par défaut, puis `rest`
+    pub fn with_levels(first: Level, rest: Vec<Level>) -> Self {
         Self {
-            version: "1.0".to_string(),
-            level,
+            version: PROJECT_FORMAT_VERSION.to_string(),
+            levels: std::iter::once(first).chain(rest).collect(),
             tilesets: Vec::new(),
+            validation: ValidationSettings::default(),
         }
     }
 
+    /// Niveau principal (le premier du projet)
+    pub fn level(&self) -> &Level {
+        &self.levels[0]
+    }
+
+    pub fn level_mut(&mut self) -> &mut Level {
+        &mut self.levels[0]
+    }
+
+    /// Trouve un niveau par son nom
+    pub fn level_by_name(&self, name: &str) -> Option<&Level> {
+        self.levels.iter().find(|level| level.name == name)
+    }
+
     pub fn add_tileset(&mut self, metadata: TilesetMetadata) {
         self.tilesets.push(metadata);
     }
 
-    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let json = serde_json::to_string_pretty(self)?;
+    /// Sauvegarde le projet ; les chemins des tilesets sont écrits relativement
+    /// au fichier pour que le projet reste valide une fois déplacé
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        let mut project = self.clone();
+        for tileset in &mut project.tilesets {
+            tileset.path = relative_path(Path::new(&tileset.path), base_dir);
+        }
+        let json = serde_json::to_string_pretty(&project)?;
         std::fs::write(path, json)?;
         Ok(())
     }
 
-    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    /// Charge un projet ; les chemins relatifs des tilesets sont résolus
+    /// par rapport au dossier du fichier
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
         let json = std::fs::read_to_string(path)?;
-        let project = serde_json::from_str(&json)?;
+        let mut project: Project = serde_json::from_str(&json)?;
+        for level in &mut project.levels {
+            level.migrate();
+        }
+        project.version = PROJECT_FORMAT_VERSION.to_string();
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        for tileset in &mut project.tilesets {
+            tileset.path = resolve_path(&tileset.path, base_dir).to_string_lossy().to_string();
+        }
         Ok(project)
     }
 
-    /// Sauvegarde en format ancien (seulement le niveau, pour compatibilité)
-    pub fn save_level_only(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        self.level.save_to_file(path)
+    /// Charge un projet (.editorproj), un niveau seul (.json) ou une carte Tiled (.tmx)
+    /// selon l'extension du fichier
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match extension.as_str() {
+            "editorproj" => Self::load_from_file(path),
+            "json" => Ok(Self::new(Level::load_from_file(path)?)),
+            "tmx" => Self::import_tmx(&path.to_string_lossy()),
+            other => Err(format!("{} : extension '.{}' non supportée", path.display(), other).into()),
+        }
+    }
+
+    /// Sauvegarde en format ancien (seulement le niveau principal, pour compatibilité)
+    pub fn save_level_only(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        self.level().save_to_file(path)
+    }
+
+    fn layer(&self, layer_name: &str) -> Option<&Layer> {
+        self.level().layers.iter().find(|l| l.name == layer_name)
+    }
+
+    /// Retourne tous les tiles d'un calque
+    pub fn get_layer_tiles(&self, layer_name: &str) -> Option<&HashMap<(i32, i32), TileData>> {
+        self.layer(layer_name).map(|l| &l.tiles)
+    }
+
+    /// Retourne tous les objets d'un calque d'objets
+    pub fn get_layer_objects(&self, layer_name: &str) -> Option<&[LevelObject]> {
+        self.layer(layer_name)
+            .filter(|l| l.kind == LayerKind::Objects)
+            .map(|l| l.objects.as_slice())
+    }
+
+    /// Trouve tous les objets d'un type donné (ex: "spawn")
+    pub fn find_objects_by_type(&self, object_type: &str) -> Vec<&LevelObject> {
+        self.level().find_objects_by_type(object_type)
+    }
+
+    /// Trouve toutes les positions des tiles d'une couleur spécifique
+    pub fn find_tiles_by_color(&self, color: [u8; 3]) -> Vec<(i32, i32)> {
+        let mut positions: Vec<(i32, i32)> = self
+            .level()
+            .layers
+            .iter()
+            .flat_map(|l| &l.tiles)
+            .filter(|(_, tile)| **tile == TileData::Color(color))
+            .map(|(&pos, _)| pos)
+            .collect();
+        positions.sort_by_key(|&(x, y)| (y, x));
+        positions
+    }
+
+    /// Métadonnées (propriétés, collisions) d'un tile texturé
+    pub fn get_tile_metadata(&self, tileset_id: usize, tile_index: u32) -> Option<&TileMetadata> {
+        self.tilesets
+            .iter()
+            .find(|t| t.id == tileset_id)?
+            .tiles
+            .get(&tile_index)
+    }
+
+    /// Lit une propriété du tile placé en (x, y) dans un calque
+    pub fn get_tile_property(&self, layer_name: &str, x: i32, y: i32, property: &str) -> Option<&TileProperty> {
+        match self.get_layer_tiles(layer_name)?.get(&(x, y))? {
+            TileData::Texture { tileset_id, tile_index, .. } => {
+                self.get_tile_metadata(*tileset_id, *tile_index)?.properties.get(property)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_single_level_project_loads() {
+        let json = r#"{
+            "version": "1.0",
+            "level": { "name": "Ancien", "width": 32, "height": 24, "tile_size": 16, "layers": [] },
+            "tilesets": []
+        }"#;
+        let project: Project = serde_json::from_str(json).unwrap();
+        assert_eq!(project.levels.len(), 1);
+        assert_eq!(project.level().name, "Ancien");
+        assert_eq!(project.level().width, 32);
+
+        let saved = serde_json::to_string(&project).unwrap();
+        assert!(saved.contains("\"levels\""));
+        assert!(!saved.contains("\"level\""));
+
+        // Fichier 1.0 livré avec le dépôt
+        let shipped = Project::load_from_file("test.editorproj").unwrap();
+        assert_eq!(shipped.levels.len(), 1);
+        assert_eq!(shipped.version, PROJECT_FORMAT_VERSION);
+    }
+
+    #[test]
+    fn project_without_level_is_rejected() {
+        let json = r#"{ "version": "2.0", "levels": [], "tilesets": [] }"#;
+        assert!(serde_json::from_str::<Project>(json).is_err());
+    }
+
+    #[test]
+    fn with_levels_keeps_order() {
+        let level = |name: &str| Level::new(name.to_string(), 8, 8, 16);
+        let project = Project::with_levels(level("A"), vec![level("B"), level("C")]);
+        let names: Vec<&str> = project.levels.iter().map(|level| level.name.as_str()).collect();
+        assert_eq!(names, ["A", "B", "C"]);
+    }
+
+    /// Dossier de travail vide, propre au test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("editor_level_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn relative_paths() {
+        let root = std::env::temp_dir().join("projet");
+        let levels = root.join("levels");
+        assert_eq!(relative_path(&root.join("tilesets").join("x.png"), &levels), "../tilesets/x.png");
+        assert_eq!(relative_path(&levels.join("x.png"), &levels), "x.png");
+        assert_eq!(relative_path(&levels.join("sous").join("x.png"), &levels), "sous/x.png");
+    }
+
+    #[test]
+    fn resolved_paths() {
+        let root = std::env::temp_dir().join("projet");
+        let levels = root.join("levels");
+        assert_eq!(resolve_path("../tilesets/x.png", &levels), root.join("tilesets").join("x.png"));
+        assert_eq!(resolve_path("x.png", &levels), levels.join("x.png"));
+        assert_eq!(resolve_path("./sous/../x.png", &levels), levels.join("x.png"));
+        // Chemin absolu d'un ancien projet : conservé
+        let absolute = root.join("ailleurs").join("x.png");
+        assert_eq!(resolve_path(&absolute.to_string_lossy(), &levels), absolute);
+    }
+
+    #[test]
+    fn tileset_paths_survive_save_and_load_elsewhere() {
+        let dir = temp_dir("project_paths");
+        let tileset = dir.join("tilesets").join("sol.png");
+        let mut project = Project::new(Level::new("A".to_string(), 4, 4, 16));
+        project.add_tileset(TilesetMetadata {
+            id: 0,
+            name: "sol".to_string(),
+            path: tileset.to_string_lossy().to_string(),
+            tile_width: 16,
+            tile_height: 16,
+            columns: 1,
+            rows: 1,
+            tiles: BTreeMap::new(),
+            terrains: Vec::new(),
+        });
+        std::fs::create_dir_all(dir.join("levels")).unwrap();
+        let file = dir.join("levels").join("monde.editorproj");
+        project.save_to_file(&file).unwrap();
+        assert!(std::fs::read_to_string(&file).unwrap().contains("\"../tilesets/sol.png\""));
+
+        // Projet déplacé ailleurs : le chemin suit le fichier
+        let moved = dir.join("copie").join("levels");
+        std::fs::create_dir_all(&moved).unwrap();
+        std::fs::copy(&file, moved.join("monde.editorproj")).unwrap();
+        let loaded = Project::load_from_file(moved.join("monde.editorproj")).unwrap();
+        assert_eq!(PathBuf::from(&loaded.tilesets[0].path), dir.join("copie").join("tilesets").join("sol.png"));
+
+        // Fichier désigné relativement au dossier courant : même résultat
+        let current_dir = std::env::current_dir().unwrap();
+        let from_cwd = PathBuf::from(relative_path(&file, &current_dir));
+        assert!(from_cwd.is_relative());
+        let loaded = Project::load_from_file(&from_cwd).unwrap();
+        assert_eq!(absolute_path(Path::new(&loaded.tilesets[0].path)), tileset);
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,348 @@
+//! Analyse de parcours : la sortie est-elle accessible depuis le départ ?
+//!
+//! Un calque choisi sert de murs (tous ses tiles, sauf ceux des départs,
+//! sorties et pièces ; dans un calque de collision, les cases `solid`). Les
+//! bords du niveau sont des murs. Deux modèles de déplacement :
+//!
+//! - `Walk` : case par case dans les quatre directions, sans gravité (vue de dessus) ;
+//! - `Platformer` : gravité. Posé sur un mur (ou une case `one_way` d'un calque
+//!   de collision), le personnage marche à gauche et à droite ou saute : il
+//!   monte d'au plus `jump_height` cases et se décale d'au plus `jump_length`
+//!   cases pendant le saut, descente comprise. Sans sol ni saut, il tombe tout droit.
+
+use crate::level::{LayerKind, Level, TileData, TileType};
+use crate::validation::markers;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Modèle de déplacement de l'analyse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Movement {
+    Walk,
+    Platformer { jump_height: u32, jump_length: u32 },
+}
+
+/// Réglages de l'analyse de parcours
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReachabilitySettings {
+    pub wall_layer: String,  // Nom du calque qui sert de murs
+    pub movement: Movement,
+}
+
+impl Default for ReachabilitySettings {
+    fn default() -> Self {
+        Self {
+            wall_layer: "Main".to_string(),
+            movement: Movement::Platformer { jump_height: 3, jump_length: 4 },
+        }
+    }
+}
+
+/// Analyse impossible
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReachabilityError {
+    MissingWallLayer(String),
+    NoSpawn,
+}
+
+impl std::fmt::Display for ReachabilityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReachabilityError::MissingWallLayer(name) => write!(f, "calque de murs '{}' absent", name),
+            ReachabilityError::NoSpawn => f.write_str("aucun départ"),
+        }
+    }
+}
+
+impl std::error::Error for ReachabilityError {}
+
+/// Résultat de l'analyse d'un niveau
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reachability {
+    pub spawn: (i32, i32),
+    pub reachable: HashSet<(i32, i32)>,  // Cases que le personnage peut occuper
+    pub path: Vec<(i32, i32)>,  // Du départ à la sortie la plus proche (vide si aucune n'est accessible)
+    pub exits: Vec<(i32, i32)>,
+    pub coins: Vec<(i32, i32)>,
+    pub unreachable_coins: Vec<(i32, i32)>,
+}
+
+impl Reachability {
+    pub fn exit_reached(&self) -> bool {
+        !self.path.is_empty()
+    }
+}
+
+/// Position du personnage pendant l'analyse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum State {
+    Stand(i32, i32),  // Posé au sol, ou en chute libre sans sol
+    Jump { x: i32, y: i32, up: u32, side: u32 },  // Saut en cours : montée et décalage restants
+}
+
+impl State {
+    fn cell(self) -> (i32, i32) {
+        match self {
+            State::Stand(x, y) | State::Jump { x, y, .. } => (x, y),
+        }
+    }
+}
+
+/// Murs et sols d'un niveau
+struct Terrain {
+    width: i32,
+    height: i32,
+    walls: HashSet<(i32, i32)>,
+    platforms: HashSet<(i32, i32)>,  // Sols traversables (`one_way`)
+}
+
+impl Terrain {
+    fn free(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height && !self.walls.contains(&(x, y))
+    }
+
+    fn grounded(&self, x: i32, y: i32) -> bool {
+        !self.free(x, y + 1) || self.platforms.contains(&(x, y + 1))
+    }
+
+    fn next(&self, state: State, movement: Movement) -> Vec<State> {
+        let mut next = Vec::new();
+        let (x, y) = state.cell();
+        let Movement::Platformer { jump_height, jump_length } = movement else {
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                if self.free(nx, ny) {
+                    next.push(State::Stand(nx, ny));
+                }
+            }
+            return next;
+        };
+
+        match state {
+            State::Stand(x, y) if self.grounded(x, y) => {
+                for nx in [x - 1, x + 1] {
+                    if self.free(nx, y) {
+                        next.push(State::Stand(nx, y));
+                    }
+                }
+                next.push(State::Jump { x, y, up: jump_height, side: jump_length });
+            }
+            State::Stand(x, y) => {
+                if self.free(x, y + 1) {
+                    next.push(State::Stand(x, y + 1));
+                }
+            }
+            State::Jump { x, y, up, side } => {
+                if up > 0 && self.free(x, y - 1) {
+                    next.push(State::Jump { x, y: y - 1, up: up - 1, side });
+                }
+                if side > 0 {
+                    for nx in [x - 1, x + 1] {
+                        if self.free(nx, y) {
+                            next.push(State::Jump { x: nx, y, up, side: side - 1 });
+                        }
+                    }
+                }
+                // Une fois la descente commencée, le saut ne remonte plus
+                if self.free(x, y + 1) && !self.grounded(x, y) {
+                    next.push(State::Jump { x, y: y + 1, up: 0, side });
+                }
+                next.push(State::Stand(x, y));
+            }
+        }
+        next
+    }
+}
+
+/// Analyse le parcours d'un niveau depuis son premier départ
+pub fn analyze(level: &Level, settings: &ReachabilitySettings) -> Result<Reachability, ReachabilityError> {
+    let layer = level
+        .layers
+        .iter()
+        .find(|layer| layer.name == settings.wall_layer)
+        .ok_or_else(|| ReachabilityError::MissingWallLayer(settings.wall_layer.clone()))?;
+    let spawn = markers(level, TileType::Spawn, "spawn")
+        .first()
+        .map(|&(_, cell)| cell)
+        .ok_or(ReachabilityError::NoSpawn)?;
+    let exits: Vec<(i32, i32)> = markers(level, TileType::Exit, "exit").into_iter().map(|(_, cell)| cell).collect();
+    let coins: Vec<(i32, i32)> = markers(level, TileType::Coin, "coin").into_iter().map(|(_, cell)| cell).collect();
+
+    let marker_tiles = [TileType::Spawn, TileType::Exit, TileType::Coin].map(|t| TileData::Color(t.color()));
+    let mut terrain = Terrain {
+        width: level.width as i32,
+        height: level.height as i32,
+        walls: HashSet::new(),
+        platforms: HashSet::new(),
+    };
+    for (&cell, tile) in &layer.tiles {
+        match tile {
+            TileData::Value(value) if layer.kind == LayerKind::Logic => {
+                match layer.logic_value(*value).map(|v| v.name.as_str()) {
+                    Some("solid") => terrain.walls.insert(cell),
+                    Some("one_way") => terrain.platforms.insert(cell),
+                    _ => false,
+                };
+            }
+            _ if layer.kind == LayerKind::Logic || marker_tiles.contains(tile) => {}
+            _ => {
+                terrain.walls.insert(cell);
+            }
+        }
+    }
+
+    // Parcours en largeur : le premier état posé sur une sortie donne le chemin le plus court
+    let exit_cells: HashSet<(i32, i32)> = exits.iter().copied().collect();
+    let start = State::Stand(spawn.0, spawn.1);
+    let mut parents: HashMap<State, State> = HashMap::new();
+    let mut visited: HashSet<State> = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    let mut exit_state = None;
+    while let Some(state) = queue.pop_front() {
+        if exit_state.is_none() && exit_cells.contains(&state.cell()) {
+            exit_state = Some(state);
+        }
+        for next in terrain.next(state, settings.movement) {
+            if visited.insert(next) {
+                parents.insert(next, state);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut current = exit_state;
+    while let Some(state) = current {
+        if path.last() != Some(&state.cell()) {
+            path.push(state.cell());
+        }
+        current = parents.get(&state).copied();
+    }
+    path.reverse();
+
+    let reachable: HashSet<(i32, i32)> = visited.into_iter().map(State::cell).collect();
+    let unreachable_coins = coins.iter().copied().filter(|coin| !reachable.contains(coin)).collect();
+    Ok(Reachability { spawn, reachable, path, exits, coins, unreachable_coins })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Layer;
+
+    /// Niveau décrit ligne par ligne dans le calque "Main" :
+    /// `#` mur, `S` départ, `E` sortie, `C` pièce, `.` vide
+    fn level(rows: &[&str]) -> Level {
+        let mut level = Level::new("Test".to_string(), rows[0].len() as u32, rows.len() as u32, 16);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let tile = match c {
+                    '#' => TileData::Color([90, 90, 90]),
+                    'S' => TileData::Color(TileType::Spawn.color()),
+                    'E' => TileData::Color(TileType::Exit.color()),
+                    'C' => TileData::Color(TileType::Coin.color()),
+                    _ => continue,
+                };
+                level.layers[1].set_tile(x as i32, y as i32, tile);
+            }
+        }
+        level
+    }
+
+    fn platformer(jump_height: u32, jump_length: u32) -> ReachabilitySettings {
+        ReachabilitySettings { movement: Movement::Platformer { jump_height, jump_length }, ..Default::default() }
+    }
+
+    #[test]
+    fn jump_height_limit() {
+        let level = level(&[
+            ".....",
+            "..#..",
+            "S.#.E",
+            "#####",
+        ]);
+        assert!(!analyze(&level, &platformer(1, 4)).unwrap().exit_reached());
+        let result = analyze(&level, &platformer(2, 4)).unwrap();
+        assert!(result.exit_reached());
+        assert_eq!(result.path.first(), Some(&(0, 2)));
+        assert_eq!(result.path.last(), Some(&(4, 2)));
+    }
+
+    #[test]
+    fn jump_length_limit() {
+        // Un puits de trois cases trop profond pour en ressortir
+        let level = level(&[
+            ".......",
+            "S.....E",
+            "##...##",
+            "##...##",
+            "##...##",
+            "##...##",
+        ]);
+        assert!(!analyze(&level, &platformer(2, 3)).unwrap().exit_reached());
+        assert!(analyze(&level, &platformer(2, 4)).unwrap().exit_reached());
+    }
+
+    #[test]
+    fn unreachable_coin() {
+        let level = level(&[
+            "..........C",
+            "...........",
+            "...........",
+            "S.........E",
+            "###########",
+        ]);
+        let result = analyze(&level, &platformer(1, 4)).unwrap();
+        assert!(result.exit_reached());
+        assert_eq!(result.coins, vec![(10, 0)]);
+        assert_eq!(result.unreachable_coins, vec![(10, 0)]);
+        assert!(analyze(&level, &platformer(3, 4)).unwrap().unreachable_coins.is_empty());
+    }
+
+    #[test]
+    fn walk_ignores_gravity() {
+        let level = level(&[
+            "S.#E",
+            "..#.",
+            "....",
+        ]);
+        let walk = ReachabilitySettings { movement: Movement::Walk, ..Default::default() };
+        assert_eq!(analyze(&level, &walk).unwrap().path.len(), 8);
+        assert!(!analyze(&level, &platformer(0, 0)).unwrap().exit_reached());
+    }
+
+    #[test]
+    fn one_way_platforms() {
+        // Le bas du niveau sert de sol ; plateforme traversable en (2, 2)
+        let mut level = level(&[
+            "..E..",
+            ".....",
+            ".....",
+            "S....",
+        ]);
+        let mut logic = Layer::new_logic("Collision".to_string());
+        let one_way = logic.logic_values.iter().find(|v| v.name == "one_way").unwrap().value;
+        logic.set_tile(2, 2, TileData::Value(one_way));
+        level.layers.push(logic);
+        let settings = ReachabilitySettings { wall_layer: "Collision".to_string(), ..platformer(2, 1) };
+
+        // Traversée par en dessous, la plateforme porte ensuite le personnage
+        let result = analyze(&level, &settings).unwrap();
+        assert!(result.reachable.contains(&(2, 2)));
+        assert!(result.exit_reached());
+        assert!(result.path.contains(&(2, 1)));
+
+        // Sans elle, le saut est trop court pour atteindre la sortie
+        level.layers[3].set_tile(2, 2, TileData::empty());
+        assert!(!analyze(&level, &settings).unwrap().exit_reached());
+    }
+
+    #[test]
+    fn errors() {
+        let settings = ReachabilitySettings { wall_layer: "Murs".to_string(), ..Default::default() };
+        assert_eq!(
+            analyze(&level(&["S.E"]), &settings),
+            Err(ReachabilityError::MissingWallLayer("Murs".to_string()))
+        );
+        assert_eq!(analyze(&level(&["..E"]), &ReachabilitySettings::default()), Err(ReachabilityError::NoSpawn));
+    }
+}
@@ -0,0 +1,236 @@
+//! Autotiling : jeux de terrains définis sur un tileset
+//!
+//! Un terrain associe un masque de voisinage à un `tile_index`. Une case fait
+//! partie du terrain si elle porte l'un de ses tiles ; son masque est calculé à
+//! partir des cases voisines et donne le tile à afficher. Les règles sont
+//! enregistrées dans le projet pour que les parsers puissent les réévaluer.
+
+use crate::level::{Layer, TileData};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Bits des voisins (sens horaire à partir du nord)
+pub const NORTH: u8 = 1;
+pub const NORTH_EAST: u8 = 2;
+pub const EAST: u8 = 4;
+pub const SOUTH_EAST: u8 = 8;
+pub const SOUTH: u8 = 16;
+pub const SOUTH_WEST: u8 = 32;
+pub const WEST: u8 = 64;
+pub const NORTH_WEST: u8 = 128;
+
+/// Type de règles d'un terrain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerrainKind {
+    /// 4 bits : N=1, E=2, S=4, O=8 (16 tiles)
+    Edge4,
+    /// 8 bits, un coin ne compte que si ses deux côtés sont pleins (47 tiles)
+    Blob47,
+    /// Coins Wang : NO=1, NE=2, SE=4, SO=8, un coin est plein si les
+    /// quatre cases qui le partagent sont du terrain (16 tiles)
+    Corner,
+}
+
+impl TerrainKind {
+    pub fn all() -> [TerrainKind; 3] {
+        [TerrainKind::Edge4, TerrainKind::Blob47, TerrainKind::Corner]
+    }
+
+    /// Nom affiché dans l'éditeur
+    pub fn name(&self) -> &'static str {
+        match self {
+            TerrainKind::Edge4 => "4 bits (16)",
+            TerrainKind::Blob47 => "Blob 8 bits (47)",
+            TerrainKind::Corner => "Coins Wang (16)",
+        }
+    }
+
+    /// Masques possibles, par ordre croissant
+    pub fn masks(&self) -> Vec<u8> {
+        match self {
+            TerrainKind::Edge4 | TerrainKind::Corner => (0..16).collect(),
+            TerrainKind::Blob47 => (0..=255u8).filter(|&m| blob_mask(m) == m).collect(),
+        }
+    }
+}
+
+/// Retire les coins dont un des deux côtés adjacents est vide
+fn blob_mask(mask: u8) -> u8 {
+    let mut result = mask & (NORTH | EAST | SOUTH | WEST);
+    for (corner, a, b) in [
+        (NORTH_EAST, NORTH, EAST),
+        (SOUTH_EAST, SOUTH, EAST),
+        (SOUTH_WEST, SOUTH, WEST),
+        (NORTH_WEST, NORTH, WEST),
+    ] {
+        if mask & corner != 0 && mask & a != 0 && mask & b != 0 {
+            result |= corner;
+        }
+    }
+    result
+}
+
+/// Jeu de terrain d'un tileset : masque de voisinage → tile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerrainSet {
+    pub name: String,
+    pub kind: TerrainKind,
+    #[serde(default)]
+    pub tiles: BTreeMap<u8, u32>,
+}
+
+impl TerrainSet {
+    pub fn new(name: String, kind: TerrainKind) -> Self {
+        Self { name, kind, tiles: BTreeMap::new() }
+    }
+
+    /// Indique si un tile fait partie du terrain
+    pub fn contains(&self, tileset_id: usize, tile: &TileData) -> bool {
+        match tile {
+            TileData::Texture { tileset_id: id, tile_index, .. } => {
+                *id == tileset_id && self.tiles.values().any(|t| t == tile_index)
+            }
+            _ => false,
+        }
+    }
+
+    /// Masque d'une case selon l'appartenance de ses 8 voisines
+    pub fn mask(&self, is_member: impl Fn(i32, i32) -> bool, x: i32, y: i32) -> u8 {
+        let neighbour = |dx: i32, dy: i32, bit: u8| if is_member(x + dx, y + dy) { bit } else { 0 };
+        let full = neighbour(0, -1, NORTH)
+            | neighbour(1, -1, NORTH_EAST)
+            | neighbour(1, 0, EAST)
+            | neighbour(1, 1, SOUTH_EAST)
+            | neighbour(0, 1, SOUTH)
+            | neighbour(-1, 1, SOUTH_WEST)
+            | neighbour(-1, 0, WEST)
+            | neighbour(-1, -1, NORTH_WEST);
+
+        let has = |bits: u8| full & bits == bits;
+        match self.kind {
+            TerrainKind::Edge4 => {
+                (has(NORTH) as u8) | (has(EAST) as u8) << 1 | (has(SOUTH) as u8) << 2 | (has(WEST) as u8) << 3
+            }
+            TerrainKind::Blob47 => blob_mask(full),
+            TerrainKind::Corner => {
+                (has(NORTH | NORTH_WEST | WEST) as u8)
+                    | (has(NORTH | NORTH_EAST | EAST) as u8) << 1
+                    | (has(SOUTH | SOUTH_EAST | EAST) as u8) << 2
+                    | (has(SOUTH | SOUTH_WEST | WEST) as u8) << 3
+            }
+        }
+    }
+
+    /// Tile pour un masque ; à défaut, celui du masque défini le plus proche
+    pub fn tile_for(&self, mask: u8) -> Option<u32> {
+        self.tiles.get(&mask).copied().or_else(|| {
+            self.tiles
+                .iter()
+                .min_by_key(|(&m, _)| (m ^ mask).count_ones())
+                .map(|(_, &tile_index)| tile_index)
+        })
+    }
+
+    /// Cases à modifier quand (x, y) est peint (`paint`) ou effacé avec le
+    /// pinceau de terrain : la case elle-même et ses voisines du terrain
+    pub fn brush_changes(
+        &self,
+        layer: &Layer,
+        tileset_id: usize,
+        x: i32,
+        y: i32,
+        paint: bool,
+    ) -> Vec<((i32, i32), TileData)> {
+        let is_member = |cx: i32, cy: i32| {
+            if (cx, cy) == (x, y) {
+                paint
+            } else {
+                self.contains(tileset_id, &layer.get_tile(cx, cy))
+            }
+        };
+
+        let mut changes = Vec::new();
+        for cy in y - 1..=y + 1 {
+            for cx in x - 1..=x + 1 {
+                if (cx, cy) == (x, y) && !paint {
+                    changes.push(((cx, cy), TileData::empty()));
+                } else if is_member(cx, cy) {
+                    if let Some(tile_index) = self.tile_for(self.mask(is_member, cx, cy)) {
+                        changes.push(((cx, cy), TileData::texture(tileset_id, tile_index)));
+                    }
+                }
+            }
+        }
+        changes
+    }
+
+    /// Réévalue toutes les cases du terrain d'un calque (tiles corrigés uniquement)
+    pub fn resolve_layer(&self, layer: &Layer, tileset_id: usize) -> Vec<((i32, i32), TileData)> {
+        let is_member = |cx: i32, cy: i32| self.contains(tileset_id, &layer.get_tile(cx, cy));
+        layer
+            .tiles
+            .iter()
+            .filter(|(_, tile)| self.contains(tileset_id, tile))
+            .filter_map(|(&(x, y), tile)| {
+                let tile_index = self.tile_for(self.mask(is_member, x, y))?;
+                let resolved = TileData::texture(tileset_id, tile_index);
+                (resolved != *tile).then_some(((x, y), resolved))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Terrain dont le tile de chaque masque porte le numéro du masque
+    fn terrain(kind: TerrainKind) -> TerrainSet {
+        let mut set = TerrainSet::new("Herbe".to_string(), kind);
+        set.tiles = kind.masks().into_iter().map(|mask| (mask, mask as u32)).collect();
+        set
+    }
+
+    #[test]
+    fn blob_has_47_masks() {
+        assert_eq!(TerrainKind::Blob47.masks().len(), 47);
+        assert_eq!(TerrainKind::Edge4.masks().len(), 16);
+    }
+
+    #[test]
+    fn masks_from_neighbours() {
+        // Seuls le nord, le nord-est et l'est sont du terrain
+        let cells = [(0, -1), (1, -1), (1, 0)];
+        let is_member = |x: i32, y: i32| cells.contains(&(x, y));
+        assert_eq!(terrain(TerrainKind::Edge4).mask(is_member, 0, 0), 1 | 2);
+        assert_eq!(terrain(TerrainKind::Blob47).mask(is_member, 0, 0), NORTH | NORTH_EAST | EAST);
+        assert_eq!(terrain(TerrainKind::Corner).mask(is_member, 0, 0), 2);
+        // Un coin isolé ne compte pas dans le blob
+        let corner_only = |x: i32, y: i32| (x, y) == (1, -1);
+        assert_eq!(terrain(TerrainKind::Blob47).mask(corner_only, 0, 0), 0);
+    }
+
+    #[test]
+    fn missing_mask_uses_closest() {
+        let mut set = TerrainSet::new("Eau".to_string(), TerrainKind::Edge4);
+        set.tiles = BTreeMap::from([(0, 10), (15, 20)]);
+        assert_eq!(set.tile_for(15), Some(20));
+        assert_eq!(set.tile_for(7), Some(20));
+        assert_eq!(set.tile_for(1), Some(10));
+    }
+
+    #[test]
+    fn brush_updates_neighbours() {
+        let set = terrain(TerrainKind::Edge4);
+        let mut layer = Layer::new("Sol".to_string());
+        layer.set_tile(0, 0, TileData::texture(4, 0));
+
+        let changes: BTreeMap<(i32, i32), TileData> = set.brush_changes(&layer, 4, 1, 0, true).into_iter().collect();
+        assert_eq!(changes[&(0, 0)], TileData::texture(4, 2));  // Voisin à l'est
+        assert_eq!(changes[&(1, 0)], TileData::texture(4, 8));  // Voisin à l'ouest
+        assert_eq!(changes.len(), 2);
+
+        let erase: BTreeMap<(i32, i32), TileData> = set.brush_changes(&layer, 4, 0, 0, false).into_iter().collect();
+        assert_eq!(erase[&(0, 0)], TileData::empty());
+    }
+}
@@ -0,0 +1,978 @@
+//! Export et import au format Tiled (.tmx / .tsx)
+//!
+//! Chaque calque devient un calque de tiles encodé en CSV. Les tilesets sont
+//! écrits dans des fichiers .tsx externes ; les tiles de couleur sont regroupés
+//! dans un tileset généré (image PNG + propriété `color` sur chaque tile).
+//! Les calques d'objets deviennent des `<objectgroup>`. Les propriétés,
+//! collisions et animations des tiles sont écrites dans les `<tile>` de leur
+//! tileset, les terrains d'autotiling dans ses `<wangsets>`.
+
+use crate::level::{Layer, LayerKind, Level, LevelObject, TileData, TileFlags};
+use crate::project::{relative_path, resolve_path, AnimationFrame, CollisionShape, Project, TileMetadata, TileProperty, TilesetMetadata};
+use crate::terrain::{TerrainKind, TerrainSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Bits de retournement utilisés par Tiled dans les GID
+const TILED_FLIP_MASK: u32 = 0xE000_0000;
+const TILED_FLIP_H: u32 = 0x8000_0000;
+const TILED_FLIP_V: u32 = 0x4000_0000;
+const TILED_FLIP_D: u32 = 0x2000_0000;
+
+/// Bits Tiled d'un tile transformé. Tiled échange d'abord les axes (diagonale)
+/// puis retourne : un quart de tour horaire vaut diagonale + horizontal.
+fn flags_to_gid_bits(flags: TileFlags) -> u32 {
+    let bit = |set: bool, bit: u32| if set { bit } else { 0 };
+    if flags.rotate {
+        TILED_FLIP_D | bit(!flags.flip_x, TILED_FLIP_H) | bit(flags.flip_y, TILED_FLIP_V)
+    } else {
+        bit(flags.flip_x, TILED_FLIP_H) | bit(flags.flip_y, TILED_FLIP_V)
+    }
+}
+
+fn flags_from_gid(gid: u32) -> TileFlags {
+    let (h, v) = (gid & TILED_FLIP_H != 0, gid & TILED_FLIP_V != 0);
+    if gid & TILED_FLIP_D != 0 {
+        TileFlags { flip_x: !h, flip_y: v, rotate: true }
+    } else {
+        TileFlags { flip_x: h, flip_y: v, rotate: false }
+    }
+}
+
+/// Propriété qui identifie un tile du tileset de couleurs généré
+const COLOR_PROPERTY: &str = "color";
+
+/// Propriété Tiled qui porte les valeurs d'un calque de collision (JSON)
+const LOGIC_VALUES_PROPERTY: &str = "editor_logic_values";
+
+/// Nombre de colonnes de l'image du tileset de couleurs
+const COLOR_TILESET_COLUMNS: u32 = 8;
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn rgb_to_hex(rgb: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", rgb[0], rgb[1], rgb[2])
+}
+
+fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim().trim_start_matches('#');
+    // Tiled peut écrire les couleurs en #AARRGGBB
+    let hex = if hex.len() == 8 { &hex[2..] } else { hex };
+    if hex.len() != 6 {
+        return None;
+    }
+    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+    Some([r, g, b])
+}
+
+/// Attributs communs d'un calque Tiled : visibilité, verrou, opacité, teinte,
+/// décalage et parallaxe (omis quand ils ont leur valeur par défaut). Les groupes
+/// ne sont pas exportés : `visible` tient compte de leur visibilité.
+fn layer_attributes(layer: &Layer, visible: bool) -> String {
+    let mut attributes = String::new();
+    if !visible {
+        attributes.push_str(" visible=\"0\"");
+    }
+    if layer.locked {
+        attributes.push_str(" locked=\"1\"");
+    }
+    if layer.opacity != 1.0 {
+        attributes.push_str(&format!(" opacity=\"{}\"", layer.opacity));
+    }
+    if layer.tint != [255, 255, 255] {
+        attributes.push_str(&format!(" tintcolor=\"{}\"", rgb_to_hex(layer.tint).to_lowercase()));
+    }
+    if layer.offset != (0.0, 0.0) {
+        attributes.push_str(&format!(" offsetx=\"{}\" offsety=\"{}\"", layer.offset.0, layer.offset.1));
+    }
+    if layer.parallax != (1.0, 1.0) {
+        attributes.push_str(&format!(" parallaxx=\"{}\" parallaxy=\"{}\"", layer.parallax.0, layer.parallax.1));
+    }
+    attributes
+}
+
+/// Lit les attributs communs d'un `<layer>` ou d'un `<objectgroup>`
+fn read_layer_attributes(node: roxmltree::Node, layer: &mut Layer) {
+    let number = |name: &str, default: f32| node.attribute(name).and_then(|v| v.parse().ok()).unwrap_or(default);
+    layer.visible = node.attribute("visible") != Some("0");
+    layer.locked = node.attribute("locked") == Some("1");
+    layer.opacity = number("opacity", 1.0);
+    layer.tint = node.attribute("tintcolor").and_then(parse_hex_color).unwrap_or([255, 255, 255]);
+    layer.offset = (number("offsetx", 0.0), number("offsety", 0.0));
+    layer.parallax = (number("parallaxx", 1.0), number("parallaxy", 1.0));
+}
+
+/// Tileset tel qu'il apparaît dans la carte : premier GID et fichier .tsx
+struct TmxTileset {
+    firstgid: u32,
+    source: String,
+}
+
+impl Project {
+    /// Exporte le niveau d'un projet à un seul niveau en carte Tiled (.tmx) avec
+    /// ses tilesets (.tsx) à côté. Une carte Tiled ne contient qu'un niveau : avec
+    /// plusieurs niveaux, utiliser `export_level_tmx`.
+    pub fn export_tmx(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if self.levels.len() > 1 {
+            return Err(format!(
+                "le projet contient {} niveaux et une carte .tmx n'en contient qu'un : choisir le niveau à exporter",
+                self.levels.len()
+            )
+            .into());
+        }
+        self.export_level_tmx(0, path)
+    }
+
+    /// Exporte un niveau du projet (par index) en carte Tiled (.tmx) avec ses tilesets (.tsx) à côté
+    pub fn export_level_tmx(&self, index: usize, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let level = self
+            .levels
+            .get(index)
+            .ok_or_else(|| format!("niveau {} absent du projet ({} niveaux)", index, self.levels.len()))?;
+        self.check_tile_references(level)?;
+        let tmx_path = Path::new(path);
+        let dir = tmx_path.parent().unwrap_or(Path::new("."));
+        let stem = tmx_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("niveau")
+            .to_string();
+
+        // Tilesets d'images : un .tsx par tileset du projet
+        let mut map_tilesets = Vec::new();
+        let mut used_names = HashSet::new();
+        let mut next_gid = 1;
+        for tileset in &self.tilesets {
+            let base = Path::new(&tileset.name)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("tileset")
+                .to_string();
+            let mut file_name = format!("{}.tsx", base);
+            if !used_names.insert(file_name.clone()) {
+                file_name = format!("{}_{}.tsx", base, tileset.id);
+                used_names.insert(file_name.clone());
+            }
+            let image_source = relative_path(Path::new(&tileset.path), dir);
+            std::fs::write(dir.join(&file_name), tileset_to_tsx(tileset, &image_source))?;
+
+            map_tilesets.push(TmxTileset { firstgid: next_gid, source: file_name });
+            next_gid += tileset.columns * tileset.rows;
+        }
+
+        // Tileset de couleurs généré : une case unie par couleur utilisée (les
+        // valeurs des calques de collision prennent la couleur de leur définition)
+        let color_of = |layer: &Layer, tile: &TileData| match tile {
+            TileData::Color(rgb) => Some(*rgb),
+            TileData::Value(value) => layer.logic_value(*value).map(|v| v.color),
+            _ => None,
+        };
+        let colors: Vec<[u8; 3]> = level
+            .layers
+            .iter()
+            .flat_map(|layer| layer.tiles.values().filter_map(move |tile| color_of(layer, tile)))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let color_firstgid = next_gid;
+        if !colors.is_empty() {
+            let image_name = format!("{}_couleurs.png", stem);
+            let tsx_name = format!("{}_couleurs.tsx", stem);
+            write_color_image(&dir.join(&image_name), &colors, level.tile_size)?;
+            std::fs::write(
+                dir.join(&tsx_name),
+                color_tileset_to_tsx(&colors, level.tile_size, &image_name),
+            )?;
+            map_tilesets.push(TmxTileset { firstgid: color_firstgid, source: tsx_name });
+        }
+
+        let gid_of = |layer: &Layer, tile: &TileData| -> u32 {
+            match tile {
+                TileData::Empty => 0,
+                TileData::Color(_) | TileData::Value(_) => color_of(layer, tile)
+                    .and_then(|rgb| colors.iter().position(|c| *c == rgb))
+                    .map_or(0, |idx| color_firstgid + idx as u32),
+                TileData::Texture { tileset_id, tile_index, flags } => self
+                    .tilesets
+                    .iter()
+                    .position(|t| t.id == *tileset_id)
+                    .map_or(0, |pos| (map_tilesets[pos].firstgid + tile_index) | flags_to_gid_bits(*flags)),
+            }
+        };
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<map version=\"1.10\" orientation=\"orthogonal\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"{}\" nextobjectid=\"{}\">\n",
+            level.width,
+            level.height,
+            level.tile_size,
+            level.tile_size,
+            level.layers.len() + 1,
+            level.next_object_id()
+        ));
+        for tileset in &map_tilesets {
+            xml.push_str(&format!(
+                " <tileset firstgid=\"{}\" source=\"{}\"/>\n",
+                tileset.firstgid,
+                xml_escape(&tileset.source)
+            ));
+        }
+        for (idx, layer) in level.layers.iter().enumerate() {
+            if layer.kind == LayerKind::Objects {
+                xml.push_str(&object_group_to_tmx(idx + 1, layer, level.is_layer_visible(idx)));
+                continue;
+            }
+            xml.push_str(&format!(
+                " <layer id=\"{}\" name=\"{}\" width=\"{}\" height=\"{}\"{}>\n",
+                idx + 1,
+                xml_escape(&layer.name),
+                level.width,
+                level.height,
+                layer_attributes(layer, level.is_layer_visible(idx))
+            ));
+            // Calque de collision : ses valeurs sont gardées en propriété pour le réimport
+            if layer.kind == LayerKind::Logic {
+                xml.push_str(&format!(
+                    "  <properties>\n   <property name=\"{}\" value=\"{}\"/>\n  </properties>\n",
+                    LOGIC_VALUES_PROPERTY,
+                    xml_escape(&serde_json::to_string(&layer.logic_values)?)
+                ));
+            }
+            xml.push_str("  <data encoding=\"csv\">\n");
+            let rows: Vec<String> = (0..level.height as i32)
+                .map(|y| {
+                    (0..level.width as i32)
+                        .map(|x| gid_of(layer, &layer.get_tile(x, y)).to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .collect();
+            xml.push_str(&rows.join(",\n"));
+            xml.push_str("\n  </data>\n </layer>\n");
+        }
+        xml.push_str("</map>\n");
+
+        std::fs::write(tmx_path, xml)?;
+        Ok(())
+    }
+
+    /// Refuse l'export d'un niveau dont des tiles texturés n'ont pas de GID :
+    /// tileset absent du projet ou `tile_index` hors du tileset (qui déborderait
+    /// sur le tileset suivant)
+    fn check_tile_references(&self, level: &Level) -> Result<(), String> {
+        let tile_counts = self.tile_counts();
+        let mut problems = Vec::new();
+        for layer in &level.layers {
+            let mut missing: BTreeMap<usize, usize> = BTreeMap::new();
+            let mut out_of_range: BTreeMap<usize, usize> = BTreeMap::new();
+            for tile in layer.tiles.values() {
+                if let TileData::Texture { tileset_id, tile_index, .. } = tile {
+                    match tile_counts.get(tileset_id) {
+                        None => *missing.entry(*tileset_id).or_default() += 1,
+                        Some(&count) if *tile_index >= count => *out_of_range.entry(*tileset_id).or_default() += 1,
+                        Some(_) => {}
+                    }
+                }
+            }
+            for (tileset_id, count) in missing {
+                problems.push(format!("calque '{}' : {} tiles du tileset {} absent du projet", layer.name, count, tileset_id));
+            }
+            for (tileset_id, count) in out_of_range {
+                problems.push(format!("calque '{}' : {} tiles hors du tileset {}", layer.name, count, tileset_id));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("export .tmx impossible, tiles sans équivalent Tiled : {}", problems.join(" ; ")))
+        }
+    }
+
+    /// Importe une carte Tiled orthogonale (.tmx) et ses tilesets
+    pub fn import_tmx(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let tmx_path = Path::new(path);
+        let dir = tmx_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let content = std::fs::read_to_string(tmx_path)?;
+        let doc = roxmltree::Document::parse(&content)?;
+        let map = doc.root_element();
+
+        if map.attribute("orientation") != Some("orthogonal") {
+            return Err("seules les cartes orthogonales sont supportées".into());
+        }
+        let attr_u32 = |node: roxmltree::Node, name: &str| -> Result<u32, String> {
+            node.attribute(name)
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| format!("attribut '{}' manquant ou invalide", name))
+        };
+        let width = attr_u32(map, "width")?;
+        let height = attr_u32(map, "height")?;
+        let tile_size = attr_u32(map, "tilewidth")?;
+        if width == 0 || height == 0 || tile_size == 0 {
+            return Err(format!("dimensions de carte invalides : {}x{} (tile {}px)", width, height, tile_size).into());
+        }
+
+        // Tilesets : les tilesets de couleurs redeviennent des TileData::Color
+        enum Source {
+            Image(usize),
+            Colors(Vec<Option<[u8; 3]>>),
+        }
+        let mut tilesets = Vec::new();
+        let mut ranges: Vec<(u32, Source)> = Vec::new();
+        for node in map.children().filter(|n| n.has_tag_name("tileset")) {
+            let firstgid = attr_u32(node, "firstgid")?;
+            let (tsx_content, base_dir) = match node.attribute("source") {
+                Some(source) => {
+                    let tsx_path = dir.join(source);
+                    let base_dir = tsx_path.parent().map(Path::to_path_buf).unwrap_or_else(|| dir.clone());
+                    (Some(std::fs::read_to_string(&tsx_path)?), base_dir)
+                }
+                None => (None, dir.clone()),
+            };
+            let tsx_doc = match &tsx_content {
+                Some(text) => Some(roxmltree::Document::parse(text)?),
+                None => None,
+            };
+            let tileset_node = tsx_doc.as_ref().map_or(node, |d| d.root_element());
+
+            // Tileset de couleurs généré : chaque tile porte une couleur
+            let colors = tileset_colors(tileset_node);
+            let tile_count = attr_u32(tileset_node, "tilecount")?;
+            if !colors.is_empty() && colors.len() == tile_count as usize && colors.iter().all(Option::is_some) {
+                ranges.push((firstgid, Source::Colors(colors)));
+                continue;
+            }
+
+            let image = tileset_node
+                .children()
+                .find(|n| n.has_tag_name("image"))
+                .ok_or("tileset sans image")?;
+            let image_path: PathBuf = resolve_path(image.attribute("source").unwrap_or_default(), &base_dir);
+            let tile_width = attr_u32(tileset_node, "tilewidth")?;
+            let tile_height = attr_u32(tileset_node, "tileheight")?;
+            let columns = attr_u32(tileset_node, "columns")?.max(1);
+
+            let id = tilesets.len();
+            tilesets.push(TilesetMetadata {
+                id,
+                name: tileset_node
+                    .attribute("name")
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("tileset_{}", id)),
+                path: image_path.to_string_lossy().to_string(),
+                tile_width,
+                tile_height,
+                columns,
+                rows: tile_count.div_ceil(columns),
+                tiles: tileset_tile_metadata(tileset_node),
+                terrains: tileset_terrains(tileset_node),
+            });
+            ranges.push((firstgid, Source::Image(id)));
+        }
+        ranges.sort_by_key(|(firstgid, _)| *firstgid);
+
+        let tile_of = |raw_gid: u32| -> TileData {
+            let gid = raw_gid & !TILED_FLIP_MASK;
+            if gid == 0 {
+                return TileData::Empty;
+            }
+            match ranges.iter().rev().find(|(firstgid, _)| *firstgid <= gid) {
+                Some((firstgid, Source::Image(id))) => TileData::Texture {
+                    tileset_id: *id,
+                    tile_index: gid - firstgid,
+                    flags: flags_from_gid(raw_gid),
+                },
+                Some((firstgid, Source::Colors(colors))) => colors
+                    .get((gid - firstgid) as usize)
+                    .copied()
+                    .flatten()
+                    .map_or(TileData::Empty, TileData::Color),
+                None => TileData::Empty,
+            }
+        };
+
+        let name = tmx_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Niveau importé")
+            .to_string();
+        let mut level = Level::new(name, width, height, tile_size);
+        level.layers.clear();
+
+        for node in map.children().filter(|n| n.has_tag_name("layer") || n.has_tag_name("objectgroup")) {
+            if node.has_tag_name("objectgroup") {
+                level.layers.push(object_group_from_tmx(node));
+                continue;
+            }
+            let mut layer = Layer::new(node.attribute("name").unwrap_or("Layer").to_string());
+            read_layer_attributes(node, &mut layer);
+            let layer_name = node.attribute("name").unwrap_or("Layer");
+            let layer_width = if node.has_attribute("width") { attr_u32(node, "width")? } else { width };
+            let layer_height = if node.has_attribute("height") { attr_u32(node, "height")? } else { height };
+            if layer_width == 0 || layer_height == 0 {
+                return Err(format!("calque '{}' : dimensions invalides {}x{}", layer_name, layer_width, layer_height).into());
+            }
+
+            let data = node
+                .children()
+                .find(|n| n.has_tag_name("data"))
+                .ok_or("calque sans données")?;
+            let gids: Vec<u32> = match data.attribute("encoding") {
+                Some("csv") => data
+                    .text()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.parse::<u32>())
+                    .collect::<Result<_, _>>()?,
+                None => data
+                    .children()
+                    .filter(|n| n.has_tag_name("tile"))
+                    .map(|n| n.attribute("gid").and_then(|v| v.parse().ok()).unwrap_or(0))
+                    .collect(),
+                Some(other) => {
+                    return Err(format!("encodage de calque '{}' non supporté (utilisez CSV)", other).into())
+                }
+            };
+
+            let expected = layer_width as usize * layer_height as usize;
+            if gids.len() != expected {
+                return Err(format!(
+                    "calque '{}' : {} tiles dans les données au lieu de {} ({}x{})",
+                    layer_name,
+                    gids.len(),
+                    expected,
+                    layer_width,
+                    layer_height
+                )
+                .into());
+            }
+            for (i, gid) in gids.into_iter().enumerate() {
+                let x = (i as u32 % layer_width) as i32;
+                let y = (i as u32 / layer_width) as i32;
+                layer.set_tile(x, y, tile_of(gid));
+            }
+
+            // Calque de collision exporté par l'éditeur : les couleurs redeviennent des valeurs
+            let logic_values = node
+                .descendants()
+                .filter(|n| n.has_tag_name("property"))
+                .find(|n| n.attribute("name") == Some(LOGIC_VALUES_PROPERTY))
+                .and_then(|n| n.attribute("value"));
+            if let Some(json) = logic_values {
+                layer.kind = LayerKind::Logic;
+                layer.logic_values = serde_json::from_str(json)?;
+                for tile in layer.tiles.values_mut() {
+                    let value = match tile {
+                        TileData::Color(rgb) => layer.logic_values.iter().find(|v| v.color == *rgb).map(|v| v.value),
+                        _ => None,
+                    };
+                    *tile = value.map_or(TileData::Empty, TileData::Value);
+                }
+                layer.tiles.retain(|_, tile| !tile.is_empty());
+            }
+            level.layers.push(layer);
+        }
+
+        if level.layers.is_empty() {
+            level.add_layer("Main".to_string());
+        }
+
+        let mut project = Project::new(level);
+        project.tilesets = tilesets;
+        Ok(project)
+    }
+}
+
+/// Génère un `<objectgroup>` pour un calque d'objets
+fn object_group_to_tmx(id: usize, layer: &Layer, visible: bool) -> String {
+    let mut xml = format!(
+        " <objectgroup id=\"{}\" name=\"{}\"{}>\n",
+        id,
+        xml_escape(&layer.name),
+        layer_attributes(layer, visible)
+    );
+    for object in &layer.objects {
+        xml.push_str(&format!(
+            "  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}",
+            object.id,
+            xml_escape(&object.name),
+            xml_escape(&object.object_type),
+            object.x,
+            object.y,
+            object.width,
+            object.height,
+            if object.rotation != 0.0 { format!(" rotation=\"{}\"", object.rotation) } else { String::new() }
+        ));
+        if object.properties.is_empty() {
+            xml.push_str("/>\n");
+            continue;
+        }
+        xml.push_str(">\n   <properties>\n");
+        for (key, value) in &object.properties {
+            xml.push_str(&format!(
+                "    <property name=\"{}\" value=\"{}\"/>\n",
+                xml_escape(key),
+                xml_escape(value)
+            ));
+        }
+        xml.push_str("   </properties>\n  </object>\n");
+    }
+    xml.push_str(" </objectgroup>\n");
+    xml
+}
+
+/// Lit un `<objectgroup>` Tiled en calque d'objets
+fn object_group_from_tmx(node: roxmltree::Node) -> Layer {
+    let mut layer = Layer::new_objects(node.attribute("name").unwrap_or("Objets").to_string());
+    read_layer_attributes(node, &mut layer);
+    let number = |object: roxmltree::Node, name: &str| {
+        object.attribute(name).and_then(|v| v.parse::<f32>().ok()).unwrap_or(0.0)
+    };
+
+    for object in node.children().filter(|n| n.has_tag_name("object")) {
+        let id = object.attribute("id").and_then(|v| v.parse().ok()).unwrap_or(0);
+        // Tiled ≥ 1.9 utilise `class`, les versions précédentes `type`
+        let object_type = object.attribute("type").or(object.attribute("class")).unwrap_or_default();
+        let mut level_object = LevelObject::new(
+            id,
+            object_type,
+            number(object, "x"),
+            number(object, "y"),
+            number(object, "width"),
+            number(object, "height"),
+        );
+        level_object.name = object.attribute("name").unwrap_or_default().to_string();
+        level_object.rotation = number(object, "rotation");
+        for property in object.descendants().filter(|n| n.has_tag_name("property")) {
+            if let Some(key) = property.attribute("name") {
+                let value = property.attribute("value").or(property.text()).unwrap_or_default();
+                level_object.properties.insert(key.to_string(), value.to_string());
+            }
+        }
+        layer.objects.push(level_object);
+    }
+    layer
+}
+
+/// Génère le contenu .tsx d'un tileset d'images
+fn tileset_to_tsx(tileset: &TilesetMetadata, image_source: &str) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <tileset version=\"1.10\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" tilecount=\"{}\" columns=\"{}\">\n \
+         <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+        xml_escape(&tileset.name),
+        tileset.tile_width,
+        tileset.tile_height,
+        tileset.columns * tileset.rows,
+        tileset.columns,
+        xml_escape(image_source),
+        tileset.columns * tileset.tile_width,
+        tileset.rows * tileset.tile_height
+    );
+    for (tile_index, metadata) in &tileset.tiles {
+        xml.push_str(&tile_metadata_to_tsx(*tile_index, metadata));
+    }
+    if !tileset.terrains.is_empty() {
+        xml.push_str(" <wangsets>\n");
+        for terrain in &tileset.terrains {
+            xml.push_str(&terrain_to_tsx(terrain));
+        }
+        xml.push_str(" </wangsets>\n");
+    }
+    xml.push_str("</tileset>\n");
+    xml
+}
+
+/// Positions du `wangid` Tiled (haut, haut-droite, droite... sens horaire)
+/// occupées par chaque bit du masque d'un terrain
+fn wang_positions(kind: TerrainKind) -> &'static [usize] {
+    match kind {
+        TerrainKind::Edge4 => &[0, 2, 4, 6],
+        TerrainKind::Blob47 => &[0, 1, 2, 3, 4, 5, 6, 7],
+        TerrainKind::Corner => &[7, 1, 3, 5],
+    }
+}
+
+/// Génère le `<wangset>` d'un terrain (couleur 1 = terrain, 0 = vide)
+fn terrain_to_tsx(terrain: &TerrainSet) -> String {
+    let wang_type = match terrain.kind {
+        TerrainKind::Edge4 => "edge",
+        TerrainKind::Blob47 => "mixed",
+        TerrainKind::Corner => "corner",
+    };
+    let mut xml = format!(
+        "  <wangset name=\"{}\" type=\"{}\" tile=\"-1\">\n   <wangcolor name=\"{}\" color=\"#ff0000\" tile=\"-1\" probability=\"1\"/>\n",
+        xml_escape(&terrain.name),
+        wang_type,
+        xml_escape(&terrain.name)
+    );
+    for (&mask, &tile_index) in &terrain.tiles {
+        let mut wang_id = [0u8; 8];
+        for (bit, &position) in wang_positions(terrain.kind).iter().enumerate() {
+            wang_id[position] = (mask >> bit) & 1;
+        }
+        let wang_id: Vec<String> = wang_id.iter().map(u8::to_string).collect();
+        xml.push_str(&format!("   <wangtile tileid=\"{}\" wangid=\"{}\"/>\n", tile_index, wang_id.join(",")));
+    }
+    xml.push_str("  </wangset>\n");
+    xml
+}
+
+/// Lit les `<wangset>` d'un tileset (toute couleur non nulle compte comme terrain)
+fn tileset_terrains(tileset: roxmltree::Node) -> Vec<TerrainSet> {
+    let wangsets = tileset.children().filter(|n| n.has_tag_name("wangsets"));
+    wangsets
+        .flat_map(|n| n.children())
+        .filter(|n| n.has_tag_name("wangset"))
+        .map(|wangset| {
+            let kind = match wangset.attribute("type") {
+                Some("edge") => TerrainKind::Edge4,
+                Some("corner") => TerrainKind::Corner,
+                _ => TerrainKind::Blob47,
+            };
+            let mut terrain = TerrainSet::new(wangset.attribute("name").unwrap_or("terrain").to_string(), kind);
+            for tile in wangset.children().filter(|n| n.has_tag_name("wangtile")) {
+                let (Some(tile_index), Some(wang_id)) = (
+                    tile.attribute("tileid").and_then(|v| v.parse::<u32>().ok()),
+                    tile.attribute("wangid"),
+                ) else {
+                    continue;
+                };
+                let wang_id: Vec<bool> = wang_id.split(',').map(|v| v.trim() != "0").collect();
+                let mask = wang_positions(kind)
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &position)| wang_id.get(position).copied().unwrap_or(false))
+                    .fold(0u8, |mask, (bit, _)| mask | 1 << bit);
+                terrain.tiles.entry(mask).or_insert(tile_index);
+            }
+            terrain
+        })
+        .collect()
+}
+
+/// Génère le `<tile>` d'un tile avec ses propriétés, ses collisions et son animation
+fn tile_metadata_to_tsx(tile_index: u32, metadata: &TileMetadata) -> String {
+    let mut xml = format!(" <tile id=\"{}\">\n", tile_index);
+    if !metadata.properties.is_empty() {
+        xml.push_str("  <properties>\n");
+        for (name, property) in &metadata.properties {
+            let value = match property {
+                TileProperty::Bool(value) => value.to_string(),
+                TileProperty::Int(value) => value.to_string(),
+                TileProperty::Float(value) => value.to_string(),
+                TileProperty::String(value) => value.clone(),
+                TileProperty::Color(rgb) => rgb_to_hex(*rgb),
+            };
+            xml.push_str(&format!(
+                "   <property name=\"{}\" type=\"{}\" value=\"{}\"/>\n",
+                xml_escape(name),
+                property.type_name(),
+                xml_escape(&value)
+            ));
+        }
+        xml.push_str("  </properties>\n");
+    }
+    if !metadata.collision.is_empty() {
+        xml.push_str("  <objectgroup draworder=\"index\">\n");
+        for (id, shape) in metadata.collision.iter().enumerate() {
+            match shape {
+                CollisionShape::Rect { x, y, width, height } => xml.push_str(&format!(
+                    "   <object id=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                    id + 1,
+                    x,
+                    y,
+                    width,
+                    height
+                )),
+                CollisionShape::Polygon { points } => {
+                    let points: Vec<String> = points.iter().map(|[px, py]| format!("{},{}", px, py)).collect();
+                    xml.push_str(&format!(
+                        "   <object id=\"{}\" x=\"0\" y=\"0\">\n    <polygon points=\"{}\"/>\n   </object>\n",
+                        id + 1,
+                        points.join(" ")
+                    ));
+                }
+            }
+        }
+        xml.push_str("  </objectgroup>\n");
+    }
+    if !metadata.animation.is_empty() {
+        xml.push_str("  <animation>\n");
+        for frame in &metadata.animation {
+            xml.push_str(&format!(
+                "   <frame tileid=\"{}\" duration=\"{}\"/>\n",
+                frame.tile_index, frame.duration_ms
+            ));
+        }
+        xml.push_str("  </animation>\n");
+    }
+    xml.push_str(" </tile>\n");
+    xml
+}
+
+/// Propriétés, collisions et animations des `<tile>` d'un tileset Tiled
+fn tileset_tile_metadata(tileset: roxmltree::Node) -> BTreeMap<u32, TileMetadata> {
+    let mut tiles = BTreeMap::new();
+    for tile in tileset.children().filter(|n| n.has_tag_name("tile")) {
+        let Some(id) = tile.attribute("id").and_then(|v| v.parse::<u32>().ok()) else {
+            continue;
+        };
+        let mut metadata = TileMetadata::default();
+
+        let properties = tile.children().filter(|n| n.has_tag_name("properties"));
+        for property in properties.flat_map(|n| n.children()).filter(|n| n.has_tag_name("property")) {
+            let Some(name) = property.attribute("name") else {
+                continue;
+            };
+            let value = property.attribute("value").or(property.text()).unwrap_or_default();
+            let parsed = match property.attribute("type").unwrap_or("string") {
+                "bool" => value.parse().ok().map(TileProperty::Bool),
+                "int" => value.parse().ok().map(TileProperty::Int),
+                "float" => value.parse().ok().map(TileProperty::Float),
+                "color" => parse_hex_color(value).map(TileProperty::Color),
+                _ => Some(TileProperty::String(value.to_string())),
+            };
+            if let Some(parsed) = parsed {
+                metadata.properties.insert(name.to_string(), parsed);
+            }
+        }
+
+        let groups = tile.children().filter(|n| n.has_tag_name("objectgroup"));
+        for object in groups.flat_map(|n| n.children()).filter(|n| n.has_tag_name("object")) {
+            let number = |name: &str| object.attribute(name).and_then(|v| v.parse::<f32>().ok()).unwrap_or(0.0);
+            let (x, y) = (number("x"), number("y"));
+            // Les points d'un polygone Tiled sont relatifs à la position de l'objet
+            let polygon = object.children().find(|n| n.has_tag_name("polygon"));
+            let shape = match polygon.and_then(|n| n.attribute("points")) {
+                Some(points) => CollisionShape::Polygon {
+                    points: points
+                        .split_whitespace()
+                        .filter_map(|point| {
+                            let (px, py) = point.split_once(',')?;
+                            Some([x + px.parse::<f32>().ok()?, y + py.parse::<f32>().ok()?])
+                        })
+                        .collect(),
+                },
+                None => CollisionShape::Rect { x, y, width: number("width"), height: number("height") },
+            };
+            metadata.collision.push(shape);
+        }
+
+        let animations = tile.children().filter(|n| n.has_tag_name("animation"));
+        for frame in animations.flat_map(|n| n.children()).filter(|n| n.has_tag_name("frame")) {
+            let value = |name: &str| frame.attribute(name).and_then(|v| v.parse::<u32>().ok());
+            if let (Some(tile_index), Some(duration_ms)) = (value("tileid"), value("duration")) {
+                metadata.animation.push(AnimationFrame { tile_index, duration_ms });
+            }
+        }
+
+        if !metadata.is_empty() {
+            tiles.insert(id, metadata);
+        }
+    }
+    tiles
+}
+
+/// Génère le contenu .tsx du tileset de couleurs
+fn color_tileset_to_tsx(colors: &[[u8; 3]], tile_size: u32, image_name: &str) -> String {
+    let columns = COLOR_TILESET_COLUMNS.min(colors.len() as u32);
+    let rows = (colors.len() as u32).div_ceil(columns);
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <tileset version=\"1.10\" name=\"couleurs\" tilewidth=\"{}\" tileheight=\"{}\" tilecount=\"{}\" columns=\"{}\">\n \
+         <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+        tile_size,
+        tile_size,
+        colors.len(),
+        columns,
+        xml_escape(image_name),
+        columns * tile_size,
+        rows * tile_size
+    );
+    for (id, rgb) in colors.iter().enumerate() {
+        xml.push_str(&format!(
+            " <tile id=\"{}\">\n  <properties>\n   <property name=\"{}\" type=\"color\" value=\"{}\"/>\n  </properties>\n </tile>\n",
+            id,
+            COLOR_PROPERTY,
+            rgb_to_hex(*rgb)
+        ));
+    }
+    xml.push_str("</tileset>\n");
+    xml
+}
+
+/// Écrit l'image PNG du tileset de couleurs
+fn write_color_image(path: &Path, colors: &[[u8; 3]], tile_size: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let columns = COLOR_TILESET_COLUMNS.min(colors.len() as u32);
+    let rows = (colors.len() as u32).div_ceil(columns);
+    let image = image::RgbImage::from_fn(columns * tile_size, rows * tile_size, |px, py| {
+        let idx = (py / tile_size * columns + px / tile_size) as usize;
+        image::Rgb(colors.get(idx).copied().unwrap_or([0, 0, 0]))
+    });
+    image.save(path)?;
+    Ok(())
+}
+
+/// Couleurs portées par les tiles d'un tileset (propriété `color`), indexées par tile
+fn tileset_colors(tileset: roxmltree::Node) -> Vec<Option<[u8; 3]>> {
+    let mut colors = Vec::new();
+    for tile in tileset.children().filter(|n| n.has_tag_name("tile")) {
+        let Some(id) = tile.attribute("id").and_then(|v| v.parse::<usize>().ok()) else {
+            continue;
+        };
+        let color = tile
+            .descendants()
+            .filter(|n| n.has_tag_name("property"))
+            .find(|n| n.attribute("name") == Some(COLOR_PROPERTY))
+            .and_then(|n| n.attribute("value"))
+            .and_then(parse_hex_color);
+        if colors.len() <= id {
+            colors.resize(id + 1, None);
+        }
+        colors[id] = color;
+    }
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Dossier de travail vide, propre au test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("editor_level_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn round_trip_exemple_projet() {
+        let dir = temp_dir("tmx_round_trip");
+        let mut project = Project::load_from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/exemple_projet.editorproj")).unwrap();
+
+        // L'exemple n'a que des couleurs : on ajoute un tileset, des tiles transformés,
+        // un calque d'objets et un calque de collision
+        let image = dir.join("terrain.png");
+        image::RgbImage::new(64, 32).save(&image).unwrap();
+        project.add_tileset(TilesetMetadata {
+            id: 0,
+            name: "terrain".to_string(),
+            path: image.to_string_lossy().to_string(),
+            tile_width: 16,
+            tile_height: 16,
+            columns: 4,
+            rows: 2,
+            tiles: BTreeMap::new(),
+            terrains: Vec::new(),
+        });
+        let level = project.level_mut();
+        let layer = &mut level.layers[0];
+        layer.set_tile(1, 1, TileData::texture(0, 3));
+        layer.set_tile(2, 1, TileData::Texture { tileset_id: 0, tile_index: 5, flags: TileFlags { flip_x: true, flip_y: false, rotate: true } });
+        layer.set_tile(3, 1, TileData::Texture { tileset_id: 0, tile_index: 7, flags: TileFlags { flip_x: false, flip_y: true, rotate: false } });
+        let mut objects = Layer::new_objects("Entités".to_string());
+        objects.objects.push(LevelObject::new(1, "spawn", 16.0, 32.0, 16.0, 16.0));
+        level.layers.push(objects);
+        let mut logic = Layer::new_logic("Collision".to_string());
+        logic.set_tile(4, 4, TileData::Value(1));
+        logic.set_tile(5, 4, TileData::Value(3));
+        level.layers.push(logic);
+
+        let tmx = dir.join("carte.tmx");
+        project.export_tmx(&tmx.to_string_lossy()).unwrap();
+        let imported = Project::import_tmx(&tmx.to_string_lossy()).unwrap();
+
+        let (before, after) = (project.level(), imported.level());
+        assert_eq!(before.layers.len(), after.layers.len());
+        for (a, b) in before.layers.iter().zip(&after.layers) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.kind, b.kind);
+            assert_eq!(a.tiles, b.tiles, "tiles du calque '{}'", a.name);
+        }
+        let paths = |p: &Project| -> Vec<PathBuf> {
+            p.tilesets.iter().map(|t| std::fs::canonicalize(&t.path).unwrap()).collect()
+        };
+        assert_eq!(paths(&project), paths(&imported));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn import_rejects_invalid_layer_sizes() {
+        let dir = temp_dir("tmx_invalid");
+        let write = |name: &str, layer: &str| {
+            let path = dir.join(name);
+            std::fs::write(
+                &path,
+                format!(
+                    "<map orientation=\"orthogonal\" width=\"2\" height=\"2\" tilewidth=\"16\" tileheight=\"16\">{}</map>",
+                    layer
+                ),
+            )
+            .unwrap();
+            path.to_string_lossy().to_string()
+        };
+
+        let zero = write("zero.tmx", "<layer name=\"A\" width=\"0\" height=\"2\"><data encoding=\"csv\">0,0</data></layer>");
+        assert!(Project::import_tmx(&zero).is_err());
+        let short = write("short.tmx", "<layer name=\"A\" width=\"2\" height=\"2\"><data encoding=\"csv\">0,0,0</data></layer>");
+        assert!(Project::import_tmx(&short).is_err());
+        let valid = write("valid.tmx", "<layer name=\"A\" width=\"2\" height=\"2\"><data encoding=\"csv\">0,0,0,0</data></layer>");
+        assert!(Project::import_tmx(&valid).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn export_refuses_several_levels() {
+        let mut project = Project::new(Level::new("A".to_string(), 4, 4, 16));
+        project.levels.push(Level::new("B".to_string(), 4, 4, 16));
+        let dir = temp_dir("tmx_levels");
+        let path = dir.join("carte.tmx").to_string_lossy().to_string();
+        assert!(project.export_tmx(&path).is_err());
+        project.export_level_tmx(1, &path).unwrap();
+        assert_eq!(Project::import_tmx(&path).unwrap().level().width, 4);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn export_refuses_unknown_tilesets_and_indices() {
+        let dir = temp_dir("tmx_references");
+        let path = dir.join("carte.tmx").to_string_lossy().to_string();
+        let mut project = Project::new(Level::new("A".to_string(), 4, 4, 16));
+        project.add_tileset(TilesetMetadata {
+            id: 2,
+            name: "terrain".to_string(),
+            path: dir.join("terrain.png").to_string_lossy().to_string(),
+            tile_width: 16,
+            tile_height: 16,
+            columns: 2,
+            rows: 2,
+            tiles: BTreeMap::new(),
+            terrains: Vec::new(),
+        });
+
+        project.level_mut().layers[1].set_tile(0, 0, TileData::texture(7, 0));
+        let error = project.export_tmx(&path).unwrap_err().to_string();
+        assert!(error.contains("tileset 7 absent"), "{}", error);
+        assert!(!Path::new(&path).exists());
+
+        project.level_mut().layers[1].set_tile(0, 0, TileData::texture(2, 4));
+        let error = project.export_tmx(&path).unwrap_err().to_string();
+        assert!(error.contains("hors du tileset 2"), "{}", error);
+
+        project.level_mut().layers[1].set_tile(0, 0, TileData::texture(2, 3));
+        project.export_tmx(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,10 @@
-use crate::editor::{EditorState, PaintMode, Tool};
+use crate::asset_manager::TileRef;
+use crate::editor::{BrushTransform, EditorState, FillMode, PaintMode, ResizeDialog, TilesetDialog, Tool, WorldView};
+use crate::level::{Anchor, LayerKind, LogicValue, TileData, TileEncoding, TileFlags};
+use crate::project::{AnimationFrame, CollisionShape, TileProperty};
+use crate::terrain::{self, TerrainKind, TerrainSet};
+use crate::reachability::{Movement, ReachabilitySettings};
+use crate::validation::{Severity, ValidationRule, ValidationSettings};
 use eframe::egui;
 
 /// Convertit une couleur RGB en string hexadécimale
@@ -7,7 +13,7 @@ fn rgb_to_hex(rgb: [u8; 3]) -> String {
 }
 
 /// Parse une string hexadécimale en RGB
-fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
+pub fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
     let hex = hex.trim().trim_start_matches('#');
     
     if hex.len() == 6 {
@@ -31,14 +37,66 @@ fn update_hex_from_rgb(state: &mut EditorState) {
     state.color_hex_input = rgb_to_hex(state.selected_color);
 }
 
+/// Remplace le niveau courant par un projet et recharge ses tilesets
+fn open_project(ctx: &egui::Context, state: &mut EditorState, project: crate::project::Project, filename: &str) {
+    let level_count = project.levels.len();
+    let mut levels = project.levels.into_iter();
+    let Some(first) = levels.next() else {
+        state.show_notification(format!("❌ {} : projet sans niveau", filename));
+        return;
+    };
+    state.set_levels(first, levels.collect());
+    state.reach_settings = project.validation.reachability.clone().unwrap_or_default();
+    state.validation = project.validation;
+    
+    // Recharger tous les tilesets
+    state.asset_manager = crate::asset_manager::AssetManager::new();
+    let mut loaded_count = 0;
+    let mut missing_count = 0;
+    let mut rejected = Vec::new();
+    
+    for tileset_meta in &project.tilesets {
+        // Un tileset introuvable garde son ID pour que ses tiles restent référencés
+        match state.asset_manager.restore_tileset(ctx, tileset_meta) {
+            Ok(true) => loaded_count += 1,
+            Ok(false) => missing_count += 1,
+            Err(e) => {
+                eprintln!("Tileset '{}' ignoré : {}", tileset_meta.name, e);
+                rejected.push(tileset_meta.name.clone());
+            }
+        }
+    }
+    
+    state.last_loaded_file = Some(filename.to_string());
+    state.zoom = 1.0;
+    state.offset = egui::Vec2::ZERO;
+    
+    if !rejected.is_empty() {
+        // Les tiles de l'ID en double s'affichent avec le premier tileset qui le porte
+        state.show_notification(format!("⚠️ Projet chargé : {} ; tileset(s) ignoré(s), ID en double : {}",
+            filename, rejected.join(", ")));
+        state.show_relocate_dialog = missing_count > 0;
+    } else if missing_count == 0 {
+        state.show_notification(format!("✅ Projet chargé : {} ({} niveau(x), {} tilesets)", 
+            filename,
+            level_count,
+            loaded_count));
+    } else {
+        state.show_notification(format!("⚠️ Projet chargé : {} ({}/{} tilesets, {} introuvable(s))", 
+            filename, loaded_count, project.tilesets.len(), missing_count));
+        state.show_relocate_dialog = true;
+    }
+}
+
 pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
     egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
         egui::menu::bar(ui, |ui| {
             ui.menu_button("Fichier", |ui| {
                 if ui.button("📁 Nouveau").clicked() {
-                    state.level =
-                        crate::level::Level::new("Nouveau Niveau".to_string(), 64, 48, 16);
+                    state.set_levels(crate::level::Level::new("Nouveau Niveau".to_string(), 64, 48, 16), Vec::new());
                     state.asset_manager = crate::asset_manager::AssetManager::new();
+                    state.validation = ValidationSettings::default();
+                    state.reach_settings = ReachabilitySettings::default();
                     state.last_loaded_file = None;
                     ui.close_menu();
                 }
@@ -53,8 +111,7 @@ pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
                         .save_file()
                     {
                         let path_str = path.to_str().unwrap();
-                        let mut project = crate::project::Project::new(state.level.clone());
-                        project.tilesets = state.asset_manager.get_metadata();
+                        let project = state.project();
                         
                         match project.save_to_file(path_str) {
                             Ok(_) => {
@@ -104,45 +161,7 @@ pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
                         match crate::project::Project::load_from_file(path_str) {
                             Ok(project) => {
                                 let filename = path.file_name().unwrap().to_str().unwrap();
-                                state.level = project.level;
-                                
-                                // Recharger tous les tilesets
-                                state.asset_manager = crate::asset_manager::AssetManager::new();
-                                let mut loaded_count = 0;
-                                let mut failed = Vec::new();
-                                
-                                for tileset_meta in &project.tilesets {
-                                    let tileset_path = std::path::PathBuf::from(&tileset_meta.path);
-                                    match state.asset_manager.load_tileset(
-                                        ctx,
-                                        tileset_path.clone(),
-                                        tileset_meta.tile_width,
-                                        tileset_meta.tile_height,
-                                    ) {
-                                        Ok(_) => loaded_count += 1,
-                                        Err(e) => {
-                                            failed.push(format!("{}: {}", tileset_meta.name, e));
-                                        }
-                                    }
-                                }
-                                
-                                state.last_loaded_file = Some(filename.to_string());
-                                state.current_layer = 1.min(state.level.layers.len() - 1);
-                                state.zoom = 1.0;
-                                state.offset = egui::Vec2::ZERO;
-                                
-                                if failed.is_empty() {
-                                    state.show_notification(format!("✅ Projet chargé : {} ({} calques, {} tilesets)", 
-                                        filename,
-                                        state.level.layers.len(),
-                                        loaded_count));
-                                } else {
-                                    state.show_notification(format!("⚠️ Projet chargé : {} ({}/{} tilesets)", 
-                                        filename, loaded_count, project.tilesets.len()));
-                                    for error in &failed {
-                                        eprintln!("⚠️ Tileset non chargé: {}", error);
-                                    }
-                                }
+                                open_project(ctx, state, project, filename);
                             }
                             Err(e) => {
                                 state.show_notification(format!("❌ Erreur de chargement : {}", e));
@@ -162,9 +181,8 @@ pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
                         match crate::level::Level::load_from_file(path_str) {
                             Ok(level) => {
                                 let filename = path.file_name().unwrap().to_str().unwrap();
-                                state.level = level;
+                                state.set_levels(level, Vec::new());
                                 state.last_loaded_file = Some(filename.to_string());
-                                state.current_layer = 1.min(state.level.layers.len() - 1);
                                 state.zoom = 1.0;
                                 state.offset = egui::Vec2::ZERO;
                                 state.show_notification(format!("✅ Niveau chargé : {} ({} calques, {} tiles)\n⚠️ Tilesets non chargés (utilisez .editorproj)", 
@@ -181,6 +199,53 @@ pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
                     ui.close_menu();
                 }
 
+                ui.separator();
+                ui.label("🗺️ Tiled");
+
+                if ui.button("  📤 Exporter (.tmx)").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Carte Tiled", &["tmx"])
+                        .set_file_name("mon_niveau.tmx")
+                        .save_file()
+                    {
+                        let path_str = path.to_str().unwrap();
+                        let mut project = crate::project::Project::new(state.level.clone());
+                        project.tilesets = state.asset_manager.get_metadata();
+                        
+                        match project.export_tmx(path_str) {
+                            Ok(_) => {
+                                state.show_notification(format!("✅ Carte Tiled exportée : {}", 
+                                    path.file_name().unwrap().to_str().unwrap()));
+                            }
+                            Err(e) => {
+                                state.show_notification(format!("❌ Erreur d'export : {}", e));
+                                eprintln!("Erreur d'export TMX: {}", e);
+                            }
+                        }
+                    }
+                    ui.close_menu();
+                }
+
+                if ui.button("  📥 Importer (.tmx)").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Carte Tiled", &["tmx"])
+                        .pick_file()
+                    {
+                        let path_str = path.to_str().unwrap();
+                        match crate::project::Project::import_tmx(path_str) {
+                            Ok(project) => {
+                                let filename = path.file_name().unwrap().to_str().unwrap();
+                                open_project(ctx, state, project, filename);
+                            }
+                            Err(e) => {
+                                state.show_notification(format!("❌ Erreur d'import : {}", e));
+                                eprintln!("Erreur d'import TMX: {}", e);
+                            }
+                        }
+                    }
+                    ui.close_menu();
+                }
+
                 ui.separator();
 
                 if ui.button("❌ Quitter").clicked() {
@@ -190,9 +255,7 @@ pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
 
             ui.menu_button("Édition", |ui| {
                 if ui.button("🗑️ Effacer le calque actuel").clicked() {
-                    if let Some(layer) = state.level.layers.get_mut(state.current_layer) {
-                        layer.clear();
-                    }
+                    state.clear_layer(state.current_layer);
                     ui.close_menu();
                 }
 
@@ -200,17 +263,23 @@ pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
 
                 if ui.button("➕ Ajouter un calque").clicked() {
                     let layer_count = state.level.layers.len();
-                    state.level.add_layer(format!("Layer {}", layer_count + 1));
-                    state.current_layer = layer_count;
+                    state.add_layer(format!("Layer {}", layer_count + 1));
+                    ui.close_menu();
+                }
+
+                if ui.button("🎯 Ajouter un calque d'objets").clicked() {
+                    let layer_count = state.level.layers.len();
+                    state.add_object_layer(format!("Objets {}", layer_count + 1));
+                    ui.close_menu();
+                }
+
+                if ui.button("🧱 Ajouter un calque de collision").clicked() {
+                    state.add_logic_layer("Collision".to_string());
                     ui.close_menu();
                 }
 
                 if ui.button("➖ Supprimer le calque actuel").clicked() {
-                    if state.level.remove_layer(state.current_layer) {
-                        if state.current_layer >= state.level.layers.len() {
-                            state.current_layer = state.level.layers.len().saturating_sub(1);
-                        }
-                    }
+                    state.remove_layer(state.current_layer);
                     ui.close_menu();
                 }
                 
@@ -220,10 +289,27 @@ pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
                     state.show_layer_config = true;
                     ui.close_menu();
                 }
+                
+                ui.separator();
+                
+                // Budget mémoire de l'historique Undo/Redo
+                ui.horizontal(|ui| {
+                    ui.label("Mémoire historique (Mo):");
+                    let mut budget_mb = state.history_budget() / (1024 * 1024);
+                    if ui.add(egui::DragValue::new(&mut budget_mb).range(1..=1024)).changed() {
+                        state.set_history_budget(budget_mb * 1024 * 1024);
+                    }
+                });
             });
 
             ui.menu_button("Affichage", |ui| {
                 ui.checkbox(&mut state.show_grid, "Afficher la grille");
+                ui.checkbox(&mut state.parallax_preview, "🎞 Aperçu parallaxe")
+                    .on_hover_text("Applique décalages et parallaxe des calques en se déplaçant (outils désactivés)");
+                if ui.button("⚠️ Vérifier le projet...").clicked() {
+                    state.run_validation();
+                    ui.close_menu();
+                }
 
                 ui.separator();
                 ui.label(format!("Zoom: {:.0}%", state.zoom * 100.0));
@@ -234,44 +320,467 @@ pub fn draw_top_panel(ctx: &egui::Context, state: &mut EditorState) {
                 }
                 
                 ui.separator();
-                ui.label("⚙️ Configuration du Canvas");
-                
+                ui.label(format!("⚙️ Canvas : {}x{}", state.level.width, state.level.height));
+
+                if ui.button("📐 Redimensionner / décaler...").clicked() {
+                    state.resize_dialog = Some(ResizeDialog {
+                        width: state.level.width,
+                        height: state.level.height,
+                        anchor: Anchor::TOP_LEFT,
+                        shift: (0, 0),
+                    });
+                    ui.close_menu();
+                }
+
+                if ui.button("✂️ Recadrer sur le contenu").clicked() {
+                    state.crop_level_to_content();
+                    ui.close_menu();
+                }
+
+                // Les presets gardent le coin haut-gauche en place
+                for (label, width, height) in [
+                    ("🔲 Preset Petit (32x24)", 32, 24),
+                    ("🔳 Preset Moyen (64x48)", 64, 48),
+                    ("🔴 Preset Grand (128x96)", 128, 96),
+                    ("♾️ Preset Énorme (256x256)", 256, 256),
+                ] {
+                    if ui.button(label).clicked() {
+                        state.resize_level(width, height, Anchor::TOP_LEFT);
+                    }
+                }
+            });
+        });
+    });
+}
+
+/// Types proposés pour une nouvelle propriété de tile
+const TILE_PROPERTY_TYPES: [&str; 5] = ["bool", "int", "float", "string", "color"];
+
+/// Éditeur des propriétés et collisions du tile sélectionné dans le tileset
+fn draw_tile_metadata_editor(ui: &mut egui::Ui, state: &mut EditorState) {
+    let PaintMode::TextureTile { tileset_id, tile_index } = state.paint_mode else {
+        return;
+    };
+    let tile_ref = TileRef { tileset_id, tile_index };
+    let Some(tileset) = state.asset_manager.get_tileset(tileset_id) else {
+        return;
+    };
+    let (tile_width, tile_height) = (tileset.tile_width as f32, tileset.tile_height as f32);
+    let last_tile = (tileset.columns * tileset.rows).saturating_sub(1);
+    let texture_id = tileset.texture.id();
+    // L'aperçu joue l'animation du tile
+    let elapsed_ms = (ui.input(|i| i.time) * 1000.0) as u64;
+    if state.asset_manager.is_animated(tile_ref) {
+        ui.ctx().request_repaint();
+    }
+    let uv = state
+        .asset_manager
+        .get_tile_uv(state.asset_manager.animated_tile(tile_ref, elapsed_ms))
+        .map(|(u1, v1, u2, v2)| egui::Rect::from_min_max(egui::pos2(u1, v1), egui::pos2(u2, v2)));
+    let original = state.asset_manager.get_tile_metadata(tile_ref).cloned().unwrap_or_default();
+    let mut metadata = original.clone();
+
+    ui.add_space(5.0);
+    egui::CollapsingHeader::new(format!("🏷️ Tile #{} : propriétés", tile_index))
+        .id_salt("tile_metadata_editor")
+        .default_open(true)
+        .show(ui, |ui| {
+            // Aperçu du tile avec ses collisions
+            let preview_size = 96.0;
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(preview_size, preview_size), egui::Sense::hover());
+            if let Some(uv) = uv {
+                ui.painter().image(texture_id, rect, uv, egui::Color32::WHITE);
+            }
+            let scale = egui::vec2(preview_size / tile_width, preview_size / tile_height);
+            let to_preview = |x: f32, y: f32| rect.min + egui::vec2(x * scale.x, y * scale.y);
+            let collision_color = egui::Color32::from_rgba_unmultiplied(255, 0, 0, 80);
+            for shape in &metadata.collision {
+                match shape {
+                    CollisionShape::Rect { x, y, width, height } => {
+                        ui.painter().rect(
+                            egui::Rect::from_min_max(to_preview(*x, *y), to_preview(x + width, y + height)),
+                            0.0,
+                            collision_color,
+                            (1.0, egui::Color32::RED),
+                        );
+                    }
+                    CollisionShape::Polygon { points } => {
+                        let points = points.iter().map(|[x, y]| to_preview(*x, *y)).collect();
+                        ui.painter().add(egui::Shape::closed_line(points, (1.5, egui::Color32::RED)));
+                    }
+                }
+            }
+            ui.painter().rect_stroke(rect, 0.0, (1.0, egui::Color32::GRAY));
+
+            // Propriétés typées
+            ui.label("Propriétés :");
+            let mut removed = None;
+            for (name, property) in metadata.properties.iter_mut() {
                 ui.horizontal(|ui| {
-                    ui.label("Largeur:");
-                    ui.add(egui::DragValue::new(&mut state.level.width)
-                        .speed(1.0)
-                        .clamp_range(10..=1000));
+                    ui.label(format!("{} ({})", name, property.type_name()));
+                    match property {
+                        TileProperty::Bool(value) => {
+                            ui.checkbox(value, "");
+                        }
+                        TileProperty::Int(value) => {
+                            ui.add(egui::DragValue::new(value));
+                        }
+                        TileProperty::Float(value) => {
+                            ui.add(egui::DragValue::new(value).speed(0.1));
+                        }
+                        TileProperty::String(value) => {
+                            ui.add(egui::TextEdit::singleline(value).desired_width(80.0));
+                        }
+                        TileProperty::Color(rgb) => {
+                            ui.color_edit_button_srgb(rgb);
+                        }
+                    }
+                    if ui.small_button("🗑").on_hover_text("Supprimer la propriété").clicked() {
+                        removed = Some(name.clone());
+                    }
                 });
-                
+            }
+            if let Some(name) = removed {
+                metadata.properties.remove(&name);
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut state.tile_property_name).desired_width(70.0).hint_text("nom"));
+                egui::ComboBox::from_id_salt("tile_property_type")
+                    .selected_text(state.tile_property_type.as_str())
+                    .width(60.0)
+                    .show_ui(ui, |ui| {
+                        for type_name in TILE_PROPERTY_TYPES {
+                            ui.selectable_value(&mut state.tile_property_type, type_name.to_string(), type_name);
+                        }
+                    });
+                let name = state.tile_property_name.trim().to_string();
+                let can_add = !name.is_empty() && !metadata.properties.contains_key(&name);
+                if ui.add_enabled(can_add, egui::Button::new("➕")).clicked() {
+                    if let Some(property) = TileProperty::default_for(&state.tile_property_type) {
+                        metadata.properties.insert(name, property);
+                        state.tile_property_name.clear();
+                    }
+                }
+            });
+
+            // Formes de collision (pixels relatifs au tile)
+            ui.add_space(5.0);
+            ui.label("Collisions :");
+            let mut removed = None;
+            for (idx, shape) in metadata.collision.iter_mut().enumerate() {
                 ui.horizontal(|ui| {
-                    ui.label("Hauteur:");
-                    ui.add(egui::DragValue::new(&mut state.level.height)
-                        .speed(1.0)
-                        .clamp_range(10..=1000));
+                    match shape {
+                        CollisionShape::Rect { x, y, width, height } => {
+                            ui.label("▭");
+                            ui.add(egui::DragValue::new(x).range(0.0..=tile_width).prefix("x "));
+                            ui.add(egui::DragValue::new(y).range(0.0..=tile_height).prefix("y "));
+                            ui.add(egui::DragValue::new(width).range(0.0..=tile_width).prefix("l "));
+                            ui.add(egui::DragValue::new(height).range(0.0..=tile_height).prefix("h "));
+                        }
+                        CollisionShape::Polygon { points } => {
+                            ui.label(format!("⬠ {} points", points.len()));
+                        }
+                    }
+                    if ui.small_button("🗑").on_hover_text("Supprimer la forme").clicked() {
+                        removed = Some(idx);
+                    }
                 });
-                
-                if ui.button("🔲 Preset Petit (32x24)").clicked() {
-                    state.level.width = 32;
-                    state.level.height = 24;
+                if let CollisionShape::Polygon { points } = shape {
+                    ui.indent(("polygon_points", idx), |ui| {
+                        for point in points.iter_mut() {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut point[0]).range(0.0..=tile_width).prefix("x "));
+                                ui.add(egui::DragValue::new(&mut point[1]).range(0.0..=tile_height).prefix("y "));
+                            });
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.small_button("➕ Point").clicked() {
+                                points.push(points.last().copied().unwrap_or([0.0, 0.0]));
+                            }
+                            if points.len() > 3 && ui.small_button("➖ Point").clicked() {
+                                points.pop();
+                            }
+                        });
+                    });
                 }
-                
-                if ui.button("🔳 Preset Moyen (64x48)").clicked() {
-                    state.level.width = 64;
-                    state.level.height = 48;
+            }
+            if let Some(idx) = removed {
+                metadata.collision.remove(idx);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("➕ Rectangle").on_hover_text("Collision couvrant tout le tile").clicked() {
+                    metadata.collision.push(CollisionShape::Rect {
+                        x: 0.0,
+                        y: 0.0,
+                        width: tile_width,
+                        height: tile_height,
+                    });
                 }
-                
-                if ui.button("🔴 Preset Grand (128x96)").clicked() {
-                    state.level.width = 128;
-                    state.level.height = 96;
+                if ui.button("➕ Polygone").on_hover_text("Triangle à ajuster point par point").clicked() {
+                    metadata.collision.push(CollisionShape::Polygon {
+                        points: vec![[0.0, tile_height], [tile_width, tile_height], [tile_width, 0.0]],
+                    });
                 }
-                
-                if ui.button("♾️ Preset Énorme (256x256)").clicked() {
-                    state.level.width = 256;
-                    state.level.height = 256;
+            });
+
+            // Animation : images jouées en boucle à la place du tile
+            ui.add_space(5.0);
+            ui.label("🎞️ Animation :");
+            let mut removed = None;
+            for (idx, frame) in metadata.animation.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut frame.tile_index).range(0..=last_tile).prefix("#"));
+                    ui.add(egui::DragValue::new(&mut frame.duration_ms).range(1..=10_000).suffix(" ms"));
+                    if ui.small_button("🗑").on_hover_text("Supprimer l'image").clicked() {
+                        removed = Some(idx);
+                    }
+                });
+            }
+            if let Some(idx) = removed {
+                metadata.animation.remove(idx);
+            }
+            if ui.button("➕ Image").on_hover_text("Ajoute le tile suivant de la planche").clicked() {
+                let last = metadata.animation.last().copied();
+                metadata.animation.push(AnimationFrame {
+                    tile_index: last.map_or(tile_index, |frame| (frame.tile_index + 1).min(last_tile)),
+                    duration_ms: last.map_or(100, |frame| frame.duration_ms),
+                });
+            }
+        });
+
+    if metadata != original {
+        state.asset_manager.set_tile_metadata(tile_ref, metadata);
+    }
+}
+
+/// Aperçu d'un masque de terrain : 3x3 cases (ou 4 coins) pleines/vides
+fn draw_terrain_mask(ui: &mut egui::Ui, kind: TerrainKind, mask: u8) {
+    let size = 18.0;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+    let fill = egui::Color32::from_rgb(180, 120, 255);
+    let painter = ui.painter();
+    painter.rect_filled(rect, 0.0, egui::Color32::from_gray(40));
+
+    let cell = |col: f32, row: f32, span: f32| {
+        egui::Rect::from_min_size(rect.min + egui::vec2(col, row) * size / span, egui::Vec2::splat(size / span))
+    };
+    match kind {
+        TerrainKind::Corner => {
+            // NO=1, NE=2, SE=4, SO=8
+            for (bit, (col, row)) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].into_iter().enumerate() {
+                if mask & (1 << bit) != 0 {
+                    painter.rect_filled(cell(col, row, 2.0), 0.0, fill);
+                }
+            }
+        }
+        TerrainKind::Edge4 | TerrainKind::Blob47 => {
+            painter.rect_filled(cell(1.0, 1.0, 3.0), 0.0, fill);
+            let neighbours: &[(u8, f32, f32)] = if kind == TerrainKind::Edge4 {
+                &[(1, 1.0, 0.0), (2, 2.0, 1.0), (4, 1.0, 2.0), (8, 0.0, 1.0)]
+            } else {
+                &[
+                    (terrain::NORTH, 1.0, 0.0),
+                    (terrain::NORTH_EAST, 2.0, 0.0),
+                    (terrain::EAST, 2.0, 1.0),
+                    (terrain::SOUTH_EAST, 2.0, 2.0),
+                    (terrain::SOUTH, 1.0, 2.0),
+                    (terrain::SOUTH_WEST, 0.0, 2.0),
+                    (terrain::WEST, 0.0, 1.0),
+                    (terrain::NORTH_WEST, 0.0, 0.0),
+                ]
+            };
+            for &(bit, col, row) in neighbours {
+                if mask & bit != 0 {
+                    painter.rect_filled(cell(col, row, 3.0), 0.0, fill);
+                }
+            }
+        }
+    }
+    painter.rect_stroke(rect, 0.0, (1.0, egui::Color32::GRAY));
+}
+
+/// Terrains d'autotiling du tileset sélectionné : création, choix du pinceau
+/// et association masque → tile
+fn draw_terrain_editor(ui: &mut egui::Ui, state: &mut EditorState) {
+    let Some(tileset_id) = state.selected_tileset else {
+        return;
+    };
+    let Some(tileset) = state.asset_manager.get_tileset(tileset_id) else {
+        return;
+    };
+    let texture_id = tileset.texture.id();
+    let columns = tileset.columns.max(1);
+    let tile_count = tileset.columns * tileset.rows;
+    let original = tileset.terrains.clone();
+    let mut terrains = original.clone();
+    let selected_tile = match state.paint_mode {
+        PaintMode::TextureTile { tileset_id: id, tile_index } if id == tileset_id => Some(tile_index),
+        _ => None,
+    };
+    let tile_uv = |tile_index: u32| {
+        state
+            .asset_manager
+            .get_tile_uv(TileRef { tileset_id, tile_index })
+            .map(|(u1, v1, u2, v2)| egui::Rect::from_min_max(egui::pos2(u1, v1), egui::pos2(u2, v2)))
+    };
+
+    ui.add_space(5.0);
+    egui::CollapsingHeader::new("🧩 Terrains (autotiling)")
+        .id_salt("terrain_editor")
+        .show(ui, |ui| {
+            let mut removed = None;
+            for (index, terrain) in terrains.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let is_selected = state.selected_terrain == Some((tileset_id, index));
+                    let label = format!("{} ({}/{})", terrain.name, terrain.tiles.len(), terrain.kind.masks().len());
+                    if ui.selectable_label(is_selected, label).on_hover_text(terrain.kind.name()).clicked() {
+                        state.selected_terrain = Some((tileset_id, index));
+                        state.current_tool = Tool::Terrain;
+                    }
+                    if ui.small_button("🗑").on_hover_text("Supprimer le terrain").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = removed {
+                terrains.remove(index);
+                state.selected_terrain = None;
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut state.terrain_name).desired_width(90.0).hint_text("nom"));
+                let name = state.terrain_name.trim().to_string();
+                for kind in TerrainKind::all() {
+                    if ui
+                        .add_enabled(!name.is_empty(), egui::Button::new("➕").small())
+                        .on_hover_text(kind.name())
+                        .clicked()
+                    {
+                        terrains.push(TerrainSet::new(name.clone(), kind));
+                        state.selected_terrain = Some((tileset_id, terrains.len() - 1));
+                        state.terrain_name.clear();
+                    }
+                }
+            });
+
+            // Règles du terrain sélectionné
+            let Some((_, index)) = state.selected_terrain.filter(|(id, _)| *id == tileset_id) else {
+                return;
+            };
+            let Some(terrain) = terrains.get_mut(index) else {
+                return;
+            };
+            ui.separator();
+            ui.label(format!("{} : {}", terrain.name, terrain.kind.name()));
+            let masks = terrain.kind.masks();
+
+            // Bloc standard : 4x4 (16 masques) ou 8 par ligne (47 masques), dans l'ordre croissant
+            let block_width = if masks.len() > 16 { 8 } else { 4 };
+            if let Some(base) = selected_tile {
+                if ui
+                    .button("📐 Remplir depuis le tile sélectionné")
+                    .on_hover_text(format!("Masques dans l'ordre croissant, {} par ligne du tileset", block_width))
+                    .clicked()
+                {
+                    for (i, &mask) in masks.iter().enumerate() {
+                        let i = i as u32;
+                        let tile_index = base + (i / block_width) * columns + i % block_width;
+                        if tile_index < tile_count {
+                            terrain.tiles.insert(mask, tile_index);
+                        }
+                    }
+                }
+            } else {
+                ui.small("Sélectionnez un tile de ce tileset pour l'associer à un masque.");
+            }
+
+            egui::ScrollArea::vertical()
+                .id_salt("terrain_rules_scroll")
+                .max_height(250.0)
+                .show(ui, |ui| {
+                    for mask in masks {
+                        ui.horizontal(|ui| {
+                            draw_terrain_mask(ui, terrain.kind, mask);
+                            let tile = terrain.tiles.get(&mask).copied();
+                            let (rect, _) = ui.allocate_exact_size(egui::vec2(18.0, 18.0), egui::Sense::hover());
+                            match tile.and_then(tile_uv) {
+                                Some(uv) => ui.painter().image(texture_id, rect, uv, egui::Color32::WHITE),
+                                None => ui.painter().rect_stroke(rect, 0.0, (1.0, egui::Color32::DARK_GRAY)),
+                            };
+                            ui.label(tile.map_or("—".to_string(), |t| format!("#{}", t)));
+                            if let Some(tile_index) = selected_tile {
+                                if ui.small_button("⬅").on_hover_text("Associer le tile sélectionné").clicked() {
+                                    terrain.tiles.insert(mask, tile_index);
+                                }
+                            }
+                            if tile.is_some() && ui.small_button("✖").on_hover_text("Retirer").clicked() {
+                                terrain.tiles.remove(&mask);
+                            }
+                        });
+                    }
+                });
+        });
+
+    if terrains != original {
+        state.asset_manager.set_terrains(tileset_id, terrains);
+    }
+}
+
+/// Niveaux du projet : ouvrir, renommer, ajouter, dupliquer, supprimer et carte du monde
+fn draw_level_browser(ui: &mut egui::Ui, state: &mut EditorState) {
+    egui::CollapsingHeader::new(format!("🗺️ Niveaux ({})", state.level_count()))
+        .id_salt("level_browser")
+        .default_open(true)
+        .show(ui, |ui| {
+            // Actions appliquées après l'itération
+            let mut opened = None;
+            let mut renamed = None;
+            let mut removed = None;
+            let level_count = state.level_count();
+            for index in 0..level_count {
+                let level = state.level_at(index);
+                ui.horizontal(|ui| {
+                    if index == state.current_level {
+                        let mut name = level.name.clone();
+                        if ui.add(egui::TextEdit::singleline(&mut name).desired_width(120.0)).changed() {
+                            renamed = Some((index, name));
+                        }
+                    } else if ui.selectable_label(false, &level.name).on_hover_text("Ouvrir ce niveau").clicked() {
+                        opened = Some(index);
+                    }
+                    ui.weak(format!("{}x{}", level.width, level.height));
+                    if level_count > 1
+                        && ui.small_button("🗑").on_hover_text("Supprimer le niveau (non annulable)").clicked()
+                    {
+                        removed = Some(index);
+                    }
+                });
+            }
+            if let Some((index, name)) = renamed {
+                state.rename_level(index, name);
+            }
+            if let Some(index) = opened {
+                state.switch_level(index);
+            }
+            if let Some(index) = removed {
+                state.remove_level(index);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("➕ Nouveau").on_hover_text("Niveau vide de même taille").clicked() {
+                    state.add_level();
+                }
+                if ui.button("📄 Dupliquer").on_hover_text("Copie du niveau courant").clicked() {
+                    state.duplicate_level();
                 }
             });
+            let mut show_world = state.world_view.is_some();
+            if ui.toggle_value(&mut show_world, "🌍 Carte du monde").changed() {
+                state.world_view = show_world.then(WorldView::new);
+            }
         });
-    });
 }
 
 pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
@@ -306,6 +815,15 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                     state.selection.start = None;
                     state.selection.end = None;
                 }
+                if ui
+                    .selectable_label(state.current_tool == Tool::MagicWand, "🪄 Baguette")
+                    .on_hover_text("Sélectionner des tiles identiques")
+                    .clicked()
+                {
+                    state.current_tool = Tool::MagicWand;
+                    state.selection.start = None;
+                    state.selection.end = None;
+                }
             });
             
             ui.horizontal(|ui| {
@@ -327,6 +845,22 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                     state.selection.start = None;
                     state.selection.end = None;
                 }
+                if ui
+                    .selectable_label(state.current_tool == Tool::Fill, "🪣 Remplir")
+                    .on_hover_text("Remplir une zone de tiles identiques")
+                    .clicked()
+                {
+                    state.current_tool = Tool::Fill;
+                    state.selection.start = None;
+                    state.selection.end = None;
+                }
+                if ui
+                    .selectable_label(state.current_tool == Tool::Terrain, "🧩 Terrain")
+                    .on_hover_text("Autotiling : choisit le tile selon les voisins")
+                    .clicked()
+                {
+                    state.current_tool = Tool::Terrain;
+                }
             });
             
             // Afficher l'instruction pour les outils de sélection
@@ -340,23 +874,171 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                 }
             }
             
-            // Instructions pour le mode Sélection
-            if state.current_tool == Tool::Select {
+            // Options du pot de peinture
+            if state.current_tool == Tool::Fill {
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut state.fill_mode, FillMode::Contiguous, "Contigu")
+                        .on_hover_text("Remplit la zone contiguë de tiles identiques");
+                    ui.radio_value(&mut state.fill_mode, FillMode::Global, "Global")
+                        .on_hover_text("Remplace tous les tiles identiques du calque");
+                });
+                ui.label("   (clic droit pour vider la zone)");
+                if state.selection.is_active() {
+                    ui.label("   (limité à la sélection)");
+                }
+            }
+
+            // Options de la baguette magique
+            if state.current_tool == Tool::MagicWand {
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut state.fill_mode, FillMode::Contiguous, "Contigu")
+                        .on_hover_text("Sélectionne les tiles identiques voisins");
+                    ui.radio_value(&mut state.fill_mode, FillMode::Global, "Tout le calque")
+                        .on_hover_text("Sélectionne tous les tiles identiques du calque");
+                });
+                ui.label("• Maj+clic : Ajouter, Alt+clic : Retirer");
+                ui.label("   (clic droit pour annuler la sélection)");
+            }
+
+            // Terrain actif du pinceau d'autotiling
+            if state.current_tool == Tool::Terrain {
+                ui.add_space(5.0);
+                let terrain = state.selected_terrain.and_then(|(tileset_id, index)| {
+                    state.asset_manager.get_tileset(tileset_id)?.terrains.get(index)
+                });
+                match terrain {
+                    Some(terrain) => ui.label(format!("🧩 {} ({})", terrain.name, terrain.kind.name())),
+                    None => ui.label("👉 Choisissez un terrain dans 🧩 Terrains du tileset"),
+                };
+                ui.label("   (clic droit pour effacer)");
+            }
+
+            // Retournements / rotation du tile (ou du tampon) peint
+            if matches!(state.current_tool, Tool::Paint | Tool::LineFill | Tool::RectFill | Tool::Fill)
+                && (state.stamp.is_some() || matches!(state.paint_mode, PaintMode::TextureTile { .. }))
+            {
+                ui.add_space(5.0);
+                let flags = state.brush_flags;
+                let on_stamp = state.stamp.is_some();
+                ui.horizontal(|ui| {
+                    for (transform, label, active, hint) in [
+                        (BrushTransform::FlipX, "↔", flags.flip_x, "Retourner horizontalement (X)"),
+                        (BrushTransform::FlipY, "↕", flags.flip_y, "Retourner verticalement (Y)"),
+                        (BrushTransform::Rotate, "⟳", flags.rotate, "Tourner de 90° (R)"),
+                    ] {
+                        if ui.selectable_label(active && !on_stamp, label).on_hover_text(hint).clicked() {
+                            state.transform_brush(transform);
+                        }
+                    }
+                    if !flags.is_none() && !on_stamp && ui.small_button("↺").on_hover_text("Sans transformation").clicked() {
+                        state.brush_flags = TileFlags::NONE;
+                    }
+                });
+            }
+            
+            // Valeurs d'un calque de collision : choix de la valeur peinte, nom et couleur
+            if state.is_logic_layer(state.current_layer) {
+                ui.add_space(5.0);
+                ui.label("🧱 Calque de collision");
+                let mut values = state.level.layers[state.current_layer].logic_values.clone();
+                let mut removed = None;
+                for (i, value) in values.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(state.logic_brush == value.value, value.value.to_string())
+                            .on_hover_text("Valeur peinte par les outils")
+                            .clicked()
+                        {
+                            state.logic_brush = value.value;
+                        }
+                        ui.color_edit_button_srgb(&mut value.color);
+                        ui.add(egui::TextEdit::singleline(&mut value.name).desired_width(90.0));
+                        if ui.small_button("🗑").on_hover_text("Retirer cette valeur").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = removed {
+                    values.remove(i);
+                }
+                let next = values.iter().map(|v| v.value).max().unwrap_or(0).checked_add(1);
+                if let Some(next) = next {
+                    if ui.button("➕ Nouvelle valeur").clicked() {
+                        values.push(LogicValue::new(next, &format!("value_{}", next), [160, 160, 160]));
+                    }
+                }
+                state.set_logic_values(state.current_layer, values);
+                ui.label("• Pinceau, formes, pot : poser la valeur");
+                ui.label("• Gomme : retirer la valeur");
+            }
+            
+            // Instructions pour un calque d'objets (les outils de tiles y sont inactifs)
+            if state.is_object_layer(state.current_layer) {
+                ui.add_space(5.0);
+                ui.label("🎯 Calque d'objets");
+                ui.label("• Double-clic : Créer un objet");
+                ui.label("• Clic : Sélectionner");
+                ui.label("• Glisser : Déplacer");
+                ui.label("• Poignée jaune : Redimensionner");
+                ui.label("• Delete : Supprimer l'objet");
+                ui.horizontal(|ui| {
+                    ui.label("Type créé :");
+                    ui.add(egui::TextEdit::singleline(&mut state.object_type_brush).desired_width(90.0));
+                });
+            } else if state.current_tool == Tool::Select {
                 ui.add_space(5.0);
                 ui.label("📦 Mode Sélection");
                 ui.label("• Glissez pour sélectionner");
                 ui.label("• Ctrl+C : Copier");
                 ui.label("• Ctrl+V : Coller");
                 ui.label("• Delete : Supprimer");
+                ui.label("• Maj / Alt : Ajouter / Retirer une zone");
+                ui.label("• Ctrl+I : Inverser");
+                ui.label("• Glisser l'intérieur : Déplacer");
+                ui.label("• Flèches : Décaler d'une case");
+                ui.label("• X / Y / R : Retourner, tourner");
                 ui.label("• Echap : Annuler");
                 
-                if state.selection.is_active {
-                    ui.label("✅ Sélection active");
-                }
                 if state.clipboard.is_some() {
                     ui.label("📋 Presse-papier plein");
+                    if ui
+                        .button("🖌️ Utiliser comme tampon")
+                        .on_hover_text("Peindre le presse-papier avec l'outil Pinceau")
+                        .clicked()
+                    {
+                        state.use_clipboard_as_stamp();
+                    }
                 }
             }
+
+            // Actions sur la sélection active (outils Sélection et Baguette)
+            if matches!(state.current_tool, Tool::Select | Tool::MagicWand)
+                && state.selection.is_active()
+                && !state.is_object_layer(state.current_layer)
+            {
+                ui.label(format!("✅ Sélection active ({} tiles)", state.selection.mask().len()));
+                ui.horizontal(|ui| {
+                    for (transform, label, hint) in [
+                        (BrushTransform::FlipX, "↔", "Retourner horizontalement (X)"),
+                        (BrushTransform::FlipY, "↕", "Retourner verticalement (Y)"),
+                        (BrushTransform::Rotate, "⟳", "Tourner de 90° (R)"),
+                    ] {
+                        if ui.button(label).on_hover_text(hint).clicked() {
+                            state.transform_selection(transform);
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("🔄 Inverser").on_hover_text("Ctrl+I").clicked() {
+                        state.invert_selection();
+                    }
+                    if ui.button("🪣 Remplir").on_hover_text("Remplir la sélection avec le pinceau").clicked() {
+                        state.fill_selection();
+                    }
+                });
+            }
             
             ui.add_space(10.0);
             
@@ -365,6 +1047,8 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
             ui.separator();
             ui.label("Ctrl+Z : Annuler");
             ui.label("Ctrl+Y : Rétablir");
+            ui.label("X / Y : Retourner le pinceau");
+            ui.label("R : Tourner le pinceau de 90°");
             
             // Indicateurs d'état
             ui.add_space(5.0);
@@ -381,8 +1065,16 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                 };
                 ui.colored_label(undo_color, "↶");
                 ui.colored_label(redo_color, "↷");
+                ui.label(format!(
+                    "{:.1} / {} Mo",
+                    state.history_usage() as f32 / (1024.0 * 1024.0),
+                    state.history_budget() / (1024 * 1024)
+                ));
             });
 
+            ui.add_space(10.0);
+            draw_level_browser(ui, state);
+
             ui.add_space(10.0);
             
             // Section Tilesets
@@ -404,38 +1096,84 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                 }
             }
             
+            let missing_count = state.asset_manager.missing_tilesets().len();
+            if missing_count > 0
+                && ui
+                    .button(format!("🔗 {} tileset(s) introuvable(s)", missing_count))
+                    .on_hover_text("Localiser les images manquantes")
+                    .clicked()
+            {
+                state.show_relocate_dialog = true;
+            }
+            
             ui.add_space(5.0);
             
             // Afficher les tilesets chargés
+            let mut stamp_request = None;  // (tileset, premier tile, dernier tile)
+            let elapsed_ms = (ui.input(|i| i.time) * 1000.0) as u64;
             let tilesets = state.asset_manager.get_all_tilesets();
             if !tilesets.is_empty() {
                 egui::ScrollArea::vertical()
-                    .id_source("tilesets_list_scroll")
+                    .id_salt("tilesets_list_scroll")
                     .max_height(200.0)
                     .show(ui, |ui| {
-                        for (idx, tileset) in tilesets.iter().enumerate() {
-                            let is_selected = state.selected_tileset == Some(idx);
+                        for tileset in tilesets {
+                            let tileset_id = tileset.id;
+                            let is_selected = state.selected_tileset == Some(tileset_id);
                             
                             ui.horizontal(|ui| {
-                                if ui.selectable_label(is_selected, &tileset.name).clicked() {
-                                    state.selected_tileset = Some(idx);
+                                let label = if tileset.missing {
+                                    format!("⚠️ {}", tileset.name)
+                                } else {
+                                    tileset.name.clone()
+                                };
+                                if ui.selectable_label(is_selected, label).clicked() {
+                                    state.selected_tileset = Some(tileset_id);
                                 }
                                 
                                 ui.label(format!("({}x{})", tileset.columns, tileset.rows));
+
+                                if is_selected {
+                                    if ui.small_button("🔀").on_hover_text("Remplacer par un autre tileset").clicked() {
+                                        state.tileset_dialog = Some(TilesetDialog { tileset_id, remove: false, target: None });
+                                    }
+                                    if ui.small_button("🗑").on_hover_text("Retirer le tileset").clicked() {
+                                        state.tileset_dialog = Some(TilesetDialog { tileset_id, remove: true, target: None });
+                                    }
+                                }
                             });
                             
                             // Si ce tileset est sélectionné, afficher la grille de tiles
                             if is_selected {
                                 ui.indent("tileset_tiles", |ui| {
+                                    // Même disposition que l'image : un rectangle glissé forme un tampon
                                     let tile_count = tileset.columns * tileset.rows;
-                                    let tiles_per_row = 4;
-                                    let tile_display_size = 48.0;
+                                    let tiles_per_row = tileset.columns.max(1);
+                                    let tile_display_size = 32.0;
+                                    let highlight = state
+                                        .tileset_drag
+                                        .or_else(|| state.stamp.as_ref()?.tileset_bounds(tileset_id, tileset.columns))
+                                        .or(match state.paint_mode {
+                                            PaintMode::TextureTile { tileset_id: id, tile_index } if id == tileset_id => {
+                                                Some((tile_index, tile_index))
+                                            }
+                                            _ => None,
+                                        });
+                                    let in_highlight = |tile_index: u32| {
+                                        highlight.is_some_and(|(first, last)| {
+                                            let (x, y) = (tile_index % tiles_per_row, tile_index / tiles_per_row);
+                                            let (x0, x1) = ((first % tiles_per_row).min(last % tiles_per_row), (first % tiles_per_row).max(last % tiles_per_row));
+                                            let (y0, y1) = ((first / tiles_per_row).min(last / tiles_per_row), (first / tiles_per_row).max(last / tiles_per_row));
+                                            (x0..=x1).contains(&x) && (y0..=y1).contains(&y)
+                                        })
+                                    };
                                     
-                                    egui::ScrollArea::vertical()
-                                        .id_source(format!("tileset_tiles_scroll_{}", idx))
+                                    egui::ScrollArea::both()
+                                        .id_salt(format!("tileset_tiles_scroll_{}", tileset_id))
                                         .max_height(300.0)
                                         .show(ui, |ui| {
-                                            for row in 0..(tile_count + tiles_per_row - 1) / tiles_per_row {
+                                            ui.spacing_mut().item_spacing = egui::vec2(1.0, 1.0);
+                                            for row in 0..tile_count.div_ceil(tiles_per_row) {
                                                 ui.horizontal(|ui| {
                                                     for col in 0..tiles_per_row {
                                                         let tile_index = row * tiles_per_row + col;
@@ -443,8 +1181,12 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                                                             break;
                                                         }
                                                         
-                                                        let tile_x = tile_index % tileset.columns;
-                                                        let tile_y = tile_index / tileset.columns;
+                                                        // Les tiles animés jouent leur animation (pinceau animé)
+                                                        let tile_ref = TileRef { tileset_id, tile_index };
+                                                        let animated = state.asset_manager.is_animated(tile_ref);
+                                                        let shown = state.asset_manager.animated_tile(tile_ref, elapsed_ms).tile_index;
+                                                        let tile_x = shown % tileset.columns;
+                                                        let tile_y = shown / tileset.columns;
                                                         
                                                         let uv = egui::Rect::from_min_max(
                                                             egui::pos2(
@@ -457,12 +1199,6 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                                                             ),
                                                         );
                                                         
-                                                        let is_selected_tile = matches!(
-                                                            state.paint_mode,
-                                                            PaintMode::TextureTile { tileset_id, tile_index: ti }
-                                                            if tileset_id == idx && ti == tile_index
-                                                        );
-                                                        
                                                         let image_button = egui::ImageButton::new(
                                                             egui::load::SizedTexture::new(
                                                                 tileset.texture.id(),
@@ -470,9 +1206,22 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                                                             )
                                                         ).uv(uv);
                                                         
-                                                        let response = ui.add(image_button);
+                                                        let response = ui
+                                                            .add(image_button)
+                                                            .interact(egui::Sense::click_and_drag());
+
+                                                        if animated {
+                                                            ui.painter().text(
+                                                                response.rect.right_top(),
+                                                                egui::Align2::RIGHT_TOP,
+                                                                "▶",
+                                                                egui::FontId::proportional(10.0),
+                                                                egui::Color32::WHITE,
+                                                            );
+                                                            ui.ctx().request_repaint();
+                                                        }
                                                         
-                                                        if is_selected_tile {
+                                                        if in_highlight(tile_index) {
                                                             ui.painter().rect_stroke(
                                                                 response.rect,
                                                                 2.0,
@@ -481,22 +1230,42 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                                                         }
                                                         
                                                         if response.clicked() {
-                                                            state.paint_mode = PaintMode::TextureTile {
-                                                                tileset_id: idx,
-                                                                tile_index,
-                                                            };
-                                                            state.current_tool = Tool::Paint;
+                                                            stamp_request = Some((tileset_id, tile_index, tile_index));
+                                                        } else if response.drag_started() {
+                                                            state.tileset_drag = Some((tile_index, tile_index));
+                                                        } else if ui.rect_contains_pointer(response.rect) {
+                                                            if let Some((_, end)) = &mut state.tileset_drag {
+                                                                *end = tile_index;
+                                                            }
                                                         }
                                                     }
                                                 });
                                             }
                                         });
+
+                                    // Bouton relâché : le rectangle glissé devient le tampon
+                                    if let Some((first, last)) = state.tileset_drag {
+                                        if !ui.input(|i| i.pointer.primary_down()) {
+                                            stamp_request = Some((tileset_id, first, last));
+                                            state.tileset_drag = None;
+                                        }
+                                    }
                                 });
                             }
                         }
                     });
             }
 
+            if let Some((tileset_id, first, last)) = stamp_request {
+                state.set_tileset_stamp(tileset_id, first, last);
+            }
+
+            // Propriétés et collisions du tile sélectionné
+            draw_tile_metadata_editor(ui, state);
+
+            // Terrains d'autotiling du tileset sélectionné
+            draw_terrain_editor(ui, state);
+
             ui.add_space(10.0);
             ui.heading("🎨 Sélecteur de Couleur");
             ui.separator();
@@ -554,6 +1323,7 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
             // Bouton pour appliquer la couleur
             if ui.button("✏️ Utiliser cette couleur").clicked() {
                 state.paint_mode = PaintMode::ColorTile(state.selected_color);
+                state.stamp = None;
                 state.current_tool = Tool::Paint;
             }
             
@@ -562,7 +1332,7 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
             // Palette de couleurs prédéfinies
             ui.label("Couleurs prédéfinies:");
             egui::ScrollArea::vertical()
-                .id_source("preset_colors_scroll")
+                .id_salt("preset_colors_scroll")
                 .max_height(200.0)
                 .show(ui, |ui| {
                 let preset_colors = vec![
@@ -586,6 +1356,7 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
                             state.selected_color = color;
                             update_hex_from_rgb(state);
                             state.paint_mode = PaintMode::ColorTile(color);
+                            state.stamp = None;
                             state.current_tool = Tool::Paint;
                         }
                         
@@ -606,6 +1377,109 @@ pub fn draw_side_panel(ctx: &egui::Context, state: &mut EditorState) {
         });
 }
 
+/// Types d'objets proposés dans l'inspecteur (le champ reste libre)
+const COMMON_OBJECT_TYPES: [&str; 6] = ["spawn", "exit", "enemy", "trigger", "door", "item"];
+
+/// Inspecteur de l'objet sélectionné (calque d'objets)
+pub fn draw_inspector_panel(ctx: &egui::Context, state: &mut EditorState) {
+    let Some(mut object) = state.selected_object().cloned() else {
+        return;
+    };
+
+    egui::SidePanel::right("inspector_panel")
+        .default_width(220.0)
+        .show(ctx, |ui| {
+            ui.heading(format!("🔍 Objet #{}", object.id));
+            ui.separator();
+
+            egui::Grid::new("object_fields").num_columns(2).show(ui, |ui| {
+                ui.label("Nom :");
+                ui.text_edit_singleline(&mut object.name);
+                ui.end_row();
+
+                ui.label("Type :");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut object.object_type).desired_width(90.0));
+                    egui::ComboBox::from_id_salt("object_type_combo")
+                        .selected_text("▾")
+                        .width(30.0)
+                        .show_ui(ui, |ui| {
+                            for object_type in COMMON_OBJECT_TYPES {
+                                ui.selectable_value(&mut object.object_type, object_type.to_string(), object_type);
+                            }
+                        });
+                });
+                ui.end_row();
+
+                ui.label("X :");
+                ui.add(egui::DragValue::new(&mut object.x).speed(1.0));
+                ui.end_row();
+
+                ui.label("Y :");
+                ui.add(egui::DragValue::new(&mut object.y).speed(1.0));
+                ui.end_row();
+
+                ui.label("Largeur :");
+                ui.add(egui::DragValue::new(&mut object.width).speed(1.0).range(1.0..=f32::MAX));
+                ui.end_row();
+
+                ui.label("Hauteur :");
+                ui.add(egui::DragValue::new(&mut object.height).speed(1.0).range(1.0..=f32::MAX));
+                ui.end_row();
+
+                ui.label("Rotation :");
+                ui.add(egui::DragValue::new(&mut object.rotation).speed(1.0).range(-360.0..=360.0).suffix("°"));
+                ui.end_row();
+            });
+
+            ui.add_space(10.0);
+            ui.label("🏷️ Propriétés");
+            ui.separator();
+
+            // Édition clé/valeur : les renommages et suppressions sont appliqués après l'itération
+            let mut renamed = None;
+            let mut removed = None;
+            for (key, value) in object.properties.iter_mut() {
+                ui.horizontal(|ui| {
+                    let mut new_key = key.clone();
+                    if ui.add(egui::TextEdit::singleline(&mut new_key).desired_width(70.0)).changed() {
+                        renamed = Some((key.clone(), new_key));
+                    }
+                    ui.add(egui::TextEdit::singleline(value).desired_width(90.0));
+                    if ui.small_button("🗑").on_hover_text("Supprimer la propriété").clicked() {
+                        removed = Some(key.clone());
+                    }
+                });
+            }
+            if let Some((old_key, new_key)) = renamed {
+                if !new_key.is_empty() && !object.properties.contains_key(&new_key) {
+                    if let Some(value) = object.properties.remove(&old_key) {
+                        object.properties.insert(new_key, value);
+                    }
+                }
+            }
+            if let Some(key) = removed {
+                object.properties.remove(&key);
+            }
+            if ui.button("➕ Ajouter une propriété").clicked() {
+                let mut index = object.properties.len() + 1;
+                while object.properties.contains_key(&format!("prop{}", index)) {
+                    index += 1;
+                }
+                object.properties.insert(format!("prop{}", index), String::new());
+            }
+
+            ui.add_space(10.0);
+            ui.separator();
+            if ui.button("🗑️ Supprimer l'objet").clicked() {
+                state.delete_selected_object();
+                return;
+            }
+
+            state.update_object(object);
+        });
+}
+
 pub fn draw_bottom_panel(ctx: &egui::Context, state: &mut EditorState) {
     egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
         ui.horizontal(|ui| {
@@ -630,10 +1504,549 @@ pub fn draw_bottom_panel(ctx: &egui::Context, state: &mut EditorState) {
     });
 }
 
+/// Fenêtre de relocalisation des tilesets introuvables au chargement
+fn draw_relocate_dialog(ctx: &egui::Context, state: &mut EditorState) {
+    let missing = state.asset_manager.missing_tilesets();
+    if missing.is_empty() {
+        state.show_relocate_dialog = false;
+        return;
+    }
+
+    let mut open = true;
+    let mut relocate = None;
+    egui::Window::new("🔗 Tilesets introuvables")
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label("Ces images n'ont pas été trouvées. Les tiles concernés sont affichés en magenta.");
+            ui.add_space(5.0);
+            for &id in &missing {
+                let Some(tileset) = state.asset_manager.get_tileset(id) else {
+                    continue;
+                };
+                ui.horizontal(|ui| {
+                    ui.label(format!("⚠️ {}", tileset.name));
+                    if ui.button("📂 Localiser...").clicked() {
+                        relocate = Some(id);
+                    }
+                });
+                ui.small(tileset.path.to_string_lossy());
+            }
+        });
+
+    if let Some(id) = relocate {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Images", &["png", "jpg", "jpeg"])
+            .pick_file()
+        {
+            let folder = path.parent().map(std::path::Path::to_path_buf);
+            match state.asset_manager.relocate_tileset(ctx, id, path) {
+                Ok(()) => {
+                    // Les autres tilesets manquants sont cherchés dans le même dossier
+                    let mut relocated = 1;
+                    for other in state.asset_manager.missing_tilesets() {
+                        let candidate = state
+                            .asset_manager
+                            .get_tileset(other)
+                            .and_then(|t| t.path.file_name().map(|name| name.to_owned()))
+                            .zip(folder.as_ref())
+                            .map(|(name, folder)| folder.join(name));
+                        if let Some(candidate) = candidate.filter(|c| c.exists()) {
+                            if state.asset_manager.relocate_tileset(ctx, other, candidate).is_ok() {
+                                relocated += 1;
+                            }
+                        }
+                    }
+                    state.show_notification(format!("✅ {} tileset(s) relocalisé(s)", relocated));
+                }
+                Err(e) => state.show_notification(format!("❌ {}", e)),
+            }
+        }
+    }
+
+    if !open {
+        state.show_relocate_dialog = false;
+    }
+}
+
+/// Fenêtre de retrait / remplacement d'un tileset : les tiles qui le
+/// référencent sont redirigés vers un autre tileset ou effacés
+fn draw_tileset_dialog(ctx: &egui::Context, state: &mut EditorState) {
+    let Some(mut dialog) = state.tileset_dialog else {
+        return;
+    };
+    let Some(tileset) = state.asset_manager.get_tileset(dialog.tileset_id) else {
+        state.tileset_dialog = None;
+        return;
+    };
+    let name = tileset.name.clone();
+    let references = state.count_tileset_references(dialog.tileset_id);
+
+    // Tileset inutilisé : retrait direct
+    if dialog.remove && references == 0 {
+        state.tileset_dialog = None;
+        state.remove_tileset(dialog.tileset_id);
+        return;
+    }
+
+    let others: Vec<(usize, String)> = state
+        .asset_manager
+        .get_all_tilesets()
+        .iter()
+        .filter(|t| t.id != dialog.tileset_id)
+        .map(|t| (t.id, t.name.clone()))
+        .collect();
+
+    enum Action {
+        Remap(usize),
+        Clear,
+        Cancel,
+    }
+    let mut action = None;
+    let mut open = true;
+    let title = if dialog.remove { "🗑 Retirer un tileset" } else { "🔀 Remplacer un tileset" };
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(format!("'{}' est utilisé par {} tile(s) dans le projet.", name, references));
+            ui.add_space(5.0);
+
+            if others.is_empty() {
+                ui.label("Aucun autre tileset pour le remplacer.");
+            } else {
+                let selected_name = dialog
+                    .target
+                    .and_then(|id| others.iter().find(|(other, _)| *other == id))
+                    .map_or("Choisir...", |(_, name)| name.as_str());
+                egui::ComboBox::from_label("Remplacer par")
+                    .selected_text(selected_name)
+                    .show_ui(ui, |ui| {
+                        for (id, other_name) in &others {
+                            ui.selectable_value(&mut dialog.target, Some(*id), other_name);
+                        }
+                    });
+            }
+            ui.add_space(5.0);
+
+            ui.horizontal(|ui| {
+                let remap_label = if dialog.remove { "🔀 Remplacer et retirer" } else { "🔀 Remplacer" };
+                if let Some(target) = dialog.target {
+                    if ui.button(remap_label).clicked() {
+                        action = Some(Action::Remap(target));
+                    }
+                }
+                if dialog.remove && ui.button("🧹 Effacer les tiles et retirer").clicked() {
+                    action = Some(Action::Clear);
+                }
+                if ui.button("Annuler").clicked() {
+                    action = Some(Action::Cancel);
+                }
+            });
+        });
+
+    state.tileset_dialog = Some(dialog);
+    match action {
+        Some(Action::Remap(target)) => {
+            let count = state.remap_tileset(dialog.tileset_id, target);
+            if dialog.remove {
+                state.remove_tileset(dialog.tileset_id);
+            }
+            state.show_notification(format!("🔀 {} tile(s) redirigé(s)", count));
+            state.tileset_dialog = None;
+        }
+        Some(Action::Clear) => {
+            let count = state.clear_tileset_tiles(dialog.tileset_id);
+            state.remove_tileset(dialog.tileset_id);
+            state.show_notification(format!("🧹 {} tile(s) effacé(s), tileset '{}' retiré", count, name));
+            state.tileset_dialog = None;
+        }
+        Some(Action::Cancel) => state.tileset_dialog = None,
+        None if !open => state.tileset_dialog = None,
+        None => {}
+    }
+}
+
+/// Groupes de calques de la fenêtre de configuration : arborescence, nom,
+/// visibilité, verrou, groupe parent et suppression
+fn draw_layer_groups(ui: &mut egui::Ui, state: &mut EditorState) {
+    ui.heading("📁 Groupes de calques");
+    
+    let tree: Vec<(u32, usize)> = state.level.group_tree().into_iter().map(|(group, depth)| (group.id, depth)).collect();
+    let paths: Vec<(u32, String)> = tree.iter().map(|&(id, _)| (id, state.level.group_path(Some(id)))).collect();
+    let mut reparent = None;
+    let mut removed = None;
+    
+    for &(id, depth) in &tree {
+        let Some(group) = state.level.groups.iter_mut().find(|group| group.id == id) else {
+            continue;
+        };
+        ui.horizontal(|ui| {
+            ui.add_space(depth as f32 * 16.0);
+            ui.label("📁");
+            ui.add(egui::TextEdit::singleline(&mut group.name).desired_width(120.0));
+            ui.checkbox(&mut group.visible, "👁").on_hover_text("Masque tous les calques du groupe");
+            ui.checkbox(&mut group.locked, "🔒").on_hover_text("Verrouille tous les calques du groupe");
+            
+            let parent_name = |parent: Option<u32>| {
+                paths
+                    .iter()
+                    .find(|(g, _)| Some(*g) == parent)
+                    .map_or("Racine".to_string(), |(_, path)| path.clone())
+            };
+            let mut parent = group.parent;
+            egui::ComboBox::from_id_salt(("group_parent", id))
+                .selected_text(parent_name(parent))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut parent, None, parent_name(None));
+                    for &(other, _) in paths.iter().filter(|(other, _)| *other != id) {
+                        ui.selectable_value(&mut parent, Some(other), parent_name(Some(other)));
+                    }
+                });
+            if parent != group.parent {
+                reparent = Some((id, parent));
+            }
+            
+            if ui.small_button("🗑").on_hover_text("Supprimer le groupe (ses calques remontent d'un niveau)").clicked() {
+                removed = Some(id);
+            }
+        });
+    }
+    
+    if let Some((id, parent)) = reparent {
+        if !state.level.set_group_parent(id, parent) {
+            state.show_notification("❌ Un groupe ne peut pas être placé dans un de ses sous-groupes".to_string());
+        }
+    }
+    if let Some(id) = removed {
+        state.level.remove_group(id);
+    }
+    
+    if ui.button("➕ Nouveau groupe").clicked() {
+        let name = format!("Groupe {}", state.level.groups.len() + 1);
+        state.level.add_group(name, None);
+    }
+}
+
+/// Fenêtre de redimensionnement (ancrage 3x3) et de décalage du niveau
+fn draw_resize_dialog(ctx: &egui::Context, state: &mut EditorState) {
+    let Some(mut dialog) = state.resize_dialog else {
+        return;
+    };
+
+    enum Action {
+        Resize,
+        Shift,
+        Close,
+    }
+    let mut action = None;
+    let mut open = true;
+    egui::Window::new("📐 Taille du niveau")
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(format!("Taille actuelle : {}x{}", state.level.width, state.level.height));
+            ui.horizontal(|ui| {
+                ui.label("Largeur:");
+                ui.add(egui::DragValue::new(&mut dialog.width).speed(1.0).range(1..=1000));
+                ui.label("Hauteur:");
+                ui.add(egui::DragValue::new(&mut dialog.height).speed(1.0).range(1..=1000));
+            });
+
+            ui.label("Ancrage du contenu :");
+            egui::Grid::new("resize_anchor").spacing([2.0, 2.0]).show(ui, |ui| {
+                for y in 0..3 {
+                    for x in 0..3 {
+                        let anchor = Anchor { x, y };
+                        let label = if dialog.anchor == anchor { "●" } else { "○" };
+                        if ui.selectable_label(dialog.anchor == anchor, label).clicked() {
+                            dialog.anchor = anchor;
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.label("Les tiles hors des nouvelles limites sont retirés.");
+            if ui.button("✅ Redimensionner").clicked() {
+                action = Some(Action::Resize);
+            }
+
+            ui.separator();
+            ui.label("Décaler tout le niveau (en cases) :");
+            ui.horizontal(|ui| {
+                ui.label("X:");
+                ui.add(egui::DragValue::new(&mut dialog.shift.0).speed(0.2));
+                ui.label("Y:");
+                ui.add(egui::DragValue::new(&mut dialog.shift.1).speed(0.2));
+                if ui.button("↔ Décaler").clicked() {
+                    action = Some(Action::Shift);
+                }
+            });
+
+            ui.add_space(5.0);
+            if ui.button("Fermer").clicked() {
+                action = Some(Action::Close);
+            }
+        });
+
+    state.resize_dialog = Some(dialog);
+    match action {
+        Some(Action::Resize) => state.resize_level(dialog.width, dialog.height, dialog.anchor),
+        Some(Action::Shift) => state.shift_level(dialog.shift.0, dialog.shift.1),
+        Some(Action::Close) => state.resize_dialog = None,
+        None if !open => state.resize_dialog = None,
+        None => {}
+    }
+}
+
+/// Aperçu du tile attendu par une règle de validation
+fn draw_rule_tile(ui: &mut egui::Ui, tile: TileData) {
+    match tile {
+        TileData::Color([r, g, b]) => {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::hover());
+            ui.painter().rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+            ui.painter().rect_stroke(rect, 2.0, (1.0, egui::Color32::GRAY));
+        }
+        TileData::Texture { tileset_id, tile_index, .. } => {
+            ui.label(format!("🖼 {}#{}", tileset_id, tile_index));
+        }
+        TileData::Value(value) => {
+            ui.label(format!("🧱 {}", value));
+        }
+        TileData::Empty => {
+            ui.label("∅");
+        }
+    }
+}
+
+/// Panneau des problèmes : résultat de la vérification (un clic centre la vue
+/// sur la case en cause), contrôles intégrés et règles du projet
+fn draw_problems_panel(ctx: &egui::Context, state: &mut EditorState) {
+    enum Action {
+        Validate,
+        Focus(usize),
+        AddRule,
+        UseBrush(usize),
+        RemoveRule(usize),
+        Analyze,
+        ClearAnalysis,
+        CenterOn((i32, i32)),
+    }
+    let mut action = None;
+    let mut open = true;
+    let level_names: Vec<String> = (0..state.level_count()).map(|index| state.level_at(index).name.clone()).collect();
+    let layer_names: Vec<String> = state.level.layers.iter().map(|layer| layer.name.clone()).collect();
+    let current_level_name = state.level.name.clone();
+
+    egui::Window::new("⚠️ Problèmes")
+        .resizable(true)
+        .default_width(420.0)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("🔄 Vérifier").clicked() {
+                    action = Some(Action::Validate);
+                }
+                let errors = state.problems.iter().filter(|p| p.severity == Severity::Error).count();
+                ui.label(format!("{} erreur(s), {} avertissement(s)", errors, state.problems.len() - errors));
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .id_salt("problems_scroll")
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    if state.problems.is_empty() {
+                        ui.weak("Aucun problème (cliquez sur Vérifier après une modification)");
+                    }
+                    for (index, problem) in state.problems.iter().enumerate() {
+                        let icon = match problem.severity {
+                            Severity::Error => "❌",
+                            Severity::Warning => "⚠️",
+                        };
+                        let mut text = match problem.level.and_then(|level| level_names.get(level)) {
+                            Some(name) if level_names.len() > 1 => format!("{} [{}] {}", icon, name, problem.message),
+                            _ => format!("{} {}", icon, problem.message),
+                        };
+                        if let Some(&(x, y)) = problem.cells.first() {
+                            text.push_str(&format!(" — ({}, {})", x, y));
+                        }
+                        let selected = state.focused_problem == Some(index);
+                        let response = ui.selectable_label(selected, text);
+                        let response = if problem.cells.is_empty() {
+                            response
+                        } else {
+                            response.on_hover_text(format!("{} case(s) : cliquer pour centrer la vue", problem.cells.len()))
+                        };
+                        if response.clicked() {
+                            action = Some(Action::Focus(index));
+                        }
+                    }
+                });
+
+            ui.separator();
+            ui.label("Contrôles intégrés :");
+            ui.label("Sans ces options, départ et sortie manquants sont de simples avertissements.");
+            ui.checkbox(&mut state.validation.require_spawn, "🟢 Exiger exactement un départ par niveau");
+            ui.checkbox(&mut state.validation.require_exit, "🔵 Exiger au moins une sortie par niveau");
+
+            ui.separator();
+            ui.label("📏 Règles du projet :");
+            for (index, rule) in state.validation.rules.iter_mut().enumerate() {
+                ui.push_id(index, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut rule.description).desired_width(140.0));
+                        draw_rule_tile(ui, rule.tile);
+                        if ui.small_button("🖌").on_hover_text("Utiliser le tile du pinceau").clicked() {
+                            action = Some(Action::UseBrush(index));
+                        }
+                        if ui.small_button("🗑").clicked() {
+                            action = Some(Action::RemoveRule(index));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("rule_layer")
+                            .width(110.0)
+                            .selected_text(rule.layer.as_deref().unwrap_or("Tous les calques"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut rule.layer, None, "Tous les calques");
+                                for name in &layer_names {
+                                    ui.selectable_value(&mut rule.layer, Some(name.clone()), name);
+                                }
+                            });
+                        ui.label("min");
+                        ui.add(egui::DragValue::new(&mut rule.min).range(0..=9999));
+                        let mut bounded = rule.max.is_some();
+                        if ui.checkbox(&mut bounded, "max").changed() {
+                            rule.max = bounded.then_some(rule.min);
+                        }
+                        if let Some(max) = &mut rule.max {
+                            ui.add(egui::DragValue::new(max).range(rule.min..=9999));
+                        }
+                        egui::ComboBox::from_id_salt("rule_severity")
+                            .width(90.0)
+                            .selected_text(rule.severity.name())
+                            .show_ui(ui, |ui| {
+                                for severity in [Severity::Error, Severity::Warning] {
+                                    ui.selectable_value(&mut rule.severity, severity, severity.name());
+                                }
+                            });
+                    });
+                    let mut this_level = rule.levels == [current_level_name.clone()];
+                    if ui.checkbox(&mut this_level, format!("Seulement le niveau '{}'", current_level_name)).changed() {
+                        rule.levels = if this_level { vec![current_level_name.clone()] } else { Vec::new() };
+                    }
+                });
+                ui.add_space(4.0);
+            }
+            if ui.button("➕ Règle avec le tile du pinceau").clicked() {
+                action = Some(Action::AddRule);
+            }
+
+            ui.separator();
+            ui.label("🧭 Parcours du départ à la sortie :");
+            let reach = &mut state.reach_settings;
+            ui.horizontal(|ui| {
+                ui.label("Murs :");
+                egui::ComboBox::from_id_salt("reach_walls")
+                    .selected_text(reach.wall_layer.as_str())
+                    .show_ui(ui, |ui| {
+                        for name in &layer_names {
+                            ui.selectable_value(&mut reach.wall_layer, name.clone(), name);
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                let platformer = matches!(reach.movement, Movement::Platformer { .. });
+                if ui.radio(!platformer, "🚶 Marche (4 directions)").clicked() {
+                    reach.movement = Movement::Walk;
+                }
+                if ui.radio(platformer, "🦘 Plateforme (gravité)").clicked() && !platformer {
+                    reach.movement = ReachabilitySettings::default().movement;
+                }
+            });
+            if let Movement::Platformer { jump_height, jump_length } = &mut reach.movement {
+                ui.horizontal(|ui| {
+                    ui.label("Saut : hauteur");
+                    ui.add(egui::DragValue::new(jump_height).range(0..=64));
+                    ui.label("longueur");
+                    ui.add(egui::DragValue::new(jump_length).range(0..=64));
+                });
+            }
+            let mut included = state.validation.reachability.is_some();
+            ui.checkbox(&mut included, "Inclure dans la vérification du projet");
+            state.validation.reachability = included.then(|| state.reach_settings.clone());
+
+            ui.horizontal(|ui| {
+                if ui.button("▶ Analyser ce niveau").clicked() {
+                    action = Some(Action::Analyze);
+                }
+                if state.reach_result.is_some() && ui.button("✖ Masquer l'aperçu").clicked() {
+                    action = Some(Action::ClearAnalysis);
+                }
+            });
+            if let Some(result) = &state.reach_result {
+                ui.label(format!(
+                    "{} case(s) accessible(s), sortie {}",
+                    result.reachable.len(),
+                    if result.exit_reached() { "atteinte ✅" } else { "inaccessible ❌" }
+                ));
+                for &(x, y) in &result.unreachable_coins {
+                    if ui.selectable_label(false, format!("🪙 Pièce inaccessible ({}, {})", x, y)).clicked() {
+                        action = Some(Action::CenterOn((x, y)));
+                    }
+                }
+            }
+        });
+
+    if !open {
+        state.show_problems = false;
+        state.focused_problem = None;
+    }
+    match action {
+        Some(Action::Validate) => state.run_validation(),
+        Some(Action::Focus(index)) => state.focus_problem(index),
+        Some(Action::AddRule) => {
+            let layer = state.level.layers.get(state.current_layer).map(|layer| layer.name.clone());
+            state.validation.rules.push(ValidationRule::exactly("Nouvelle règle", layer, state.brush_tile(), 1));
+        }
+        Some(Action::UseBrush(index)) => {
+            let tile = state.brush_tile();
+            state.validation.rules[index].tile = tile;
+        }
+        Some(Action::RemoveRule(index)) => {
+            state.validation.rules.remove(index);
+        }
+        Some(Action::Analyze) => state.run_reachability(),
+        Some(Action::ClearAnalysis) => state.reach_result = None,
+        Some(Action::CenterOn(cell)) => state.center_on(cell),
+        None => {}
+    }
+}
+
 pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
     // Mettre à jour les notifications
     state.update_notification(ctx.input(|i| i.stable_dt));
     
+    // Fenêtre de relocalisation des tilesets introuvables
+    if state.show_relocate_dialog {
+        draw_relocate_dialog(ctx, state);
+    }
+
+    // Fenêtre de retrait / remplacement d'un tileset
+    draw_tileset_dialog(ctx, state);
+
+    // Fenêtre de redimensionnement du niveau
+    draw_resize_dialog(ctx, state);
+
+    // Panneau des problèmes et règles de validation
+    if state.show_problems {
+        draw_problems_panel(ctx, state);
+    }
+    
     // Fenêtre de configuration des calques
     if state.show_layer_config {
         egui::Window::new("⚙️ Configuration des Calques")
@@ -649,25 +2062,86 @@ pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
                 
                 // Liste des calques avec renommage
                 egui::ScrollArea::vertical()
-                    .id_source("layer_config_scroll")
-                    .max_height(200.0)
+                    .id_salt("layer_config_scroll")
+                    .max_height(320.0)
                     .show(ui, |ui| {
+                        let mut renamed = None;
+                        let groups: Vec<(u32, String)> = state
+                            .level
+                            .group_tree()
+                            .into_iter()
+                            .map(|(group, _)| (group.id, state.level.group_path(Some(group.id))))
+                            .collect();
                         for (idx, layer) in state.level.layers.iter_mut().enumerate() {
                             ui.horizontal(|ui| {
                                 ui.label(format!("{}.", idx + 1));
                                 
                                 let mut name = layer.name.clone();
                                 if ui.text_edit_singleline(&mut name).changed() {
-                                    layer.name = name;
+                                    renamed = Some((idx, name));
                                 }
                                 
                                 ui.checkbox(&mut layer.visible, "👁");
+                                ui.checkbox(&mut layer.locked, "🔒");
+                                
+                                // Groupe du calque
+                                let group_name = |id: Option<u32>| {
+                                    groups
+                                        .iter()
+                                        .find(|(g, _)| Some(*g) == id)
+                                        .map_or("Aucun groupe".to_string(), |(_, path)| format!("📁 {}", path))
+                                };
+                                egui::ComboBox::from_id_salt(("layer_group", idx))
+                                    .selected_text(group_name(layer.group))
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut layer.group, None, group_name(None));
+                                        for (id, _) in &groups {
+                                            ui.selectable_value(&mut layer.group, Some(*id), group_name(Some(*id)));
+                                        }
+                                    });
+                                
+                                let mut compact = layer.encoding == TileEncoding::Rows;
+                                if ui.checkbox(&mut compact, "🗜")
+                                    .on_hover_text("Encodage compact (rangées RLE) à la sauvegarde")
+                                    .changed()
+                                {
+                                    layer.encoding = if compact { TileEncoding::Rows } else { TileEncoding::Map };
+                                }
                                 
-                                ui.label(format!("({} tiles)", layer.tiles.len()));
+                                match layer.kind {
+                                    LayerKind::Objects => ui.label(format!("({} objets)", layer.objects.len())),
+                                    LayerKind::Logic => ui.label(format!("(🧱 {} cases)", layer.tiles.len())),
+                                    LayerKind::Tiles => ui.label(format!("({} tiles)", layer.tiles.len())),
+                                };
+                            });
+                            
+                            // Rendu : opacité, teinte, décalage (pixels) et parallaxe
+                            ui.indent(("layer_render", idx), |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Opacité");
+                                    ui.add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0));
+                                    ui.label("Teinte");
+                                    ui.color_edit_button_srgb(&mut layer.tint);
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Décalage");
+                                    ui.add(egui::DragValue::new(&mut layer.offset.0).prefix("x: "));
+                                    ui.add(egui::DragValue::new(&mut layer.offset.1).prefix("y: "));
+                                    ui.label("Parallaxe");
+                                    ui.add(egui::DragValue::new(&mut layer.parallax.0).speed(0.01).prefix("x: "));
+                                    ui.add(egui::DragValue::new(&mut layer.parallax.1).speed(0.01).prefix("y: "));
+                                });
                             });
                         }
+                        if let Some((idx, name)) = renamed {
+                            state.rename_layer(idx, name);
+                        }
                     });
                 
+                ui.add_space(10.0);
+                ui.separator();
+                draw_layer_groups(ui, state);
+                
                 ui.add_space(10.0);
                 ui.separator();
                 ui.heading("Actions rapides");
@@ -675,13 +2149,25 @@ pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
                 ui.horizontal(|ui| {
                     if ui.button("➕ Ajouter calque").clicked() {
                         let count = state.level.layers.len();
-                        state.level.add_layer(format!("Layer {}", count + 1));
+                        state.add_layer(format!("Layer {}", count + 1));
                     }
                     
                     if ui.button("🗑️ Tout effacer").clicked() {
-                        for layer in &mut state.level.layers {
-                            layer.clear();
-                        }
+                        // Les calques verrouillés gardent leurs tiles
+                        let layers = state
+                            .level
+                            .layers
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, layer)| {
+                                if state.level.is_layer_locked(idx) {
+                                    layer.clone()
+                                } else {
+                                    crate::level::Layer { tiles: Default::default(), ..layer.clone() }
+                                }
+                            })
+                            .collect();
+                        state.replace_layers(layers, state.current_layer);
                     }
                 });
                 
@@ -689,34 +2175,35 @@ pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
                 ui.separator();
                 ui.heading("Presets de calques");
                 
+                let preset = |names: &[&str]| -> Vec<crate::level::Layer> {
+                    names.iter().map(|name| crate::level::Layer::new(name.to_string())).collect()
+                };
+                
                 if ui.button("🎨 Setup RPG Standard (5 calques)").clicked() {
-                    state.level.layers.clear();
-                    state.level.add_layer("Fond lointain".to_string());
-                    state.level.add_layer("Arrière-plan".to_string());
-                    state.level.add_layer("Gameplay".to_string());
-                    state.level.add_layer("Décorations".to_string());
-                    state.level.add_layer("Premier plan".to_string());
-                    state.current_layer = 2; // Gameplay par défaut
+                    let layers = preset(&["Fond lointain", "Arrière-plan", "Gameplay", "Décorations", "Premier plan"]);
+                    state.replace_layers(layers, 2); // Gameplay par défaut
                 }
                 
                 if ui.button("🏗️ Setup Parallax (7 calques)").clicked() {
-                    state.level.layers.clear();
-                    state.level.add_layer("Ciel".to_string());
-                    state.level.add_layer("Montagnes".to_string());
-                    state.level.add_layer("Arbres lointains".to_string());
-                    state.level.add_layer("Terrain".to_string());
-                    state.level.add_layer("Objets".to_string());
-                    state.level.add_layer("Arbres proches".to_string());
-                    state.level.add_layer("UI/Overlay".to_string());
-                    state.current_layer = 3; // Terrain par défaut
+                    let mut layers = preset(&[
+                        "Ciel",
+                        "Montagnes",
+                        "Arbres lointains",
+                        "Terrain",
+                        "Objets",
+                        "Arbres proches",
+                        "UI/Overlay",
+                    ]);
+                    // Du plus lointain (lent) au premier plan (rapide) ; l'interface reste fixe
+                    for (layer, factor) in layers.iter_mut().zip([0.0, 0.2, 0.4, 1.0, 1.0, 1.5, 0.0]) {
+                        layer.parallax = (factor, factor);
+                    }
+                    state.replace_layers(layers, 3); // Terrain par défaut
                 }
                 
                 if ui.button("🎮 Setup Minimal (3 calques)").clicked() {
-                    state.level.layers.clear();
-                    state.level.add_layer("Background".to_string());
-                    state.level.add_layer("Main".to_string());
-                    state.level.add_layer("Foreground".to_string());
-                    state.current_layer = 1;
+                    let layers = preset(&["Background", "Main", "Foreground"]);
+                    state.replace_layers(layers, 1);
                 }
                 
                 ui.add_space(10.0);
@@ -741,6 +2228,19 @@ pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
     }
     
     egui::CentralPanel::default().show(ctx, |ui| {
+        // Carte du monde à la place du niveau courant
+        if state.world_view.is_some() {
+            ui.horizontal(|ui| {
+                ui.label(format!("🌍 Carte du monde : {} niveau(x)", state.level_count()));
+                if ui.button("✏️ Éditer le niveau courant").clicked() {
+                    state.world_view = None;
+                }
+            });
+            ui.separator();
+            state.draw_world_view(ui);
+            return;
+        }
+
         // Panneau des calques en haut avec contrôles
         ui.horizontal(|ui| {
             ui.label("📑 Calques:");
@@ -753,14 +2253,29 @@ pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
             let mut move_up = None;
             let mut move_down = None;
             let mut toggle_visibility = Vec::new();
+            let mut toggle_lock = Vec::new();
+            let mut toggle_solo = Vec::new();
             
             for (idx, layer) in state.level.layers.iter().enumerate() {
                 ui.group(|ui| {
                     ui.horizontal(|ui| {
                         let is_current = idx == current_layer;
                         
+                        // Chemin du groupe, et calque grisé si un groupe le masque
+                        let group_path = state.level.group_path(layer.group);
+                        if !group_path.is_empty() {
+                            ui.weak(format!("📁 {} /", group_path));
+                        }
+                        let mut label = match layer.kind {
+                            LayerKind::Objects => egui::RichText::new(format!("🎯 {}", layer.name)),
+                            LayerKind::Logic => egui::RichText::new(format!("🧱 {}", layer.name)),
+                            LayerKind::Tiles => egui::RichText::new(&layer.name),
+                        };
+                        if !state.is_layer_shown(idx) {
+                            label = label.weak();
+                        }
                         if ui
-                            .selectable_label(is_current, &layer.name)
+                            .selectable_label(is_current, label)
                             .on_hover_text("Cliquer pour sélectionner ce calque")
                             .clicked()
                         {
@@ -775,6 +2290,21 @@ pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
                             toggle_visibility.push((idx, visible));
                         }
                         
+                        let lock_icon = if state.level.is_layer_locked(idx) { "🔒" } else { "🔓" };
+                        if ui.selectable_label(layer.locked, lock_icon)
+                            .on_hover_text("Verrouiller le calque (ou l'un de ses groupes l'est)")
+                            .clicked()
+                        {
+                            toggle_lock.push(idx);
+                        }
+                        
+                        if ui.selectable_label(layer.solo, "🎧")
+                            .on_hover_text("Solo : n'afficher que les calques solo")
+                            .clicked()
+                        {
+                            toggle_solo.push(idx);
+                        }
+                        
                         // Boutons de réorganisation
                         if is_current {
                             if ui.small_button("⬆").on_hover_text("Déplacer vers le haut").clicked() {
@@ -791,12 +2321,14 @@ pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
             
             // Bouton pour ajouter un calque rapidement
             if ui.button("➕").on_hover_text("Ajouter un nouveau calque").clicked() {
-                state.level.add_layer(format!("Layer {}", layer_count + 1));
-                state.current_layer = layer_count;
+                state.add_layer(format!("Layer {}", layer_count + 1));
             }
             
             // Appliquer les actions après l'itération
             if let Some(idx) = new_current_layer {
+                if idx != state.current_layer {
+                    state.selected_object = None;
+                }
                 state.current_layer = idx;
             }
             
@@ -806,17 +2338,25 @@ pub fn draw_central_panel(ctx: &egui::Context, state: &mut EditorState) {
                 }
             }
             
-            if let Some(idx) = move_up {
-                if state.level.move_layer_up(idx) {
-                    state.current_layer = idx.saturating_sub(1);
+            for idx in toggle_lock {
+                if let Some(layer) = state.level.layers.get_mut(idx) {
+                    layer.locked = !layer.locked;
                 }
             }
             
-            if let Some(idx) = move_down {
-                if state.level.move_layer_down(idx) {
-                    state.current_layer = (idx + 1).min(state.level.layers.len() - 1);
+            for idx in toggle_solo {
+                if let Some(layer) = state.level.layers.get_mut(idx) {
+                    layer.solo = !layer.solo;
                 }
             }
+            
+            if let Some(idx) = move_up {
+                state.move_layer(idx, true);
+            }
+            
+            if let Some(idx) = move_down {
+                state.move_layer(idx, false);
+            }
         });
 
         ui.separator();
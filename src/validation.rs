@@ -0,0 +1,542 @@
+//! Vérifications d'un projet avant sa livraison
+//!
+//! Les contrôles intégrés (départ unique, sortie présente, tiles hors des
+//! limites, références de tilesets invalides...) s'ajoutent aux règles propres
+//! au projet, enregistrées dans le .editorproj (« exactement un tile de telle
+//! couleur dans tel calque »). L'éditeur affiche le résultat dans le panneau
+//! des problèmes ; la commande `validate` l'affiche sans interface.
+
+use crate::level::{LayerKind, Level, TileData, TileType};
+use crate::project::Project;
+use crate::reachability::{self, ReachabilityError, ReachabilitySettings};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+/// Gravité d'un problème : une erreur rend le niveau injouable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "erreur",
+            Severity::Warning => "avertissement",
+        }
+    }
+}
+
+/// Problème détecté par la validation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub severity: Severity,
+    pub level: Option<usize>,  // Index du niveau dans le projet (None : projet entier)
+    pub layer: Option<usize>,  // Index du calque concerné dans le niveau
+    pub cells: Vec<(i32, i32)>,  // Cases en cause, triées ligne par ligne (peut être vide)
+    pub message: String,
+}
+
+impl Problem {
+    fn new(severity: Severity, message: String) -> Self {
+        Self { severity, level: None, layer: None, cells: Vec::new(), message }
+    }
+
+    fn in_layer(mut self, layer: usize) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    fn at(mut self, cells: impl IntoIterator<Item = (i32, i32)>) -> Self {
+        self.cells = cells.into_iter().collect();
+        self.cells.sort_by_key(|&(x, y)| (y, x));
+        self
+    }
+}
+
+/// Règle du projet : nombre de tiles identiques attendu dans un calque
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationRule {
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,  // Nom du calque (None : tous les calques)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub levels: Vec<String>,  // Niveaux concernés (vide : tous)
+    pub tile: TileData,  // Les transformations d'un tile texturé sont ignorées
+    #[serde(default)]
+    pub min: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+    pub severity: Severity,
+}
+
+impl ValidationRule {
+    /// Exactement `count` tiles `tile` dans le calque `layer`
+    pub fn exactly(description: impl Into<String>, layer: Option<String>, tile: TileData, count: u32) -> Self {
+        Self {
+            description: description.into(),
+            layer,
+            levels: Vec::new(),
+            tile,
+            min: count,
+            max: Some(count),
+            severity: Severity::Error,
+        }
+    }
+
+    pub fn applies_to(&self, level: &Level) -> bool {
+        self.levels.is_empty() || self.levels.contains(&level.name)
+    }
+
+    pub fn matches(&self, tile: &TileData) -> bool {
+        match (&self.tile, tile) {
+            (
+                TileData::Texture { tileset_id, tile_index, .. },
+                TileData::Texture { tileset_id: other_id, tile_index: other_index, .. },
+            ) => tileset_id == other_id && tile_index == other_index,
+            (expected, tile) => expected == tile,
+        }
+    }
+
+    /// Nombre attendu, pour les messages (« 1 », « au moins 2 », « 0 à 3 »)
+    pub fn expected(&self) -> String {
+        match self.max {
+            Some(max) if max == self.min => max.to_string(),
+            Some(max) => format!("{} à {}", self.min, max),
+            None => format!("au moins {}", self.min),
+        }
+    }
+}
+
+/// Réglages de validation enregistrés dans le projet
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidationSettings {
+    // Départ unique et sortie présente : simples avertissements tant que le
+    // projet ne les exige pas (les anciens projets restent valides)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub require_spawn: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub require_exit: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ValidationRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reachability: Option<ReachabilitySettings>,  // Sortie accessible et pièces atteignables
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl ValidationSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Project {
+    /// Nombre de tiles de chaque tileset du projet, par identifiant
+    pub fn tile_counts(&self) -> HashMap<usize, u32> {
+        self.tilesets.iter().map(|t| (t.id, t.columns * t.rows)).collect()
+    }
+
+    /// Vérifie tous les niveaux du projet ; les erreurs viennent en premier
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        let mut ids = HashSet::new();
+        for tileset in &self.tilesets {
+            if !ids.insert(tileset.id) {
+                problems.push(Problem::new(
+                    Severity::Error,
+                    format!("tileset '{}' : ID {} déjà utilisé par un autre tileset", tileset.name, tileset.id),
+                ));
+            }
+            if !Path::new(&tileset.path).exists() {
+                problems.push(Problem::new(
+                    Severity::Warning,
+                    format!("tileset '{}' introuvable : {}", tileset.name, tileset.path),
+                ));
+            }
+        }
+
+        let mut names = HashSet::new();
+        for level in &self.levels {
+            if !names.insert(level.name.as_str()) {
+                problems.push(Problem::new(
+                    Severity::Warning,
+                    format!("plusieurs niveaux s'appellent '{}'", level.name),
+                ));
+            }
+        }
+
+        let tile_counts = self.tile_counts();
+        for (index, level) in self.levels.iter().enumerate() {
+            for mut problem in validate_level(level, &tile_counts, &self.validation) {
+                problem.level = Some(index);
+                problems.push(problem);
+            }
+        }
+
+        problems.sort_by_key(|problem| problem.severity);
+        problems
+    }
+}
+
+/// Vérifie un niveau. `tile_counts` donne le nombre de tiles de chaque tileset
+/// connu ; vide (niveau .json seul), les références de tilesets ne sont pas vérifiées.
+pub fn validate_level(
+    level: &Level,
+    tile_counts: &HashMap<usize, u32>,
+    settings: &ValidationSettings,
+) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let error = |message: String| Problem::new(Severity::Error, message);
+    let warning = |message: String| Problem::new(Severity::Warning, message);
+
+    if level.width == 0 || level.height == 0 || level.tile_size == 0 {
+        problems.push(error(format!(
+            "dimensions invalides : {}x{} (tile {}px)",
+            level.width, level.height, level.tile_size
+        )));
+    }
+    if level.layers.is_empty() {
+        problems.push(error("aucun calque".to_string()));
+    }
+
+    for group in &level.groups {
+        if let Some(parent) = group.parent.filter(|&parent| level.group(parent).is_none()) {
+            problems.push(warning(format!("groupe '{}' : groupe parent {} absent", group.name, parent)));
+        }
+    }
+
+    let in_bounds = |x: i32, y: i32| x >= 0 && y >= 0 && x < level.width as i32 && y < level.height as i32;
+
+    for (index, layer) in level.layers.iter().enumerate() {
+        if let Some(group) = layer.group.filter(|&group| level.group(group).is_none()) {
+            problems.push(warning(format!("calque '{}' : groupe {} absent", layer.name, group)).in_layer(index));
+        }
+
+        let mut out_of_bounds = Vec::new();
+        let mut missing_tileset: BTreeMap<usize, Vec<(i32, i32)>> = BTreeMap::new();
+        let mut bad_index = Vec::new();
+        for (&(x, y), tile) in &layer.tiles {
+            if !in_bounds(x, y) {
+                out_of_bounds.push((x, y));
+            }
+            if let TileData::Texture { tileset_id, tile_index, .. } = tile {
+                match tile_counts.get(tileset_id) {
+                    Some(&count) if *tile_index >= count => bad_index.push((x, y)),
+                    Some(_) => {}
+                    // Un niveau .json seul ne référence pas ses tilesets
+                    None if tile_counts.is_empty() => {}
+                    None => missing_tileset.entry(*tileset_id).or_default().push((x, y)),
+                }
+            }
+        }
+        if !out_of_bounds.is_empty() {
+            problems.push(
+                warning(format!("calque '{}' : {} tiles hors des limites", layer.name, out_of_bounds.len()))
+                    .in_layer(index)
+                    .at(out_of_bounds),
+            );
+        }
+        for (tileset_id, cells) in missing_tileset {
+            problems.push(
+                error(format!(
+                    "calque '{}' : {} tiles référencent le tileset {} absent du projet",
+                    layer.name,
+                    cells.len(),
+                    tileset_id
+                ))
+                .in_layer(index)
+                .at(cells),
+            );
+        }
+        if !bad_index.is_empty() {
+            problems.push(
+                error(format!(
+                    "calque '{}' : {} tiles hors de leur tileset (tile_index trop grand)",
+                    layer.name,
+                    bad_index.len()
+                ))
+                .in_layer(index)
+                .at(bad_index),
+            );
+        }
+        if layer.kind == LayerKind::Objects && !layer.tiles.is_empty() {
+            problems.push(
+                warning(format!("calque d'objets '{}' : contient aussi des tiles", layer.name))
+                    .in_layer(index)
+                    .at(layer.tiles.keys().copied()),
+            );
+        }
+
+        // Valeurs de collision : seulement dans un calque de collision, et définies
+        let values: Vec<((i32, i32), u8)> = layer
+            .tiles
+            .iter()
+            .filter_map(|(&pos, tile)| match tile {
+                TileData::Value(value) => Some((pos, *value)),
+                _ => None,
+            })
+            .collect();
+        if layer.kind == LayerKind::Logic {
+            let undefined: Vec<((i32, i32), u8)> =
+                values.into_iter().filter(|&(_, value)| layer.logic_value(value).is_none()).collect();
+            if !undefined.is_empty() {
+                let names: BTreeSet<u8> = undefined.iter().map(|&(_, value)| value).collect();
+                problems.push(
+                    warning(format!("calque de collision '{}' : valeurs sans définition {:?}", layer.name, names))
+                        .in_layer(index)
+                        .at(undefined.into_iter().map(|(pos, _)| pos)),
+                );
+            }
+            let visuals: Vec<(i32, i32)> = layer
+                .tiles
+                .iter()
+                .filter(|(_, tile)| !matches!(tile, TileData::Value(_)))
+                .map(|(&pos, _)| pos)
+                .collect();
+            if !visuals.is_empty() {
+                problems.push(
+                    warning(format!("calque de collision '{}' : contient {} tiles visuels", layer.name, visuals.len()))
+                        .in_layer(index)
+                        .at(visuals),
+                );
+            }
+        } else if !values.is_empty() {
+            problems.push(
+                warning(format!("calque '{}' : contient des valeurs de collision", layer.name))
+                    .in_layer(index)
+                    .at(values.into_iter().map(|(pos, _)| pos)),
+            );
+        }
+    }
+
+    let spawn_severity = if settings.require_spawn { Severity::Error } else { Severity::Warning };
+    let spawns = markers(level, TileType::Spawn, "spawn");
+    match spawns.len() {
+        1 => {}
+        0 => problems.push(Problem::new(spawn_severity, "aucun départ (tile Départ ou objet 'spawn')".to_string())),
+        count => problems.push(
+            Problem::new(spawn_severity, format!("{} départs au lieu d'un seul", count))
+                .in_layer(spawns[0].0)
+                .at(spawns.into_iter().map(|(_, pos)| pos)),
+        ),
+    }
+    if markers(level, TileType::Exit, "exit").is_empty() {
+        let exit_severity = if settings.require_exit { Severity::Error } else { Severity::Warning };
+        problems.push(Problem::new(exit_severity, "aucune sortie (tile Sortie ou objet 'exit')".to_string()));
+    }
+
+    // Le départ et la sortie manquants sont déjà signalés plus haut
+    if let Some(reach) = &settings.reachability {
+        match reachability::analyze(level, reach) {
+            Ok(result) => {
+                if !result.exits.is_empty() && !result.exit_reached() {
+                    problems.push(error("sortie inaccessible depuis le départ".to_string()).at(result.exits));
+                }
+                if !result.unreachable_coins.is_empty() {
+                    problems.push(
+                        warning(format!("{} pièce(s) inaccessible(s)", result.unreachable_coins.len()))
+                            .at(result.unreachable_coins),
+                    );
+                }
+            }
+            Err(e @ ReachabilityError::MissingWallLayer(_)) => {
+                problems.push(warning(format!("analyse de parcours : {}", e)));
+            }
+            Err(ReachabilityError::NoSpawn) => {}
+        }
+    }
+
+    for rule in settings.rules.iter().filter(|rule| rule.applies_to(level)) {
+        let layers: Vec<usize> = match &rule.layer {
+            Some(name) => level.layers.iter().position(|l| &l.name == name).into_iter().collect(),
+            None => (0..level.layers.len()).collect(),
+        };
+        if let (Some(name), true) = (&rule.layer, layers.is_empty()) {
+            if rule.min > 0 {
+                problems.push(Problem::new(
+                    rule.severity,
+                    format!("règle '{}' : calque '{}' absent", rule.description, name),
+                ));
+            }
+            continue;
+        }
+
+        let cells: Vec<(i32, i32)> = layers
+            .iter()
+            .flat_map(|&index| &level.layers[index].tiles)
+            .filter(|(_, tile)| rule.matches(tile))
+            .map(|(&pos, _)| pos)
+            .collect();
+        let count = cells.len() as u32;
+        if count < rule.min || rule.max.is_some_and(|max| count > max) {
+            let mut problem = Problem::new(
+                rule.severity,
+                format!("règle '{}' : {} tiles trouvés, {} attendus", rule.description, count, rule.expected()),
+            )
+            .at(cells);
+            if let [index] = layers[..] {
+                problem = problem.in_layer(index);
+            }
+            problems.push(problem);
+        }
+    }
+
+    problems
+}
+
+/// Départs, sorties ou pièces d'un niveau : tiles de la couleur du type et objets
+/// du type donné, avec l'index de leur calque et leur case
+pub(crate) fn markers(level: &Level, tile_type: TileType, object_type: &str) -> Vec<(usize, (i32, i32))> {
+    let color = TileData::Color(tile_type.color());
+    let tile_size = level.tile_size.max(1) as f32;
+    let mut found: Vec<(usize, (i32, i32))> = Vec::new();
+    for (index, layer) in level.layers.iter().enumerate() {
+        if layer.kind == LayerKind::Logic {
+            continue;
+        }
+        found.extend(layer.tiles.iter().filter(|(_, tile)| **tile == color).map(|(&pos, _)| (index, pos)));
+        found.extend(
+            layer
+                .objects
+                .iter()
+                .filter(|object| object.object_type == object_type)
+                .map(|object| (index, ((object.x / tile_size).floor() as i32, (object.y / tile_size).floor() as i32))),
+        );
+    }
+    found.sort_by_key(|&(index, (x, y))| (y, x, index));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(tile_type: TileType) -> TileData {
+        TileData::Color(tile_type.color())
+    }
+
+    /// Niveau 8x4 avec un départ et une sortie dans le calque "Main"
+    fn level() -> Level {
+        let mut level = Level::new("Niveau 1".to_string(), 8, 4, 16);
+        level.layers[1].set_tile(0, 2, color(TileType::Spawn));
+        level.layers[1].set_tile(7, 2, color(TileType::Exit));
+        level
+    }
+
+    fn check(level: &Level, settings: &ValidationSettings) -> Vec<Problem> {
+        validate_level(level, &HashMap::new(), settings)
+    }
+
+    #[test]
+    fn valid_level_has_no_problem() {
+        assert_eq!(check(&level(), &ValidationSettings::default()), Vec::new());
+    }
+
+    #[test]
+    fn spawn_and_exit_are_errors_only_when_required() {
+        let empty = Level::new("Vide".to_string(), 8, 4, 16);
+        let problems = check(&empty, &ValidationSettings::default());
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().all(|p| p.severity == Severity::Warning));
+
+        let required = ValidationSettings { require_spawn: true, require_exit: true, ..Default::default() };
+        let problems = check(&empty, &required);
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().all(|p| p.severity == Severity::Error));
+    }
+
+    #[test]
+    fn several_spawns_are_located() {
+        let mut level = level();
+        level.layers[1].set_tile(3, 1, color(TileType::Spawn));
+        let problems = check(&level, &ValidationSettings::default());
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].layer, Some(1));
+        assert_eq!(problems[0].cells, vec![(3, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn tiles_out_of_bounds_and_bad_references() {
+        let mut level = level();
+        level.layers[0].set_tile(8, 0, TileData::texture(0, 1));
+        level.layers[0].set_tile(1, 0, TileData::texture(0, 4));
+        level.layers[0].set_tile(2, 0, TileData::texture(9, 0));
+        let problems = validate_level(&level, &HashMap::from([(0, 4)]), &ValidationSettings::default());
+        let summary: Vec<(Severity, Vec<(i32, i32)>)> = problems.into_iter().map(|p| (p.severity, p.cells)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Severity::Warning, vec![(8, 0)]),
+                (Severity::Error, vec![(2, 0)]),
+                (Severity::Error, vec![(1, 0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn rule_counts_tiles() {
+        let mut level = level();
+        let rule = ValidationRule::exactly("une pièce", Some("Main".to_string()), color(TileType::Coin), 1);
+        let settings = ValidationSettings { rules: vec![rule], ..Default::default() };
+
+        let problems = check(&level, &settings);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].message, "règle 'une pièce' : 0 tiles trouvés, 1 attendus");
+        assert_eq!(problems[0].layer, Some(1));
+
+        level.layers[1].set_tile(4, 2, color(TileType::Coin));
+        assert!(check(&level, &settings).is_empty());
+        level.layers[1].set_tile(5, 2, color(TileType::Coin));
+        assert_eq!(check(&level, &settings)[0].cells, vec![(4, 2), (5, 2)]);
+        // Une pièce d'un autre calque ne compte pas
+        level.layers[1].set_tile(5, 2, TileData::empty());
+        level.layers[2].set_tile(6, 2, color(TileType::Coin));
+        assert!(check(&level, &settings).is_empty());
+    }
+
+    #[test]
+    fn rule_scope_and_missing_layer() {
+        let level = level();
+        let mut rule = ValidationRule::exactly("clé", Some("Objets".to_string()), TileData::texture(0, 3), 1);
+        let settings = |rule: &ValidationRule| ValidationSettings { rules: vec![rule.clone()], ..Default::default() };
+        assert_eq!(check(&level, &settings(&rule))[0].message, "règle 'clé' : calque 'Objets' absent");
+
+        rule.levels = vec!["Niveau 2".to_string()];
+        assert!(check(&level, &settings(&rule)).is_empty());
+    }
+
+    #[test]
+    fn rule_ignores_tile_flags() {
+        let rule = ValidationRule::exactly("porte", None, TileData::texture(2, 5), 1);
+        assert!(rule.matches(&TileData::texture(2, 5).with_flags(|flags| flags.flipped_x())));
+        assert!(!rule.matches(&TileData::texture(2, 6)));
+        assert_eq!(rule.expected(), "1");
+        assert_eq!(ValidationRule { max: None, ..rule.clone() }.expected(), "au moins 1");
+        assert_eq!(ValidationRule { min: 0, max: Some(3), ..rule }.expected(), "0 à 3");
+    }
+
+    #[test]
+    fn unreachable_exit_is_an_error() {
+        let mut level = level();
+        for x in 0..8 {
+            level.layers[1].set_tile(x, 3, TileData::Color([90, 90, 90]));
+        }
+        for y in 0..3 {
+            level.layers[1].set_tile(4, y, TileData::Color([90, 90, 90]));
+        }
+        let settings = ValidationSettings { reachability: Some(ReachabilitySettings::default()), ..Default::default() };
+        let problems = check(&level, &settings);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].severity, Severity::Error);
+        assert_eq!(problems[0].cells, vec![(7, 2)]);
+
+        level.layers[1].set_tile(4, 0, TileData::empty());
+        assert!(check(&level, &settings).is_empty());
+    }
+}